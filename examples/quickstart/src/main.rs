@@ -19,5 +19,5 @@ async fn main() {
     client.register_slash_commands(vec![commands::echo()]).await;
     client.register_events(vec![events::ready()]);
 
-    client.login().await;
+    client.login().await.unwrap();
 }
@@ -27,6 +27,32 @@ impl VisitMut for UnwrapVisitor {
     }
 }
 
+/// Returns the statement extracting a `State<T>` parameter,
+/// or `None` if the parameter is not a state.
+fn state_param(param: &syn::FnArg) -> Option<proc_macro2::TokenStream> {
+    let syn::FnArg::Typed(param) = param else {
+        return None;
+    };
+
+    let syn::Type::Path(ref path) = *param.ty else {
+        return None;
+    };
+
+    if path.path.segments.last()?.ident != "State" {
+        return None;
+    }
+
+    let pat = &param.pat;
+    let ty = &param.ty;
+
+    Some(quote! {
+        let #pat: #ty = match descord::internals::State::get() {
+            Ok(state) => state,
+            Err(e) => return Err(Box::new(e) as Box<dyn std::error::Error + Send>),
+        };
+    })
+}
+
 macro_rules! event_handler_args {
     [ $($event_name:ident => $event_ty:ident:$arg_type:ident),* $(,)? ] => {
         #[allow(dead_code)]
@@ -106,17 +132,25 @@ macro_rules! type_name {
 }
 
 event_handler_args![
-//  event switch       => event type         : event data type
-    ready              => Ready              : ReadyData,
-    message_create     => MessageCreate      : Message,
-    message_delete     => MessageDelete      : Message,
-    message_delete_raw => MessageDeleteRaw   : DeletedMessage,
-    message_update     => MessageUpdate      : Message,
-    reaction_add       => MessageReactionAdd : Reaction,
-    guild_create       => GuildCreate        : GuildCreate,
-    interaction_create => InteractionCreate  : Interaction,
-    member_join        => GuildMemberAdd     : Member,
-    member_leave       => GuildMemberRemove  : MemberLeave,
+//  event switch          => event type          : event data type
+    ready                 => Ready               : ReadyData,
+    message_create        => MessageCreate       : Message,
+    message_delete        => MessageDelete       : Message,
+    message_delete_raw    => MessageDeleteRaw    : DeletedMessage,
+    message_delete_bulk   => MessageDeleteBulk   : BulkDeletedMessages,
+    message_update        => MessageUpdate       : Message,
+    reaction_add          => MessageReactionAdd  : Reaction,
+    guild_create          => GuildCreate         : GuildCreate,
+    interaction_create    => InteractionCreate   : Interaction,
+    member_join           => GuildMemberAdd      : Member,
+    member_leave          => GuildMemberRemove   : MemberLeave,
+    thread_create         => ThreadCreate        : Channel,
+    thread_update         => ThreadUpdate        : Channel,
+    thread_delete         => ThreadDelete        : Channel,
+    thread_list_sync      => ThreadListSync      : ThreadListSync,
+    thread_member_update  => ThreadMemberUpdate  : ThreadMember,
+    thread_members_update => ThreadMembersUpdate : ThreadMembersUpdate,
+    guild_members_chunk   => GuildMembersChunk   : GuildMembersChunk,
 ];
 
 #[derive(Debug, FromMeta)]
@@ -156,8 +190,13 @@ pub fn component(args: TokenStream, input: TokenStream) -> TokenStream {
         _ => panic!("self???"),
     };
 
+    let state_stmts = function.sig.inputs.iter().skip(1).map(|param| {
+        state_param(param).expect("Only `State<T>` is allowed after the `Interaction` parameter")
+    });
+
     let let_stmt = quote! {
         let #param_name = data;
+        #(#state_stmts)*
     };
 
     let expanded = quote! {
@@ -227,8 +266,16 @@ pub fn event(args: TokenStream, input: TokenStream) -> TokenStream {
         }
     };
 
-    if function.sig.inputs.len() != 1 {
-        panic!("Expected only one parameter");
+    let state_stmts: Vec<_> = function_params
+        .iter()
+        .skip(1)
+        .map(|param| {
+            state_param(param).expect("Only `State<T>` is allowed after the event parameter")
+        })
+        .collect();
+
+    if function_params.is_empty() {
+        panic!("Expected the event data as the first parameter");
     }
 
     // let (name, event_ty) = handler_args.get(&function_name.to_string(), &param_name);
@@ -265,6 +312,8 @@ pub fn event(args: TokenStream, input: TokenStream) -> TokenStream {
         let #name = data else {
             unreachable!()
         };
+
+        #(#state_stmts)*
     };
 
     let expanded = quote! {
@@ -283,7 +332,7 @@ pub fn event(args: TokenStream, input: TokenStream) -> TokenStream {
 
             internals::EventHandler {
                 event: #event_ty,
-                handler_fn: f,
+                handler_fn: std::sync::Arc::new(f),
             }
         }
     };
@@ -366,7 +415,13 @@ pub fn command(args: TokenStream, input: TokenStream) -> TokenStream {
     let mut optional_params = vec![];
 
     let mut stop = false;
-    for (idx, param) in function_params.iter().skip(1).enumerate() {
+    let mut idx = 0_usize;
+    for param in function_params.iter().skip(1) {
+        if let Some(stmt) = state_param(param) {
+            stmts.push(stmt);
+            continue;
+        }
+
         if stop {
             panic!("`Arg` should be the last parameter");
         }
@@ -436,6 +491,7 @@ pub fn command(args: TokenStream, input: TokenStream) -> TokenStream {
         stmts.push(quote! {
             let #name = args[#idx].clone() else { unreachable!() };
         });
+        idx += 1;
     }
 
     let mut let_stmts = proc_macro2::TokenStream::new();
@@ -565,7 +621,13 @@ pub fn slash(args: TokenStream, input: TokenStream) -> TokenStream {
     let mut stmts: Vec<proc_macro2::TokenStream> = vec![];
 
     let stop = false;
-    for (idx, param) in function_params.iter().skip(1).enumerate() {
+    let mut idx = 0_usize;
+    for param in function_params.iter().skip(1) {
+        if let Some(stmt) = state_param(param) {
+            stmts.push(stmt);
+            continue;
+        }
+
         if stop {
             panic!("`Arg` should be the last parameter");
         }
@@ -664,6 +726,7 @@ pub fn slash(args: TokenStream, input: TokenStream) -> TokenStream {
         stmts.push(quote! {
             let #name = args[#idx].clone() else { unreachable!() };
         });
+        idx += 1;
     }
 
     let mut let_stmts = proc_macro2::TokenStream::new();
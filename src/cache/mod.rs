@@ -3,30 +3,55 @@ use std::num::NonZeroUsize;
 use lru::LruCache;
 use tokio::sync::Mutex;
 
-use crate::consts::{
-    CHANNEL_CACHE_SIZE, GUILD_CACHE_SIZE, MESSAGE_CACHE_SIZE, RATE_LIMITS_CACHE_SIZE,
-    ROLE_CACHE_SIZE,
-};
+use crate::consts::{CHANNEL_CACHE_SIZE, GUILD_CACHE_SIZE, MESSAGE_CACHE_SIZE, ROLE_CACHE_SIZE};
 use crate::prelude::Role;
 use crate::prelude::{Channel, Guild, Message};
 
-#[derive(Debug)]
-pub struct RateLimitInfo {
-    pub remaining: u32,
-    pub reset: f64,
+/// Capacities of the caches of a client.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CacheSizes {
+    pub messages: usize,
+    pub roles: usize,
+    pub guilds: usize,
+    pub channels: usize,
 }
 
-lazy_static::lazy_static! {
-    pub(crate) static ref MESSAGE_CACHE: Mutex<LruCache<String, Message>>
-        = Mutex::new(LruCache::new(NonZeroUsize::new(MESSAGE_CACHE_SIZE).unwrap()));
-    pub(crate) static ref ROLE_CACHE: Mutex<LruCache<String, Role>>
-        = Mutex::new(LruCache::new(NonZeroUsize::new(ROLE_CACHE_SIZE).unwrap()));
-    pub(crate) static ref GUILD_CACHE: Mutex<LruCache<String, Guild>>
-        = Mutex::new(LruCache::new(NonZeroUsize::new(GUILD_CACHE_SIZE).unwrap()));
-    pub(crate) static ref RATE_LIMITS: Mutex<LruCache<String, RateLimitInfo>>
-        = Mutex::new(LruCache::new(NonZeroUsize::new(RATE_LIMITS_CACHE_SIZE).unwrap()));
-    pub(crate) static ref ENDPOINT_BUCKET_MAP: Mutex<LruCache<String, String>>
-        = Mutex::new(LruCache::new(NonZeroUsize::new(RATE_LIMITS_CACHE_SIZE).unwrap()));
-    pub(crate) static ref CHANNEL_CACHE: Mutex<LruCache<String, Channel>>
-        = Mutex::new(LruCache::new(NonZeroUsize::new(CHANNEL_CACHE_SIZE).unwrap()));
+impl Default for CacheSizes {
+    fn default() -> Self {
+        Self {
+            messages: MESSAGE_CACHE_SIZE,
+            roles: ROLE_CACHE_SIZE,
+            guilds: GUILD_CACHE_SIZE,
+            channels: CHANNEL_CACHE_SIZE,
+        }
+    }
+}
+
+/// The caches of a client, kept in its [`Http`](crate::Http) context.
+pub(crate) struct Cache {
+    pub messages: Mutex<LruCache<String, Message>>,
+    pub roles: Mutex<LruCache<String, Role>>,
+    pub guilds: Mutex<LruCache<String, Guild>>,
+    pub channels: Mutex<LruCache<String, Channel>>,
+}
+
+impl Cache {
+    pub(crate) fn new(sizes: CacheSizes) -> Self {
+        Self {
+            messages: lru(sizes.messages),
+            roles: lru(sizes.roles),
+            guilds: lru(sizes.guilds),
+            channels: lru(sizes.channels),
+        }
+    }
+}
+
+/// Creates a cache holding at most `size` entries.
+///
+/// It grows as entries are added, so clients don't allocate their whole capacity up front.
+fn lru<V>(size: usize) -> Mutex<LruCache<String, V>> {
+    let mut cache = LruCache::unbounded();
+    cache.resize(NonZeroUsize::new(size).unwrap_or(NonZeroUsize::MIN));
+
+    Mutex::new(cache)
 }
@@ -0,0 +1,433 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::consts;
+use crate::internals::DescordError;
+use crate::models::presence::Presence;
+use crate::utils::{RestObserver, RetryPolicy};
+use crate::ws::{self, payload::GatewayEncoding};
+
+use super::{Client, HttpOptions};
+
+/// The connection properties sent to discord when identifying.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdentifyProperties {
+    /// The operating system.
+    pub os: String,
+    /// The browser, bots send the name of their library.
+    pub browser: String,
+    /// The device, bots send the name of their library.
+    pub device: String,
+}
+
+impl Default for IdentifyProperties {
+    fn default() -> Self {
+        Self {
+            os: "linux".to_owned(),
+            browser: "descord".to_owned(),
+            device: "descord".to_owned(),
+        }
+    }
+}
+
+/// A builder for creating a configured [`Client`].
+pub struct ClientBuilder {
+    pub(super) token: String,
+    pub(super) intents: u32,
+    pub(super) prefix: String,
+    pub(super) api_url: String,
+    pub(super) http_options: HttpOptions,
+    pub(super) gateway_url: Option<String>,
+    pub(super) identify_properties: IdentifyProperties,
+    pub(super) reconnect_policy: ws::ReconnectPolicy,
+    pub(super) default_help: bool,
+    pub(super) shards: Option<ws::ShardCount>,
+    pub(super) compress: bool,
+    pub(super) encoding: GatewayEncoding,
+    pub(super) presence: Option<Presence>,
+    pub(super) shutdown_timeout: Duration,
+}
+
+impl ClientBuilder {
+    /// Creates a new `ClientBuilder`.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The bot token.
+    /// * `intents` - The gateway intents.
+    /// * `prefix` - The default prefix for message commands.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = ClientBuilder::new("TOKEN", GatewayIntent::NON_PRIVILEGED, "!");
+    /// ```
+    pub fn new(token: &str, intents: impl Into<u32>, prefix: &str) -> Self {
+        Self {
+            token: token.to_owned(),
+            intents: intents.into(),
+            prefix: prefix.to_owned(),
+            api_url: consts::API.to_owned(),
+            http_options: HttpOptions::default(),
+            gateway_url: None,
+            identify_properties: IdentifyProperties::default(),
+            reconnect_policy: ws::ReconnectPolicy::default(),
+            default_help: true,
+            shards: None,
+            compress: false,
+            encoding: GatewayEncoding::default(),
+            presence: None,
+            shutdown_timeout: consts::SHUTDOWN_TIMEOUT,
+        }
+    }
+
+    /// Builds the client.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DescordError::Http`] if the http client can't be built,
+    /// e.g. because the [`proxy`](Self::proxy) is invalid.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let client = ClientBuilder::new("TOKEN", GatewayIntent::NON_PRIVILEGED, "!")
+    ///     .default_help(false)
+    ///     .build()?;
+    /// ```
+    pub fn build(self) -> Result<Client, DescordError> {
+        Client::from_builder(self)
+    }
+
+    /// Sets the base url of the rest api.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The base url including the api version, `https://discord.com/api/v10` by default.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = builder.api_url("http://127.0.0.1:8080/api/v10");
+    /// ```
+    pub fn api_url(mut self, url: &str) -> Self {
+        self.api_url = url.trim_end_matches('/').to_owned();
+        self
+    }
+
+    /// Sets how long a rest request may take, including reading the response.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The timeout, 30 seconds by default.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = builder.request_timeout(Duration::from_secs(10));
+    /// ```
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.http_options.timeout = timeout;
+        self
+    }
+
+    /// Sets how long opening a connection to the rest api may take.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The timeout, 10 seconds by default.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.http_options.connect_timeout = timeout;
+        self
+    }
+
+    /// Sends the rest requests through a proxy.
+    ///
+    /// # Arguments
+    ///
+    /// * `proxy` - The proxy to use.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = builder.proxy(reqwest::Proxy::all("http://127.0.0.1:3128")?);
+    /// ```
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.http_options.proxy = Some(proxy);
+        self
+    }
+
+    /// Sets whether HTTP/2 is negotiated with the rest api.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to use HTTP/2 when available, enabled by default.
+    pub fn http2(mut self, enabled: bool) -> Self {
+        self.http_options.http2 = enabled;
+        self
+    }
+
+    /// Sets how rest requests are retried after server and transport errors.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The retry policy, `GET`, `PUT` and `DELETE` requests are retried 2 times by default.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = builder.retry_policy(RetryPolicy {
+    ///     max_attempts: 5,
+    ///     retry_non_idempotent: true,
+    ///     ..Default::default()
+    /// });
+    /// ```
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.http_options.retry_policy = policy;
+        self
+    }
+
+    /// Adds an observer notified of every rest request and rate limit delay.
+    ///
+    /// # Arguments
+    ///
+    /// * `observer` - The observer, see [`RestObserver`].
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = builder.rest_observer(Metrics::default());
+    /// ```
+    pub fn rest_observer(mut self, observer: impl RestObserver + 'static) -> Self {
+        self.http_options.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Sets the url of the gateway.
+    ///
+    /// This is used instead of the url returned by discord, including when sharding.
+    /// Urls sent in `READY` are still used to resume.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The base url of the gateway, without the query.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = builder.gateway_url("ws://127.0.0.1:8081");
+    /// ```
+    pub fn gateway_url(mut self, url: &str) -> Self {
+        self.gateway_url = Some(format!(
+            "{}{}",
+            url.trim_end_matches('/'),
+            consts::GATEWAY_QUERY
+        ));
+        self
+    }
+
+    /// Sets the capacity of the message cache of the client.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The number of messages to keep, 100,000 by default.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = builder.message_cache_size(1_000);
+    /// ```
+    pub fn message_cache_size(mut self, size: usize) -> Self {
+        self.http_options.cache_sizes.messages = size;
+        self
+    }
+
+    /// Sets the capacity of the role cache of the client.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The number of roles to keep, 100,000 by default.
+    pub fn role_cache_size(mut self, size: usize) -> Self {
+        self.http_options.cache_sizes.roles = size;
+        self
+    }
+
+    /// Sets the capacity of the guild cache of the client.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The number of guilds to keep, 30,000 by default.
+    pub fn guild_cache_size(mut self, size: usize) -> Self {
+        self.http_options.cache_sizes.guilds = size;
+        self
+    }
+
+    /// Sets the capacity of the channel cache of the client.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The number of channels to keep, 50,000 by default.
+    pub fn channel_cache_size(mut self, size: usize) -> Self {
+        self.http_options.cache_sizes.channels = size;
+        self
+    }
+
+    /// Sets how many rate limit buckets are remembered.
+    ///
+    /// # Arguments
+    ///
+    /// * `size` - The number of buckets to keep, 50,000 by default.
+    pub fn rate_limit_cache_size(mut self, size: usize) -> Self {
+        self.http_options.rate_limit_cache_size = size;
+        self
+    }
+
+    /// Sets the connection properties sent when identifying.
+    ///
+    /// # Arguments
+    ///
+    /// * `properties` - The os, browser and device of the connection.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = builder.identify_properties(IdentifyProperties {
+    ///     os: "windows".to_owned(),
+    ///     ..Default::default()
+    /// });
+    /// ```
+    pub fn identify_properties(mut self, properties: IdentifyProperties) -> Self {
+        self.identify_properties = properties;
+        self
+    }
+
+    /// Sets how the gateway reconnects after losing the connection.
+    ///
+    /// Failed attempts are reported to the error handler,
+    /// [`Client::login`] returns an error once the policy gives up.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The reconnect policy.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = builder.reconnect_policy(ReconnectPolicy {
+    ///     max_attempts: Some(10),
+    ///     ..Default::default()
+    /// });
+    /// ```
+    pub fn reconnect_policy(mut self, policy: ws::ReconnectPolicy) -> Self {
+        self.reconnect_policy = policy;
+        self
+    }
+
+    /// Sets whether the default help command is registered on login.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - Whether to add the help command, enabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = builder.default_help(false);
+    /// ```
+    pub fn default_help(mut self, enabled: bool) -> Self {
+        self.default_help = enabled;
+        self
+    }
+
+    /// Splits the gateway connection into multiple shards.
+    ///
+    /// Every shard dispatches into the same handlers, use
+    /// [`current_shard`](crate::current_shard) inside a handler
+    /// to know which shard received the event.
+    ///
+    /// # Arguments
+    ///
+    /// * `shards` - The number of shards, `ShardCount::Auto` uses the count recommended by discord.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = builder.shards(ShardCount::Auto);
+    /// ```
+    pub fn shards(mut self, shards: ws::ShardCount) -> Self {
+        self.shards = Some(shards);
+        self
+    }
+
+    /// Enables `zlib-stream` transport compression of the gateway connection.
+    ///
+    /// This greatly reduces the size of large payloads like `READY` and `GUILD_CREATE`.
+    ///
+    /// # Arguments
+    ///
+    /// * `compress` - Whether to compress the connection, disabled by default.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = builder.compression(true);
+    /// ```
+    pub fn compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Sets the format of the gateway payloads.
+    ///
+    /// `GatewayEncoding::Etf` is smaller on the wire, but it takes more work to parse than json:
+    /// decoded payloads are written as json for the models to deserialize them.
+    ///
+    /// # Arguments
+    ///
+    /// * `encoding` - The encoding, json by default.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = builder.encoding(GatewayEncoding::Etf);
+    /// ```
+    pub fn encoding(mut self, encoding: GatewayEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Sets the presence the bot starts with.
+    ///
+    /// Use [`update_presence`](crate::update_presence) to change it from inside a handler,
+    /// or a [`GatewayHandle`](crate::GatewayHandle) from any task.
+    ///
+    /// # Arguments
+    ///
+    /// * `presence` - The status and activities of the bot.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = builder.presence(Presence::new(OnlineStatus::Idle).activity(Activity::listening("!help")));
+    /// ```
+    pub fn presence(mut self, presence: Presence) -> Self {
+        self.presence = Some(presence);
+        self
+    }
+
+    /// Sets how long a shutdown waits for running handlers to finish.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum time to wait, 10 seconds by default.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = builder.shutdown_timeout(Duration::from_secs(30));
+    /// ```
+    pub fn shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+}
@@ -1,8 +1,7 @@
-use lazy_static::lazy_static;
 use reqwest::Method;
 use std::collections::HashMap;
 
-use std::sync::Mutex;
+use std::sync::Arc;
 
 use json::object;
 use nanoserde::SerJson;
@@ -10,34 +9,42 @@ use nanoserde::SerJson;
 use crate::consts::intents::GatewayIntent;
 use crate::internals::*;
 use crate::models::application_command::ApplicationCommandOption;
+use crate::models::presence::Presence;
 use crate::prelude::{CreateMessageData, Embed, Message};
 use crate::utils::{self, request};
-use crate::ws;
+use crate::ws::{self, payload::GatewayEncoding};
 use crate::{consts, internals, Event};
 
-use log::{error, info};
+use super::{ClientBuilder, Http, IdentifyProperties};
 
-// SAFETY: These will always be valid if accessed from an event.
-lazy_static::lazy_static! {
-    pub(crate) static ref BOT_ID: Mutex<Option<String>> = Mutex::new(None);
-    pub(crate) static ref SESSION_ID: Mutex<Option<String>> = Mutex::new(None);
-    pub(crate) static ref TOKEN: Mutex<Option<String>> = Mutex::new(None);
-    pub(crate) static ref RESUME_GATEWAY_URL: Mutex<Option<String>> = Mutex::new(None);
+use log::{error, info};
 
-    static ref HELP_EMBED: tokio::sync::Mutex<Embed> = tokio::sync::Mutex::new(Embed::default());
-}
+/// The embed sent by the default help command, kept in the state of the client.
+struct HelpEmbed(Embed);
 
 /// The main client struct for interacting with the Discord API.
 pub struct Client {
     intents: u32,
-    ws: ws::WsManager,
-    token: String,
+    shards: Option<ws::ShardCount>,
+    reconnect_policy: ws::ReconnectPolicy,
+    compress: bool,
+    encoding: GatewayEncoding,
+    presence: Option<Presence>,
+    gateway_url: Option<String>,
+    identify_properties: IdentifyProperties,
+    default_help: bool,
+    http: Arc<Http>,
+    shutdown: ws::ShutdownHandle,
+    gateway: ws::GatewayHandle,
+    shutdown_timeout: std::time::Duration,
     commands: HashMap<String, Command>,
     slash_commands: HashMap<String, SlashCommand>,
-    event_handlers: HashMap<Event, EventHandler>,
+    event_handlers: HashMap<Event, Vec<EventHandler>>,
     component_handlers: HashMap<String, ComponentHandler>,
     prefix: String,
     error_handler: Option<ErrorHandlerFn>,
+    event_streams: Vec<ws::EventSubscriber>,
+    state: StateMap,
 }
 
 impl Client {
@@ -49,51 +56,252 @@ impl Client {
     /// * `intents` - The gateway intents.
     /// * `prefix` - The default prefix for message commands.
     ///
+    /// # Panics
+    ///
+    /// Panics if the http client can't be built, use [`Client::builder`] to handle the error.
+    ///
     /// # Examples
     ///
     /// ```
     /// let client = Client::new("TOKEN", GatewayIntent::NON_PRIVILEGED, "!").await;
     /// ```
     pub async fn new(token: &str, intents: impl Into<u32>, prefix: &str) -> Self {
-        *TOKEN.lock().unwrap() = Some(token.to_owned());
+        ClientBuilder::new(token, intents, prefix)
+            .build()
+            .expect("Failed to build the http client")
+    }
+
+    /// Returns a builder to configure the client before creating it.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The bot token.
+    /// * `intents` - The gateway intents.
+    /// * `prefix` - The default prefix for message commands.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let client = Client::builder("TOKEN", GatewayIntent::NON_PRIVILEGED, "!")
+    ///     .api_url("http://127.0.0.1:8080/api/v10")
+    ///     .gateway_url("ws://127.0.0.1:8081")
+    ///     .build()?;
+    /// ```
+    pub fn builder(token: &str, intents: impl Into<u32>, prefix: &str) -> ClientBuilder {
+        ClientBuilder::new(token, intents, prefix)
+    }
 
-        Self {
-            intents: intents.into(),
-            token: token.to_owned(),
-            ws: ws::WsManager::new(token)
-                .await
-                .expect("Failed to initialize websockets"),
-            prefix: prefix.to_owned(),
+    pub(super) fn from_builder(builder: ClientBuilder) -> Result<Self, DescordError> {
+        Ok(Self {
+            intents: builder.intents,
+            http: Arc::new(Http::new(
+                builder.token,
+                builder.api_url,
+                builder.http_options,
+            )?),
+            shards: builder.shards,
+            reconnect_policy: builder.reconnect_policy,
+            compress: builder.compress,
+            encoding: builder.encoding,
+            presence: builder.presence,
+            gateway_url: builder.gateway_url,
+            identify_properties: builder.identify_properties,
+            default_help: builder.default_help,
+            prefix: builder.prefix,
+            shutdown: ws::ShutdownHandle::default(),
+            gateway: ws::GatewayHandle::default(),
+            shutdown_timeout: builder.shutdown_timeout,
 
             commands: HashMap::new(),
             slash_commands: HashMap::new(),
             event_handlers: HashMap::new(),
             component_handlers: HashMap::new(),
             error_handler: None,
-        }
+            event_streams: Vec::new(),
+            state: StateMap::default(),
+        })
     }
 
     /// Logs the client in and starts the event loop.
     ///
+    /// Returns once the client was stopped with its [`ShutdownHandle`](crate::ShutdownHandle)
+    /// and the running handlers finished, or the gateway connection failed for good.
+    ///
+    /// # Errors
+    ///
+    /// The error of the first connection attempt, e.g. if the gateway can't be reached,
+    /// [`DescordError::GatewayClosed`] if discord closed the connection with a code that
+    /// can't be resumed, e.g. because of an invalid token or disallowed intents, and
+    /// [`DescordError::GatewayRetriesExhausted`] if the reconnect policy gave up.
+    /// Later connection failures are retried with the reconnect policy.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// client.login().await?;
+    /// ```
+    pub async fn login(self) -> Result<(), DescordError> {
+        let http = Arc::clone(&self.http);
+        let shutdown = self.shutdown.clone();
+        let timeout = self.shutdown_timeout;
+
+        let result = http.scope(self.run()).await;
+        shutdown.wait_for_tasks(timeout).await;
+
+        result
+    }
+
+    /// Returns a handle to stop the client once it logged in.
+    ///
     /// # Examples
     ///
+    /// ```ignore
+    /// let shutdown = client.shutdown_handle();
+    /// shutdown.shutdown_on_signal();
+    ///
+    /// client.login().await?;
     /// ```
-    /// client.login().await;
+    pub fn shutdown_handle(&self) -> ws::ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Returns a handle to the gateway connection, e.g. to update the presence of the bot
+    /// from any task once the client logged in.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let gateway = client.gateway_handle();
+    /// tokio::spawn(async move {
+    ///     tokio::time::sleep(Duration::from_secs(10)).await;
+    ///     gateway.update_shard_presence(0, Presence::new(OnlineStatus::Idle)).ok();
+    /// });
+    ///
+    /// client.login().await?;
     /// ```
-    pub async fn login(mut self) {
-        self.default_help().await;
-        self.ws
-            .start(
-                self.intents,
-                ws::Handlers {
-                    event_handlers: self.event_handlers.into(),
-                    commands: self.commands.into(),
-                    slash_commands: self.slash_commands.into(),
-                    component_handlers: self.component_handlers.into(),
-                    error_handler: self.error_handler.clone(),
-                },
-            )
-            .await;
+    pub fn gateway_handle(&self) -> ws::GatewayHandle {
+        self.gateway.clone()
+    }
+
+    async fn run(mut self) -> Result<(), DescordError> {
+        if self.default_help {
+            self.default_help();
+        }
+
+        let handlers = ws::Handlers {
+            event_handlers: self.event_handlers.into(),
+            commands: self.commands.into(),
+            slash_commands: self.slash_commands.into(),
+            component_handlers: self.component_handlers.into(),
+            error_handler: self.error_handler.clone(),
+            event_streams: self.event_streams.into(),
+            state: std::sync::Arc::new(self.state),
+        };
+
+        let options = ws::GatewayOptions {
+            http: Arc::clone(&self.http),
+            reconnect_policy: self.reconnect_policy,
+            compress: self.compress,
+            encoding: self.encoding,
+            gateway_url: self.gateway_url,
+            identify_properties: self.identify_properties,
+            shutdown: self.shutdown.clone(),
+            gateway: self.gateway.clone(),
+        };
+
+        if let Some(shards) = self.shards {
+            return ws::start_sharded(options, self.intents, shards, self.presence, handlers).await;
+        }
+
+        let (gateway, mut commands) = ws::SharedGateway::new(1, self.presence);
+        self.gateway.connect(&gateway);
+
+        let gateway_url = options
+            .gateway_url
+            .clone()
+            .unwrap_or_else(|| consts::GATEWAY_URL.to_owned());
+
+        let result = match ws::WsManager::new(
+            options,
+            &gateway_url,
+            None,
+            std::sync::Arc::new(ws::IdentifyQueue::new(1)),
+            Arc::clone(&gateway),
+            commands.remove(0),
+        )
+        .await
+        {
+            Ok(mut ws) => ws.start(self.intents, handlers).await,
+            Err(e) => Err(e.into()),
+        };
+
+        gateway.cancel_member_requests();
+        result
+    }
+
+    /// Returns a stream of every gateway event the client receives.
+    ///
+    /// Events are sent to the stream in addition to the registered handlers,
+    /// the stream can be consumed from any task.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let mut events = client.event_stream();
+    ///
+    /// tokio::spawn(async move {
+    ///     while let Some(event) = events.next().await {
+    ///         println!("{:?} on shard {}", event.event, event.shard.id);
+    ///     }
+    /// });
+    /// ```
+    pub fn event_stream(&mut self) -> ws::EventStream {
+        let (subscriber, stream) = ws::EventSubscriber::new(None, consts::EVENT_STREAM_CAPACITY);
+        self.event_streams.push(subscriber);
+        stream
+    }
+
+    /// Returns a stream of the given gateway events.
+    ///
+    /// # Arguments
+    ///
+    /// * `events` - The events to receive.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let mut messages = client.event_stream_for(&[Event::MessageCreate, Event::MessageUpdate]);
+    /// ```
+    pub fn event_stream_for(&mut self, events: &[Event]) -> ws::EventStream {
+        let (subscriber, stream) = ws::EventSubscriber::new(
+            Some(events.iter().copied().collect()),
+            consts::EVENT_STREAM_CAPACITY,
+        );
+
+        self.event_streams.push(subscriber);
+        stream
+    }
+
+    /// Adds shared state that handlers can receive as a `State<T>` parameter.
+    ///
+    /// Only one state per type can be added, adding another one replaces it.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state, e.g. a database pool or a config.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// client.add_state(Config::load()?);
+    ///
+    /// #[descord::command]
+    /// async fn prefix(msg: Message, config: State<Config>) {
+    ///     msg.reply(&config.prefix).await;
+    /// }
+    /// ```
+    pub fn add_state<T: Send + Sync + 'static>(&mut self, state: T) {
+        self.state.insert(state);
     }
 
     /// Sets the error handler for the client.
@@ -124,11 +332,27 @@ impl Client {
     /// let token = client.token();
     /// ```
     pub fn token(&self) -> &str {
-        &self.token
+        self.http.token()
+    }
+
+    /// Returns the rest api context of the client.
+    ///
+    /// Use it to make rest calls with this client from outside of its handlers.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let channel = client.http().scope(utils::fetch_channel("CHANNEL_ID")).await?;
+    /// ```
+    pub fn http(&self) -> Arc<Http> {
+        Arc::clone(&self.http)
     }
 
     /// Registers event handlers.
     ///
+    /// Any number of handlers can be registered for the same event,
+    /// they are called one after another in the order they were registered.
+    ///
     /// # Arguments
     ///
     /// * `events` - A vector of event handlers.
@@ -140,14 +364,42 @@ impl Client {
     /// ```
     pub fn register_events(&mut self, events: Vec<EventHandler>) {
         events.into_iter().for_each(|event| {
-            if self.event_handlers.contains_key(&event.event) {
-                panic!("{:?} is already hooked", event.event);
-            }
-
-            self.event_handlers.insert(event.event, event);
+            self.event_handlers.entry(event.event).or_default().push(event);
         });
     }
 
+    /// Registers an async closure as an event handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event to handle.
+    /// * `f` - The handler, called with the data of the event.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let stats = Arc::new(Stats::default());
+    ///
+    /// client.on(Event::MessageCreate, move |data| {
+    ///     let stats = Arc::clone(&stats);
+    ///     async move {
+    ///         let HandlerValue::Message(message) = data else {
+    ///             return Ok(());
+    ///         };
+    ///
+    ///         stats.record(&message).await;
+    ///         Ok(())
+    ///     }
+    /// });
+    /// ```
+    pub fn on<F, Fut>(&mut self, event: Event, f: F)
+    where
+        F: Fn(HandlerValue) -> Fut + Send + Sync + 'static,
+        Fut: futures_util::Future<Output = HandlerResult> + Send + 'static,
+    {
+        self.register_events(vec![EventHandler::new(event, f)]);
+    }
+
     /// Registers callbacks for message components such as message buttons.
     ///
     /// # Arguments
@@ -209,9 +461,9 @@ impl Client {
     /// ```
     pub async fn register_slash_commands(&mut self, commands: Vec<SlashCommand>) {
         self.slash_commands.extend(
-            utils::slash::register_slash_commands(commands)
-                .await
-                .into_iter(),
+            self.http
+                .scope(utils::slash::register_slash_commands(commands))
+                .await,
         );
     }
 
@@ -226,8 +478,8 @@ impl Client {
     /// ```
     pub fn get_commands(&self) -> Vec<CommandInfo> {
         self.commands
-            .iter()
-            .map(|(_, value)| CommandInfo {
+            .values()
+            .map(|value| CommandInfo {
                 name: value.name.clone(),
                 description: value.description.clone(),
                 params: value.fn_sig.clone(),
@@ -265,7 +517,7 @@ impl Client {
     }
 
     /// Adds a default help command that lists all registered commands.
-    async fn default_help(&mut self) {
+    fn default_help(&mut self) {
         let help_cmd = format!("{}help", self.prefix);
         if self.commands.iter().any(|(name, _)| name == &help_cmd) {
             return;
@@ -290,7 +542,7 @@ impl Client {
             .field("Slash Commands", &slash_commands_field_text, false)
             .build();
 
-        *HELP_EMBED.lock().await = help_embed;
+        self.state.insert(HelpEmbed(help_embed));
 
         fn f(
             msg: Message,
@@ -299,7 +551,10 @@ impl Client {
             Box<dyn std::future::Future<Output = crate::HandlerResult> + Send + 'static>,
         > {
             Box::pin(async move {
-                msg.reply(HELP_EMBED.lock().await.clone())
+                let help = State::<HelpEmbed>::get()
+                    .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
+
+                msg.reply(help.0.clone())
                     .await
                     .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send>)?;
                 Ok(())
@@ -0,0 +1,219 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::cache::{Cache, CacheSizes};
+use crate::consts;
+use crate::internals::DescordError;
+use crate::utils::{RateLimiter, RestObserver, RetryPolicy};
+
+tokio::task_local! {
+    static CURRENT_HTTP: Arc<Http>;
+}
+
+/// Settings of the http client used for rest calls.
+#[derive(Clone)]
+pub(crate) struct HttpOptions {
+    pub timeout: Duration,
+    pub connect_timeout: Duration,
+    pub proxy: Option<reqwest::Proxy>,
+    /// Whether to negotiate HTTP/2 with the api, HTTP/1.1 is used otherwise.
+    pub http2: bool,
+    /// How many routes and buckets the rate limiter remembers.
+    pub rate_limit_cache_size: usize,
+    /// Capacities of the caches of the client.
+    pub cache_sizes: CacheSizes,
+    pub retry_policy: RetryPolicy,
+    pub observer: Option<Arc<dyn RestObserver>>,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            timeout: consts::HTTP_TIMEOUT,
+            connect_timeout: consts::HTTP_CONNECT_TIMEOUT,
+            proxy: None,
+            http2: true,
+            rate_limit_cache_size: consts::RATE_LIMITS_CACHE_SIZE,
+            cache_sizes: CacheSizes::default(),
+            retry_policy: RetryPolicy::default(),
+            observer: None,
+        }
+    }
+}
+
+/// The rest api context of a client, that is its token, api url and http client.
+///
+/// Every client owns one, so multiple clients can live in one process. Models keep the
+/// context of the client that received them and their methods use it from any task,
+/// the functions of [`utils`](crate::utils) use the context of the client running them.
+pub struct Http {
+    token: String,
+    api_url: String,
+    /// Pooled http client, shared by every request of the client.
+    client: reqwest::Client,
+    ratelimiter: RateLimiter,
+    retry_policy: RetryPolicy,
+    /// The id of the bot, known once `READY` was received.
+    bot_id: Mutex<Option<String>>,
+    cache: Cache,
+}
+
+impl Http {
+    /// Creates the context of a client.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DescordError::Http`] if the http client can't be built, e.g. because
+    /// the TLS backend can't be initialized or the proxy is invalid.
+    pub(crate) fn new(
+        token: String,
+        api_url: String,
+        options: HttpOptions,
+    ) -> Result<Self, DescordError> {
+        let mut client = reqwest::Client::builder()
+            .user_agent(consts::USER_AGENT)
+            .timeout(options.timeout)
+            .connect_timeout(options.connect_timeout);
+
+        if let Some(proxy) = options.proxy {
+            client = client.proxy(proxy);
+        }
+
+        if !options.http2 {
+            client = client.http1_only();
+        }
+
+        Ok(Self {
+            token,
+            api_url,
+            client: client.build()?,
+            ratelimiter: RateLimiter::new(options.rate_limit_cache_size, options.observer),
+            retry_policy: options.retry_policy,
+            bot_id: Mutex::new(None),
+            cache: Cache::new(options.cache_sizes),
+        })
+    }
+
+    /// Returns the context of the client running the current task.
+    ///
+    /// This is available inside handlers and inside futures passed to [`Http::scope`],
+    /// anywhere else it fails with [`DescordError::NoHttpContext`].
+    pub fn current() -> Result<Arc<Self>, DescordError> {
+        CURRENT_HTTP
+            .try_with(Arc::clone)
+            .map_err(|_| DescordError::NoHttpContext)
+    }
+
+    /// Runs a future with this context, rest calls made from it use the token of this client.
+    ///
+    /// Tasks spawned from handlers don't inherit the context. Methods of the models a client
+    /// received (e.g. [`Message::reply`](crate::prelude::Message::reply)) keep working in them,
+    /// but the functions of [`utils`](crate::utils) need to be wrapped with this.
+    ///
+    /// # Arguments
+    ///
+    /// * `future` - The future to run.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let http = Http::current()?;
+    ///
+    /// tokio::spawn(http.scope(async move {
+    ///     tokio::time::sleep(Duration::from_secs(5)).await;
+    ///     msg.reply("5 seconds later").await;
+    /// }));
+    /// ```
+    pub fn scope<F: Future>(self: &Arc<Self>, future: F) -> impl Future<Output = F::Output> {
+        CURRENT_HTTP.scope(Arc::clone(self), future)
+    }
+
+    /// Runs a future with the client a model was received by,
+    /// or with the current context if the model was built by hand.
+    pub(crate) async fn with<F: Future>(http: &Option<Arc<Self>>, future: F) -> F::Output {
+        match http {
+            Some(http) => http.scope(future).await,
+            None => future.await,
+        }
+    }
+
+    /// Returns the id of the bot, `None` until the client received `READY`.
+    pub fn bot_id(&self) -> Option<String> {
+        self.bot_id.lock().unwrap().clone()
+    }
+
+    pub(crate) fn set_bot_id(&self, id: String) {
+        *self.bot_id.lock().unwrap() = Some(id);
+    }
+
+    pub(crate) fn token(&self) -> &str {
+        &self.token
+    }
+
+    pub(crate) fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    pub(crate) fn ratelimiter(&self) -> &RateLimiter {
+        &self.ratelimiter
+    }
+
+    pub(crate) fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    pub(crate) fn cache(&self) -> &Cache {
+        &self.cache
+    }
+
+    /// The base url of the rest api, without a trailing slash.
+    pub(crate) fn api_url(&self) -> &str {
+        &self.api_url
+    }
+}
+
+impl std::fmt::Debug for Http {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // never print the token
+        f.debug_struct("Http")
+            .field("api_url", &self.api_url)
+            .field("bot_id", &self.bot_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Implemented by models that keep the client they were received by,
+/// so their methods work outside of handlers, e.g. in spawned tasks.
+pub(crate) trait Attach {
+    fn attach(&mut self, http: &Arc<Http>);
+}
+
+impl<T: Attach> Attach for Vec<T> {
+    fn attach(&mut self, http: &Arc<Http>) {
+        self.iter_mut().for_each(|model| model.attach(http));
+    }
+}
+
+impl<T: Attach> Attach for Option<T> {
+    fn attach(&mut self, http: &Arc<Http>) {
+        if let Some(model) = self {
+            model.attach(http);
+        }
+    }
+}
+
+impl<T: Attach> Attach for Box<T> {
+    fn attach(&mut self, http: &Arc<Http>) {
+        (**self).attach(http);
+    }
+}
+
+/// Attaches the client of the current context to a model received from the api.
+pub(crate) fn attach_current<T: Attach>(mut model: T) -> T {
+    if let Ok(http) = Http::current() {
+        model.attach(&http);
+    }
+
+    model
+}
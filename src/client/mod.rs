@@ -1,3 +1,9 @@
+mod builder;
 mod client;
+mod http;
+mod webhook;
 
+pub use builder::*;
 pub use client::*;
+pub use http::*;
+pub use webhook::*;
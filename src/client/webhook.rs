@@ -0,0 +1,219 @@
+use std::sync::Arc;
+
+use reqwest::Url;
+
+use super::{Http, HttpOptions};
+use crate::consts;
+use crate::internals::DescordError;
+use crate::models::message_response::{CreateMessageData, Message};
+use crate::models::webhook::{ExecuteWebhook, ModifyWebhook, Webhook};
+use crate::utils;
+
+/// A client that sends messages with a webhook, it needs neither a bot token nor a gateway connection.
+///
+/// Requests are sent with their own rate limiter and retry policy.
+///
+/// # Examples
+///
+/// ```ignore
+/// let webhook = WebhookClient::from_url("https://discord.com/api/webhooks/123/abc")?;
+///
+/// webhook
+///     .execute(ExecuteWebhook::new("Backup finished").username("Backups"))
+///     .await?;
+/// ```
+#[derive(Clone)]
+pub struct WebhookClient {
+    id: String,
+    token: String,
+    http: Arc<Http>,
+}
+
+impl std::fmt::Debug for WebhookClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // never print the token
+        f.debug_struct("WebhookClient")
+            .field("id", &self.id)
+            .field("http", &self.http)
+            .finish_non_exhaustive()
+    }
+}
+
+impl WebhookClient {
+    /// Creates a new `WebhookClient` from the ID and token of a webhook.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the webhook.
+    /// * `token` - The token of the webhook.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`DescordError::Http`] if the http client can't be built.
+    pub fn new(id: &str, token: &str) -> Result<Self, DescordError> {
+        Self::with_api_url(id, token, consts::API.to_owned())
+    }
+
+    /// Creates a new `WebhookClient` from a webhook url, e.g. `https://discord.com/api/webhooks/{id}/{token}`.
+    ///
+    /// Requests are sent to the host of the url, the current api version is used if it has none.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The url of the webhook.
+    pub fn from_url(url: &str) -> Result<Self, DescordError> {
+        let invalid = || DescordError::InvalidArgument {
+            param: "url".to_owned(),
+            expected: "a webhook url".to_owned(),
+            got: url.to_owned(),
+        };
+
+        let parsed = Url::parse(url).map_err(|_| invalid())?;
+        let segments: Vec<&str> = parsed
+            .path_segments()
+            .ok_or_else(invalid)?
+            .filter(|segment| !segment.is_empty())
+            .collect();
+
+        let position = segments
+            .iter()
+            .position(|segment| *segment == "webhooks")
+            .ok_or_else(invalid)?;
+
+        let (Some(id), Some(token)) = (segments.get(position + 1), segments.get(position + 2))
+        else {
+            return Err(invalid());
+        };
+
+        let mut api_url = parsed.origin().ascii_serialization();
+        for segment in &segments[..position] {
+            api_url += &format!("/{segment}");
+        }
+
+        if segments[..position].last() == Some(&"api") {
+            let version = consts::API.rsplit('/').next().unwrap_or_default();
+            api_url += &format!("/{version}");
+        }
+
+        Self::with_api_url(id, token, api_url)
+    }
+
+    fn with_api_url(id: &str, token: &str, api_url: String) -> Result<Self, DescordError> {
+        Ok(Self {
+            id: id.to_owned(),
+            token: token.to_owned(),
+            http: Arc::new(Http::new(String::new(), api_url, HttpOptions::default())?),
+        })
+    }
+
+    /// Returns the ID of the webhook.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Sends a message, see [`utils::execute_webhook`].
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The message.
+    pub async fn execute(&self, data: ExecuteWebhook) -> Result<Option<Message>, DescordError> {
+        self.http
+            .scope(utils::execute_webhook(&self.id, &self.token, data))
+            .await
+    }
+
+    /// Gets a message sent by the webhook.
+    ///
+    /// # Arguments
+    ///
+    /// * `message_id` - The ID of the message.
+    /// * `thread_id` - The ID of the thread the message is in.
+    pub async fn fetch_message(
+        &self,
+        message_id: &str,
+        thread_id: Option<&str>,
+    ) -> Result<Message, DescordError> {
+        self.http
+            .scope(utils::fetch_webhook_message(
+                &self.id,
+                &self.token,
+                message_id,
+                thread_id,
+            ))
+            .await
+    }
+
+    /// Edits a message sent by the webhook.
+    ///
+    /// # Arguments
+    ///
+    /// * `message_id` - The ID of the message.
+    /// * `data` - The new content of the message.
+    /// * `thread_id` - The ID of the thread the message is in.
+    pub async fn edit_message(
+        &self,
+        message_id: &str,
+        data: impl Into<CreateMessageData>,
+        thread_id: Option<&str>,
+    ) -> Result<Message, DescordError> {
+        self.http
+            .scope(utils::edit_webhook_message(
+                &self.id,
+                &self.token,
+                message_id,
+                data,
+                thread_id,
+            ))
+            .await
+    }
+
+    /// Deletes a message sent by the webhook.
+    ///
+    /// # Arguments
+    ///
+    /// * `message_id` - The ID of the message.
+    /// * `thread_id` - The ID of the thread the message is in.
+    pub async fn delete_message(
+        &self,
+        message_id: &str,
+        thread_id: Option<&str>,
+    ) -> Result<(), DescordError> {
+        self.http
+            .scope(utils::delete_webhook_message(
+                &self.id,
+                &self.token,
+                message_id,
+                thread_id,
+            ))
+            .await
+    }
+
+    /// Gets the webhook.
+    pub async fn fetch(&self) -> Result<Webhook, DescordError> {
+        self.http
+            .scope(utils::fetch_webhook_with_token(&self.id, &self.token))
+            .await
+    }
+
+    /// Modifies the name or avatar of the webhook.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The changes.
+    pub async fn modify(&self, data: ModifyWebhook) -> Result<Webhook, DescordError> {
+        self.http
+            .scope(utils::modify_webhook_with_token(
+                &self.id,
+                &self.token,
+                data,
+            ))
+            .await
+    }
+
+    /// Deletes the webhook.
+    pub async fn delete(&self) -> Result<(), DescordError> {
+        self.http
+            .scope(utils::delete_webhook_with_token(&self.id, &self.token))
+            .await
+    }
+}
@@ -0,0 +1,76 @@
+/// Close codes the gateway may close the connection with.
+/// https://discord.com/developers/docs/topics/opcodes-and-status-codes#gateway-gateway-close-event-codes
+#[repr(u16)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GatewayCloseCode {
+    UnknownError = 4000,
+    UnknownOpcode = 4001,
+    DecodeError = 4002,
+    NotAuthenticated = 4003,
+    AuthenticationFailed = 4004,
+    AlreadyAuthenticated = 4005,
+    InvalidSeq = 4007,
+    RateLimited = 4008,
+    SessionTimedOut = 4009,
+    InvalidShard = 4010,
+    ShardingRequired = 4011,
+    InvalidApiVersion = 4012,
+    InvalidIntents = 4013,
+    DisallowedIntents = 4014,
+}
+
+/// What should be done after the gateway closed the connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseBehaviour {
+    /// Reconnect and resume the session.
+    Resume,
+    /// Reconnect with a new session.
+    Identify,
+    /// Reconnecting won't help, e.g. the token is invalid.
+    Fatal,
+}
+
+impl GatewayCloseCode {
+    pub fn parse(code: u16) -> Option<GatewayCloseCode> {
+        Some(match code {
+            4000 => GatewayCloseCode::UnknownError,
+            4001 => GatewayCloseCode::UnknownOpcode,
+            4002 => GatewayCloseCode::DecodeError,
+            4003 => GatewayCloseCode::NotAuthenticated,
+            4004 => GatewayCloseCode::AuthenticationFailed,
+            4005 => GatewayCloseCode::AlreadyAuthenticated,
+            4007 => GatewayCloseCode::InvalidSeq,
+            4008 => GatewayCloseCode::RateLimited,
+            4009 => GatewayCloseCode::SessionTimedOut,
+            4010 => GatewayCloseCode::InvalidShard,
+            4011 => GatewayCloseCode::ShardingRequired,
+            4012 => GatewayCloseCode::InvalidApiVersion,
+            4013 => GatewayCloseCode::InvalidIntents,
+            4014 => GatewayCloseCode::DisallowedIntents,
+
+            _ => return None,
+        })
+    }
+
+    pub fn behaviour(self) -> CloseBehaviour {
+        match self {
+            GatewayCloseCode::UnknownError
+            | GatewayCloseCode::UnknownOpcode
+            | GatewayCloseCode::DecodeError
+            | GatewayCloseCode::NotAuthenticated
+            | GatewayCloseCode::AlreadyAuthenticated
+            | GatewayCloseCode::RateLimited => CloseBehaviour::Resume,
+
+            GatewayCloseCode::InvalidSeq | GatewayCloseCode::SessionTimedOut => {
+                CloseBehaviour::Identify
+            }
+
+            GatewayCloseCode::AuthenticationFailed
+            | GatewayCloseCode::InvalidShard
+            | GatewayCloseCode::ShardingRequired
+            | GatewayCloseCode::InvalidApiVersion
+            | GatewayCloseCode::InvalidIntents
+            | GatewayCloseCode::DisallowedIntents => CloseBehaviour::Fatal,
+        }
+    }
+}
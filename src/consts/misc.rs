@@ -1,10 +1,48 @@
+use std::time::Duration;
+
 pub const GATEWAY_URL: &str = "wss://gateway.discord.gg/?v=10&encoding=json";
+/// Query appended to gateway urls returned by discord (`/gateway/bot`, `resume_gateway_url`).
+pub const GATEWAY_QUERY: &str = "/?v=10&encoding=json";
+/// Query enabling transport compression of the gateway connection.
+pub const ZLIB_STREAM_QUERY: &str = "&compress=zlib-stream";
+/// Minimum time between two identifies in the same rate limit bucket.
+pub const IDENTIFY_INTERVAL: Duration = Duration::from_secs(5);
+/// How long a shutdown waits for running handlers by default.
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long to try sending a close frame over a connection that stopped acknowledging heartbeats.
+pub const ZOMBIE_CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
 pub const API: &str = "https://discord.com/api/v10";
+/// https://discord.com/developers/docs/reference#user-agent
+pub const USER_AGENT: &str = concat!(
+    "DiscordBot (https://github.com/thatmagicalcat/descord, ",
+    env!("CARGO_PKG_VERSION"),
+    ")"
+);
+/// Requests a bot may send in every global rate limit window.
+pub const GLOBAL_RATE_LIMIT: u32 = 50;
+pub const GLOBAL_RATE_LIMIT_WINDOW: Duration = Duration::from_secs(1);
+/// Default time allowed for a whole rest request.
+pub const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+/// Default time allowed to open a connection to the rest api.
+pub const HTTP_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
 pub const MESSAGE_CACHE_SIZE: usize = 100_000;
 pub const ROLE_CACHE_SIZE: usize = 100_000;
 pub const GUILD_CACHE_SIZE: usize = 30_000;
 pub const RATE_LIMITS_CACHE_SIZE: usize = 50_000;
 pub const CHANNEL_CACHE_SIZE: usize = 50_000;
+/// Number of events an event stream buffers, later events are dropped until it's read.
+pub const EVENT_STREAM_CAPACITY: usize = 256;
+/// Milliseconds between the unix epoch and the first second of 2015, where snowflake timestamps start.
+pub const DISCORD_EPOCH: u64 = 1_420_070_400_000;
+/// Most messages discord returns for one request.
+pub const MESSAGE_PAGE_SIZE: usize = 100;
+/// Bytes read at once from a file or stream sent as an attachment.
+pub const ATTACHMENT_CHUNK_SIZE: usize = 64 * 1024;
+/// Most messages that can be deleted with one bulk delete.
+pub const BULK_DELETE_LIMIT: usize = 100;
+/// Messages older than this can't be bulk deleted. It is 5 minutes less than the
+/// 14 days discord allows, so messages don't become too old while a purge runs.
+pub const BULK_DELETE_MAX_AGE: Duration = Duration::from_secs(14 * 24 * 60 * 60 - 5 * 60);
 pub const DISCORD_CDN: &str = "https://cdn.discordapp.com";
 
 #[derive(Debug, Clone, Copy)]
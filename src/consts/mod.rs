@@ -1,6 +1,7 @@
 mod misc;
 pub use misc::*;
 
+pub mod close_code;
 pub mod color;
 pub mod events;
 pub mod intents;
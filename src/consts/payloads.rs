@@ -1,6 +1,9 @@
 use json::object;
 use json::JsonValue;
 
+use crate::client::IdentifyProperties;
+use crate::models::presence::Presence;
+
 pub fn heartbeat(last_sequence: usize) -> JsonValue {
     if last_sequence == 0 {
         object! {
@@ -15,19 +18,99 @@ pub fn heartbeat(last_sequence: usize) -> JsonValue {
     }
 }
 
-pub fn identify(token: &str, intents: u32) -> JsonValue {
-    object! {
+pub fn identify(
+    token: &str,
+    intents: u32,
+    shard: Option<[u32; 2]>,
+    presence: Option<&Presence>,
+    properties: &IdentifyProperties,
+) -> JsonValue {
+    let mut payload = object! {
         op: 2,
         d: {
             token: token,
             properties: {
-                os: "linux",
-                browser: "descord",
-                device: "descord"
+                os: properties.os.as_str(),
+                browser: properties.browser.as_str(),
+                device: properties.device.as_str()
             },
             intents: intents
         }
+    };
+
+    if let Some([shard_id, num_shards]) = shard {
+        payload["d"]["shard"] = json::array![shard_id, num_shards];
+    }
+
+    if let Some(presence) = presence {
+        payload["d"]["presence"] = presence_data(presence);
+    }
+
+    payload
+}
+
+pub fn presence_update(presence: &Presence) -> JsonValue {
+    object! {
+        op: 3,
+        d: presence_data(presence)
+    }
+}
+
+fn presence_data(presence: &Presence) -> JsonValue {
+    let activities: Vec<JsonValue> = presence
+        .activities
+        .iter()
+        .map(|activity| {
+            let mut data = object! {
+                name: activity.name.as_str(),
+                type: activity.kind as u8
+            };
+
+            if let Some(url) = &activity.url {
+                data["url"] = url.as_str().into();
+            }
+
+            if let Some(state) = &activity.state {
+                data["state"] = state.as_str().into();
+            }
+
+            data
+        })
+        .collect();
+
+    object! {
+        since: presence.since,
+        activities: activities,
+        status: presence.status.as_str(),
+        afk: presence.afk
+    }
+}
+
+pub fn request_guild_members(
+    guild_id: &str,
+    query: Option<&str>,
+    user_ids: &[String],
+    limit: u32,
+    presences: bool,
+    nonce: &str,
+) -> JsonValue {
+    let mut payload = object! {
+        op: 8,
+        d: {
+            guild_id: guild_id,
+            limit: limit,
+            presences: presences,
+            nonce: nonce
+        }
+    };
+
+    if let Some(query) = query {
+        payload["d"]["query"] = query.into();
+    } else {
+        payload["d"]["user_ids"] = user_ids.into();
     }
+
+    payload
 }
 
 pub fn resume(token: &str, session_id: &str, seq: usize) -> JsonValue {
@@ -1,10 +1,13 @@
 use super::*;
 
+// handlers bind the variant's value directly, so it can't be boxed like `Interaction`
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum HandlerValue {
     ReadyData(ReadyData),
     Message(Message),
     DeletedMessage(DeletedMessage),
+    BulkDeletedMessages(BulkDeletedMessages),
     Reaction(Reaction),
     GuildCreate(GuildCreate),
     Interaction(Box<Interaction>),
@@ -13,11 +16,17 @@ pub enum HandlerValue {
     Reconnect(Reconnect),
     Member(Member),
     MemberLeave(MemberLeave),
+    Channel(Channel),
+    ThreadListSync(ThreadListSync),
+    ThreadMember(ThreadMember),
+    ThreadMembersUpdate(ThreadMembersUpdate),
+    GuildMembersChunk(GuildMembersChunk),
 }
 
 impl From<ReadyData> for HandlerValue { fn from(value: ReadyData) -> Self { HandlerValue::ReadyData(value) } }
 impl From<Message> for HandlerValue { fn from(value: Message) -> Self { HandlerValue::Message(value) } }
 impl From<DeletedMessage> for HandlerValue { fn from(value: DeletedMessage) -> Self { HandlerValue::DeletedMessage(value) } }
+impl From<BulkDeletedMessages> for HandlerValue { fn from(value: BulkDeletedMessages) -> Self { HandlerValue::BulkDeletedMessages(value) } }
 impl From<Reaction> for HandlerValue { fn from(value: Reaction) -> Self { HandlerValue::Reaction(value) } }
 impl From<GuildCreate> for HandlerValue { fn from(value: GuildCreate) -> Self { HandlerValue::GuildCreate(value) } }
 impl From<Interaction> for HandlerValue { fn from(value: Interaction) -> Self { HandlerValue::Interaction(Box::new(value)) } }
@@ -26,20 +35,92 @@ impl From<RoleEvent> for HandlerValue { fn from(value: RoleEvent) -> Self { Hand
 impl From<Reconnect> for HandlerValue { fn from(value: Reconnect) -> Self { HandlerValue::Reconnect(value) } }
 impl From<Member> for HandlerValue { fn from(value: Member) -> Self { HandlerValue::Member(value) } }
 impl From<MemberLeave> for HandlerValue { fn from(value: MemberLeave) -> Self { HandlerValue::MemberLeave(value) } }
+impl From<Channel> for HandlerValue { fn from(value: Channel) -> Self { HandlerValue::Channel(value) } }
+impl From<ThreadListSync> for HandlerValue { fn from(value: ThreadListSync) -> Self { HandlerValue::ThreadListSync(value) } }
+impl From<ThreadMember> for HandlerValue { fn from(value: ThreadMember) -> Self { HandlerValue::ThreadMember(value) } }
+impl From<ThreadMembersUpdate> for HandlerValue { fn from(value: ThreadMembersUpdate) -> Self { HandlerValue::ThreadMembersUpdate(value) } }
+impl From<GuildMembersChunk> for HandlerValue { fn from(value: GuildMembersChunk) -> Self { HandlerValue::GuildMembersChunk(value) } }
 
-#[derive(Debug, Clone)]
+impl crate::client::Attach for HandlerValue {
+    fn attach(&mut self, http: &std::sync::Arc<crate::client::Http>) {
+        match self {
+            HandlerValue::Message(message) => message.attach(http),
+            HandlerValue::BulkDeletedMessages(messages) => messages.attach(http),
+            HandlerValue::Reaction(reaction) => reaction.attach(http),
+            HandlerValue::GuildCreate(guild) => guild.attach(http),
+            HandlerValue::Interaction(interaction) => interaction.attach(http),
+            HandlerValue::Member(member) => member.attach(http),
+            HandlerValue::Channel(channel) => channel.attach(http),
+            HandlerValue::ThreadListSync(threads) => threads.attach(http),
+            HandlerValue::ThreadMember(member) => member.attach(http),
+            HandlerValue::ThreadMembersUpdate(update) => update.attach(http),
+            HandlerValue::GuildMembersChunk(chunk) => chunk.attach(http),
+            HandlerValue::ReadyData(_)
+            | HandlerValue::DeletedMessage(_)
+            | HandlerValue::RoleDelete(_)
+            | HandlerValue::RoleEvent(_)
+            | HandlerValue::Reconnect(_)
+            | HandlerValue::MemberLeave(_) => {}
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct EventHandler {
     pub event: Event,
     pub handler_fn: EventHandlerFn,
 }
 
-pub type EventHandlerFn = fn(
-    HandlerValue,
-) -> std::pin::Pin<
-    Box<dyn futures_util::Future<Output = HandlerResult> + Send + 'static>,
+/// An event handler, either a function generated by `#[event]` or a closure.
+pub type EventHandlerFn = std::sync::Arc<
+    dyn Fn(
+            HandlerValue,
+        ) -> std::pin::Pin<
+            Box<dyn futures_util::Future<Output = HandlerResult> + Send + 'static>,
+        > + Send
+        + Sync,
 >;
 
+impl std::fmt::Debug for EventHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventHandler")
+            .field("event", &self.event)
+            .finish_non_exhaustive()
+    }
+}
+
 impl EventHandler {
+    /// Creates an event handler from an async closure, which can capture state.
+    ///
+    /// # Arguments
+    ///
+    /// * `event` - The event to handle.
+    /// * `f` - The handler, called with the data of the event.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let counter = Arc::new(AtomicUsize::new(0));
+    ///
+    /// let handler = EventHandler::new(Event::MessageCreate, move |_data| {
+    ///     let counter = Arc::clone(&counter);
+    ///     async move {
+    ///         counter.fetch_add(1, Ordering::Relaxed);
+    ///         Ok(())
+    ///     }
+    /// });
+    /// ```
+    pub fn new<F, Fut>(event: Event, f: F) -> Self
+    where
+        F: Fn(HandlerValue) -> Fut + Send + Sync + 'static,
+        Fut: futures_util::Future<Output = HandlerResult> + Send + 'static,
+    {
+        Self {
+            event,
+            handler_fn: std::sync::Arc::new(move |data| Box::pin(f(data))),
+        }
+    }
+
     pub async fn call(&self, data: HandlerValue) -> HandlerResult {
         let fut = ((self.handler_fn)(data));
         let boxed_fut: std::pin::Pin<
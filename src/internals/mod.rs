@@ -4,18 +4,22 @@ mod commands;
 mod components;
 mod events;
 mod slash_commands;
+mod state;
 
 pub use commands::*;
 pub use components::*;
 pub use events::*;
 pub use slash_commands::*;
+pub use state::*;
 
 use crate::consts::events::Event;
 use crate::models::channel::Channel;
-use crate::models::deleted_message_response::DeletedMessage;
+use crate::models::guild::GuildMembersChunk;
+use crate::models::deleted_message_response::{BulkDeletedMessages, DeletedMessage};
 use crate::models::interaction::{Interaction, InteractionData};
 use crate::models::misc::Reconnect;
 use crate::models::reaction_response::Reaction;
+use crate::models::thread::{ThreadListSync, ThreadMember, ThreadMembersUpdate};
 use crate::prelude::*;
 use crate::utils::*;
 use futures_util::FutureExt;
@@ -73,19 +77,63 @@ pub enum DescordError {
 
     /// A WebSocket error occurred.
     #[error("WebSocket error: {0}")]
-    WebSocket(#[from] tokio_tungstenite::tungstenite::Error),
+    WebSocket(Box<tokio_tungstenite::tungstenite::Error>),
 
-    /// The gateway handshake failed (critical — will panic).
+    /// The gateway handshake failed, the connection will be retried.
     #[error("Gateway handshake failed: {0}")]
     GatewayHandshake(String),
 
-    /// The gateway connection was closed.
+    /// The gateway closed the connection with a code that can't be recovered from,
+    /// e.g. an invalid token or disallowed intents.
     #[error("Gateway closed with code {code}: {reason}")]
     GatewayClosed {
         code: u16,
         reason: String,
     },
 
+    /// An attempt to connect to the gateway failed, it will be retried.
+    #[error("Gateway connection attempt {attempt} failed: {source}")]
+    GatewayReconnect {
+        attempt: u32,
+        #[source]
+        source: Box<DescordError>,
+    },
+
+    /// The gateway could not be reconnected within the retry budget.
+    #[error("Gave up reconnecting to the gateway after {attempts} attempts")]
+    GatewayRetriesExhausted {
+        attempts: u32,
+    },
+
+    /// A function that needs the gateway connection of a handler was called outside of one.
+    #[error("This can only be used inside a handler")]
+    NotInHandler,
+
+    /// A [`GatewayHandle`](crate::GatewayHandle) was used before its client logged in.
+    #[error("The client isn't logged in")]
+    NotLoggedIn,
+
+    /// A rest call was made outside of a client, e.g. a function of [`utils`](crate::utils)
+    /// in a spawned task or a method of a model built by hand, see [`Http::scope`](crate::Http::scope).
+    #[error("Rest calls can only be made inside a handler or `Http::scope`")]
+    NoHttpContext,
+
+    /// A compressed gateway payload could not be decoded.
+    #[error("Failed to decompress gateway payload: {0}")]
+    Decompress(String),
+
+    /// An ETF gateway payload could not be decoded.
+    #[error("Failed to decode ETF payload: {0}")]
+    Etf(String),
+
+    /// A handler asked for a state that wasn't added to the client.
+    #[error("No state of type `{0}` was added to the client")]
+    MissingState(&'static str),
+
+    /// Discord didn't respond in time.
+    #[error("Timed out waiting for {0}")]
+    Timeout(String),
+
     /// Failed to deserialize JSON from Discord.
     #[error("JSON deserialization error: {0}")]
     DeserializeJson(#[from] nanoserde::DeJsonErr),
@@ -141,6 +189,13 @@ pub enum DescordError {
     Other(String),
 }
 
+// boxed to keep `Result<_, DescordError>` small
+impl From<tokio_tungstenite::tungstenite::Error> for DescordError {
+    fn from(value: tokio_tungstenite::tungstenite::Error) -> Self {
+        DescordError::WebSocket(Box::new(value))
+    }
+}
+
 #[macro_export]
 macro_rules! implemented_enum {
     [ $vis:vis enum $name:ident { $($variant:ident),* $(,)? } ] => {
@@ -0,0 +1,79 @@
+use std::any::{Any, TypeId};
+use std::sync::Arc;
+
+use super::*;
+use crate::ws::CURRENT_SHARD;
+
+/// Shared state added to the client with [`Client::add_state`](crate::Client::add_state).
+///
+/// Handlers declared with `#[command]`, `#[slash]`, `#[component]` and `#[event]`
+/// receive it by taking a `State<T>` parameter.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[descord::command]
+/// async fn count(msg: Message, counter: State<AtomicUsize>) {
+///     let count = counter.fetch_add(1, Ordering::Relaxed);
+///     msg.reply(format!("Called {count} times")).await;
+/// }
+/// ```
+pub struct State<T: ?Sized>(Arc<T>);
+
+impl<T: Send + Sync + 'static> State<T> {
+    /// Returns the state of type `T` added to the client.
+    ///
+    /// This is available inside handlers, but not inside tasks spawned from them.
+    pub fn get() -> Result<Self, DescordError> {
+        let state = CURRENT_SHARD
+            .try_with(|shard| shard.state.get::<T>())
+            .map_err(|_| DescordError::NotInHandler)?;
+
+        state
+            .map(State)
+            .ok_or(DescordError::MissingState(std::any::type_name::<T>()))
+    }
+}
+
+impl<T: ?Sized> State<T> {
+    /// Returns the inner `Arc`, e.g. to move the state into a spawned task.
+    pub fn into_inner(self) -> Arc<T> {
+        self.0
+    }
+}
+
+impl<T: ?Sized> Clone for State<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T: ?Sized> std::ops::Deref for State<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: ?Sized + std::fmt::Debug> std::fmt::Debug for State<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// The states of a client, by type.
+#[derive(Default)]
+pub(crate) struct StateMap(HashMap<TypeId, Arc<dyn Any + Send + Sync>>);
+
+impl StateMap {
+    pub fn insert<T: Send + Sync + 'static>(&mut self, state: T) {
+        self.0.insert(TypeId::of::<T>(), Arc::new(state));
+    }
+
+    pub fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.0
+            .get(&TypeId::of::<T>())
+            .and_then(|state| Arc::clone(state).downcast::<T>().ok())
+    }
+}
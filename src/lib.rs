@@ -30,7 +30,7 @@
 //!
 //!
 //!     // start the bot!
-//!     client.login().await;
+//!     client.login().await.unwrap();
 //! }
 //!
 //! // An event handler
@@ -98,17 +98,24 @@ pub mod models;
 /// Contains all the implemented rest api endpoints
 pub mod utils;
 
-pub use client::Client;
+pub use client::{Client, ClientBuilder, Http, IdentifyProperties, WebhookClient};
 
 pub use descord_macros::*;
-pub use ws::payload::Payload;
+pub use ws::payload::{GatewayEncoding, Payload};
+pub use ws::{
+    current_shard, gateway_latency, request_guild_members, update_presence, EventStream,
+    GatewayEvent, GatewayHandle, GuildMembersRequest, ReconnectPolicy, ShardCount, ShardInfo,
+    ShutdownHandle,
+};
 pub mod internals;
 
 pub use consts::events::Event;
 pub use consts::color;
 
 // Re-export error types and result aliases from internals
-pub use internals::{DescordError, DescordResult, HandlerResult};
+pub use internals::{
+    DescordError, DescordResult, EventHandler, HandlerResult, HandlerValue, State,
+};
 
 pub(crate) mod cache;
 
@@ -117,18 +124,25 @@ pub mod prelude {
     pub use super::{
         consts::permissions,
         consts::ButtonStyle,
+        consts::ChannelType,
         consts::ComponentType,
         consts::ImageFormat,
         consts::SelectMenuType,
         consts::intents::GatewayIntent,
         consts::color::Color,
         models::{
-            channel::*, channel::*, component_builder::*, components::*, embed::*,
+            channel::*, channel::*, channel_builder::*, component_builder::*, components::*,
+            deleted_message_response::{BulkDeletedMessages, DeletedMessage}, embed::*,
             embed_builder::*, guild::*, interaction::*, message_response::CreateMessageData,
-            message_response::Message, reaction_response::Reaction, ready_response::*, role::Role,
-            role_response::*, user::User,
+            message_response::Message, presence::*, reaction_response::Reaction, ready_response::*,
+            role::Role, role_response::*, thread::*, user::User, webhook::*,
         },
-        Payload,
+        utils::{
+            MessageCursor, Purge, RateLimitEvent, RateLimitKind, RequestEvent, RestObserver,
+            RetryPolicy,
+        },
+        ClientBuilder, EventStream, GatewayEncoding, GatewayEvent, GuildMembersRequest, Http,
+        Payload, ReconnectPolicy, ShardCount, ShardInfo, ShutdownHandle, WebhookClient,
         DescordError, DescordResult, HandlerResult,
     };
 }
@@ -15,7 +15,7 @@ async fn main() {
 
     register_all!(client => []);
 
-    client.login().await;
+    client.login().await.unwrap();
 }
 
 #[event]
@@ -1,4 +1,12 @@
-use nanoserde::{DeJson, SerJson};
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use nanoserde::{DeJson, DeJsonErr, DeJsonState, SerJson, SerJsonState};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::utils::FileData;
 
 /// Represents an attachment in a message.
 #[derive(Debug, DeJson, SerJson, Clone)]
@@ -34,35 +42,353 @@ pub struct Attachment {
 }
 
 /// Represents the payload for an attachment.
-#[derive(Debug, DeJson, SerJson, Clone)]
+#[derive(Debug, Clone)]
 pub struct AttachmentPayload {
     /// The filename of the attachment.
     pub file_name: String,
-    /// The file path of the attachment.
+    /// The file path of the attachment, empty if the content doesn't come from a file.
     pub file_path: String,
-    /// The MIME type of the attachment.
+    /// The MIME type of the attachment, guessed from the filename if empty.
     pub mime_type: String,
+    /// The description (alt text) of the attachment.
+    pub(crate) description: Option<String>,
+    /// Whether the attachment is hidden behind a spoiler.
+    pub(crate) spoiler: bool,
+    /// The content of the attachment, read from `file_path` if `None`.
+    source: Option<AttachmentSource>,
+}
+
+/// The json form of an [`AttachmentPayload`], the content is not serialized.
+#[derive(DeJson, SerJson)]
+struct AttachmentPayloadJson {
+    file_name: String,
+    file_path: String,
+    mime_type: String,
+    description: Option<String>,
+    #[nserde(default)]
+    spoiler: bool,
+}
+
+impl SerJson for AttachmentPayload {
+    fn ser_json(&self, d: usize, s: &mut SerJsonState) {
+        AttachmentPayloadJson {
+            file_name: self.file_name.clone(),
+            file_path: self.file_path.clone(),
+            mime_type: self.mime_type.clone(),
+            description: self.description.clone(),
+            spoiler: self.spoiler,
+        }
+        .ser_json(d, s)
+    }
+}
+
+/// Only attachments read from a file can be deserialized, the content of the others is lost.
+impl DeJson for AttachmentPayload {
+    fn de_json(s: &mut DeJsonState, i: &mut std::str::Chars) -> Result<Self, DeJsonErr> {
+        let json = AttachmentPayloadJson::de_json(s, i)?;
+        if json.file_path.is_empty() {
+            return Err(s.err_parse("attachment without a file path"));
+        }
+
+        Ok(Self {
+            description: json.description,
+            spoiler: json.spoiler,
+            ..Self::new(&json.file_name, &json.file_path, &json.mime_type)
+        })
+    }
+}
+
+/// The content of an [`AttachmentPayload`] that isn't read from a file.
+#[derive(Debug, Clone)]
+enum AttachmentSource {
+    /// Content held in memory.
+    Bytes(Bytes),
+    /// A stream, read while the attachment is sent, see [`AttachmentPayload::from_reader`].
+    Reader(AttachmentReader),
+}
+
+type BoxedReader = Pin<Box<dyn AsyncRead + Send + Sync>>;
+
+/// A stream sent as the content of an attachment, it can only be sent once.
+#[derive(Clone)]
+pub struct AttachmentReader(Arc<Mutex<Option<BoxedReader>>>);
+
+impl AttachmentReader {
+    /// Takes the stream out, `None` if it was already sent.
+    pub(crate) fn take(&self) -> Option<BoxedReader> {
+        self.0.lock().unwrap().take()
+    }
+}
+
+impl std::fmt::Debug for AttachmentReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AttachmentReader").finish_non_exhaustive()
+    }
 }
 
 impl AttachmentPayload {
-    /// Creates a new `AttachmentPayload`.
+    /// Creates a new `AttachmentPayload` from a file.
     ///
     /// # Arguments
     ///
     /// * `file_name` - The filename of the attachment.
     /// * `file_path` - The file path of the attachment.
-    /// * `mime_type` - The MIME type of the attachment.
+    /// * `mime_type` - The MIME type of the attachment, guessed from the filename if empty.
     ///
     /// # Examples
     ///
-    /// ```
+    /// ```ignore
     /// let payload = AttachmentPayload::new("file.txt", "/path/to/file.txt", "text/plain");
     /// ```
     pub fn new(file_name: &str, file_path: &str, mime_type: &str) -> Self {
         AttachmentPayload {
-            mime_type: mime_type.to_owned(),
             file_name: file_name.to_owned(),
             file_path: file_path.to_owned(),
+            mime_type: mime_type.to_owned(),
+            description: None,
+            spoiler: false,
+            source: None,
         }
     }
+
+    /// Creates a new `AttachmentPayload` from a file, named after the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the file.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let payload = AttachmentPayload::from_path("images/cat.png");
+    /// ```
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+
+        Self::new(&file_name, &path.to_string_lossy(), "")
+    }
+
+    /// Creates a new `AttachmentPayload` from content in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_name` - The filename of the attachment.
+    /// * `data` - The content of the attachment.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let payload = AttachmentPayload::from_bytes("chart.png", render_chart());
+    /// ```
+    pub fn from_bytes(file_name: &str, data: impl Into<Bytes>) -> Self {
+        Self::with_source(file_name, AttachmentSource::Bytes(data.into()))
+    }
+
+    /// Creates a new `AttachmentPayload` streaming its content from a reader.
+    ///
+    /// The reader is read while the request is sent, without buffering the whole content.
+    /// Since it can only be read once, a request with this attachment is neither retried
+    /// after a server error nor sent again after hitting a rate limit, the request fails instead.
+    /// Use [`from_reader_buffered`](Self::from_reader_buffered) for requests that must be retried.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_name` - The filename of the attachment.
+    /// * `reader` - The stream to send.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let stdout = render.stdout.take().unwrap();
+    /// let payload = AttachmentPayload::from_reader("chart.png", stdout);
+    /// ```
+    pub fn from_reader(file_name: &str, reader: impl AsyncRead + Send + Sync + 'static) -> Self {
+        let reader = AttachmentReader(Arc::new(Mutex::new(Some(Box::pin(reader)))));
+        Self::with_source(file_name, AttachmentSource::Reader(reader))
+    }
+
+    /// Creates a new `AttachmentPayload` by reading a stream to the end.
+    ///
+    /// The content isn't streamed to discord: the whole stream is buffered in memory before
+    /// this returns, so the attachment can be sent again if the request is retried. Use
+    /// [`from_path`](Self::from_path) for files, they're streamed from disk when the message is sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_name` - The filename of the attachment.
+    /// * `reader` - The stream to read.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the stream if reading it fails.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let stdout = render.stdout.take().unwrap();
+    /// let payload = AttachmentPayload::from_reader_buffered("chart.png", stdout).await?;
+    /// ```
+    pub async fn from_reader_buffered(
+        file_name: &str,
+        mut reader: impl AsyncRead + Unpin,
+    ) -> std::io::Result<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).await?;
+
+        Ok(Self::from_bytes(file_name, data))
+    }
+
+    /// Sets the MIME type of the attachment.
+    ///
+    /// # Arguments
+    ///
+    /// * `mime_type` - The MIME type, an empty string guesses it from the filename.
+    pub fn mime_type(mut self, mime_type: &str) -> Self {
+        self.mime_type = mime_type.to_owned();
+        self
+    }
+
+    /// Sets the description (alt text) of the attachment.
+    ///
+    /// # Arguments
+    ///
+    /// * `description` - The description.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let payload = AttachmentPayload::from_path("cat.png").description("A cat sleeping");
+    /// ```
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_owned());
+        self
+    }
+
+    /// Sets whether the attachment is hidden behind a spoiler.
+    ///
+    /// # Arguments
+    ///
+    /// * `spoiler` - Whether to mark the attachment as a spoiler.
+    pub fn spoiler(mut self, spoiler: bool) -> Self {
+        self.spoiler = spoiler;
+        self
+    }
+
+    /// Returns the filename sent to discord, spoilers are prefixed with `SPOILER_`.
+    pub(crate) fn upload_name(&self) -> String {
+        if self.spoiler && !self.file_name.starts_with("SPOILER_") {
+            format!("SPOILER_{}", self.file_name)
+        } else {
+            self.file_name.clone()
+        }
+    }
+
+    /// Returns the MIME type, guessing it from the filename if it wasn't set.
+    pub(crate) fn guess_mime_type(&self) -> String {
+        if !self.mime_type.is_empty() {
+            return self.mime_type.clone();
+        }
+
+        mime_guess::from_path(&self.file_name)
+            .first_or_octet_stream()
+            .to_string()
+    }
+
+    /// Returns the content of the attachment to send, files are checked but not read yet.
+    pub(crate) async fn load(&self) -> std::io::Result<FileData> {
+        match &self.source {
+            None => {
+                let length = tokio::fs::metadata(&self.file_path).await?.len();
+                Ok(FileData::File {
+                    path: self.file_path.clone().into(),
+                    length,
+                })
+            }
+            Some(AttachmentSource::Bytes(data)) => Ok(FileData::Bytes(data.clone())),
+            Some(AttachmentSource::Reader(reader)) => Ok(FileData::Reader(reader.clone())),
+        }
+    }
+
+    fn with_source(file_name: &str, source: AttachmentSource) -> Self {
+        Self {
+            source: Some(source),
+            ..Self::new(file_name, "", "")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spoilers_are_prefixed_once() {
+        let payload = AttachmentPayload::from_bytes("cat.png", "").spoiler(true);
+        assert_eq!(payload.upload_name(), "SPOILER_cat.png");
+
+        let payload = AttachmentPayload::from_bytes("SPOILER_cat.png", "").spoiler(true);
+        assert_eq!(payload.upload_name(), "SPOILER_cat.png");
+
+        let payload = AttachmentPayload::from_bytes("cat.png", "");
+        assert_eq!(payload.upload_name(), "cat.png");
+    }
+
+    #[test]
+    fn mime_types_are_guessed_from_the_filename() {
+        let payload = AttachmentPayload::from_bytes("cat.png", "");
+        assert_eq!(payload.guess_mime_type(), "image/png");
+
+        let payload = AttachmentPayload::from_bytes("data.unknown-extension", "");
+        assert_eq!(payload.guess_mime_type(), "application/octet-stream");
+
+        let payload = AttachmentPayload::from_bytes("cat.png", "").mime_type("image/webp");
+        assert_eq!(payload.guess_mime_type(), "image/webp");
+
+        let payload = AttachmentPayload::from_bytes("cat.png", "").mime_type("");
+        assert_eq!(payload.guess_mime_type(), "image/png");
+    }
+
+    #[test]
+    fn file_payloads_keep_their_fields() {
+        let payload = AttachmentPayload::new("file.txt", "/path/to/file.txt", "text/plain");
+        assert_eq!(payload.file_name, "file.txt");
+        assert_eq!(payload.file_path, "/path/to/file.txt");
+        assert_eq!(payload.mime_type, "text/plain");
+
+        let payload = AttachmentPayload::from_bytes("cat.png", "");
+        assert_eq!(payload.file_path, "");
+    }
+
+    #[test]
+    fn file_payloads_are_deserialized_with_their_fields() {
+        let payload = AttachmentPayload::new("cat.png", "/tmp/cat.png", "image/png")
+            .description("A cat sleeping")
+            .spoiler(true);
+
+        let json = payload.serialize_json();
+        let payload = AttachmentPayload::deserialize_json(&json).unwrap();
+        assert_eq!(payload.file_name, "cat.png");
+        assert_eq!(payload.file_path, "/tmp/cat.png");
+        assert_eq!(payload.mime_type, "image/png");
+        assert_eq!(payload.description.as_deref(), Some("A cat sleeping"));
+        assert!(payload.spoiler);
+
+        // the fields written before descriptions and spoilers existed
+        let json = r#"{"file_name":"cat.png","file_path":"/tmp/cat.png","mime_type":""}"#;
+        let payload = AttachmentPayload::deserialize_json(json).unwrap();
+        assert_eq!(payload.file_path, "/tmp/cat.png");
+        assert!(!payload.spoiler);
+    }
+
+    #[test]
+    fn payloads_without_a_file_are_not_deserialized() {
+        let json = AttachmentPayload::from_bytes("cat.png", "meow").serialize_json();
+
+        // an empty file would be uploaded instead of the content
+        assert!(AttachmentPayload::deserialize_json(&json).is_err());
+    }
 }
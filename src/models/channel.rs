@@ -1,8 +1,16 @@
+use std::sync::Arc;
+
+use crate::client::{Attach, Http};
 use crate::internals::DescordError;
-use crate::utils;
+use crate::utils::{self, MessageCursor};
+use futures_util::stream::BoxStream;
 use nanoserde::{DeJson, SerJson};
 
+use super::channel_builder::ModifyChannel;
 use super::message_response::{CreateMessageData, Message};
+use super::thread::{
+    CreateForumPost, DefaultReaction, ForumTag, StartThread, ThreadMember, ThreadMetadata,
+};
 
 /// Represents a Discord channel.
 #[derive(DeJson, SerJson, Debug, Clone, Default)]
@@ -54,8 +62,10 @@ pub struct Channel {
     pub message_count: Option<u32>,
     /// The member count in the channel.
     pub member_count: Option<u32>,
-    // pub thread_metadata: Option<ThreadMetadata>,
-    // pub member: Option<ThreadMember>,
+    /// The thread specific fields (if it's a thread).
+    pub thread_metadata: Option<ThreadMetadata>,
+    /// The thread member of the bot (if it's a thread the bot joined).
+    pub member: Option<ThreadMember>,
     /// The default auto-archive duration for threads in the channel.
     pub default_auto_archive_duration: Option<u32>,
     /// The permissions of the channel.
@@ -64,10 +74,12 @@ pub struct Channel {
     pub flags: Option<usize>,
     /// The total number of messages sent in the channel.
     pub total_message_sent: Option<u32>,
-    // pub available_tags: Option<Vec<Tag>>,
+    /// The tags that can be applied to posts (if it's a forum or media channel).
+    pub available_tags: Option<Vec<ForumTag>>,
     /// The applied tags in the channel.
     pub applied_tags: Option<Vec<String>>,
-    // pub default_reaction_emoji: Option<DefaultReaction>,
+    /// The emoji reacted to new posts by default (if it's a forum or media channel).
+    pub default_reaction_emoji: Option<DefaultReaction>,
     /// The default rate limit per user for threads in the channel.
     pub default_thread_rate_limit_per_user: Option<u32>,
     /// The default sort order for threads in the channel.
@@ -77,6 +89,16 @@ pub struct Channel {
     /// The mention string for the channel.
     #[nserde(default)]
     pub mention: String,
+    /// The client that received the channel.
+    #[nserde(skip)]
+    pub(crate) http: Option<Arc<Http>>,
+}
+
+impl Attach for Channel {
+    fn attach(&mut self, http: &Arc<Http>) {
+        self.http = Some(Arc::clone(http));
+        self.member.attach(http);
+    }
 }
 
 /// Represents a permission overwrite for a channel.
@@ -93,6 +115,55 @@ pub struct Overwrite {
     pub deny: String,
 }
 
+impl Overwrite {
+    /// Creates a permission overwrite for a role.
+    ///
+    /// # Arguments
+    ///
+    /// * `role_id` - The ID of the role, the guild ID for `@everyone`.
+    /// * `allow` - The allowed permissions, see [`permissions`](crate::consts::permissions).
+    /// * `deny` - The denied permissions.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let hidden = Overwrite::role(&guild_id, 0, permissions::VIEW_CHANNEL);
+    /// ```
+    pub fn role(role_id: &str, allow: u64, deny: u64) -> Self {
+        Self {
+            id: role_id.to_owned(),
+            overwrite_type: 0,
+            allow: allow.to_string(),
+            deny: deny.to_string(),
+        }
+    }
+
+    /// Creates a permission overwrite for a member.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` - The ID of the member.
+    /// * `allow` - The allowed permissions, see [`permissions`](crate::consts::permissions).
+    /// * `deny` - The denied permissions.
+    pub fn member(user_id: &str, allow: u64, deny: u64) -> Self {
+        Self {
+            id: user_id.to_owned(),
+            overwrite_type: 1,
+            allow: allow.to_string(),
+            deny: deny.to_string(),
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> json::JsonValue {
+        json::object! {
+            id: self.id.as_str(),
+            type: self.overwrite_type,
+            allow: self.allow.as_str(),
+            deny: self.deny.as_str(),
+        }
+    }
+}
+
 impl Channel {
     /// Sends a message to the channel.
     ///
@@ -106,7 +177,7 @@ impl Channel {
     /// channel.send_message("Hello, world!").await;
     /// ```
     pub async fn send_message(&self, data: impl Into<CreateMessageData>) -> Result<Message, DescordError> {
-        utils::send(&self.id, None, data).await
+        Http::with(&self.http, utils::send(&self.id, None, data)).await
     }
 
     /// Sends a typing indicator to the channel.
@@ -117,7 +188,125 @@ impl Channel {
     /// channel.send_typing().await;
     /// ```
     pub async fn send_typing(&self) -> Result<(), DescordError> {
-        utils::send_typing(&self.id).await?;
+        Http::with(&self.http, utils::send_typing(&self.id)).await?;
         Ok(())
     }
+
+    /// Modifies the channel, only the fields set in `data` are changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The changes.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let channel = channel.modify(ModifyChannel::new().topic("Closed")).await?;
+    /// ```
+    pub async fn modify(&self, data: ModifyChannel) -> Result<Channel, DescordError> {
+        Http::with(&self.http, utils::modify_channel(&self.id, data)).await
+    }
+
+    /// Deletes the channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - The reason shown in the audit log.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// channel.delete(Some("Ticket closed")).await?;
+    /// ```
+    pub async fn delete(&self, reason: Option<&str>) -> Result<(), DescordError> {
+        Http::with(&self.http, utils::delete_channel(&self.id, reason)).await
+    }
+
+    /// Fetches messages of the channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `cursor` - Which messages to fetch, the latest messages if `None`.
+    /// * `limit` - The number of messages, between 1 and 100, 50 by default.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let messages = channel.fetch_messages(None, Some(10)).await?;
+    /// ```
+    pub async fn fetch_messages(
+        &self,
+        cursor: Option<MessageCursor>,
+        limit: Option<usize>,
+    ) -> Result<Vec<Message>, DescordError> {
+        Http::with(&self.http, utils::get_channel_messages(&self.id, cursor, limit)).await
+    }
+
+    /// Returns a stream of every message in the channel, newest first.
+    ///
+    /// See [`utils::channel_history`].
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let mut history = channel.history();
+    ///
+    /// while let Some(message) = history.next().await {
+    ///     println!("{}", message?.content);
+    /// }
+    /// ```
+    pub fn history(&self) -> BoxStream<'static, Result<Message, DescordError>> {
+        utils::history(&self.id, None, self.http.clone())
+    }
+
+    /// Starts a thread in the channel that isn't connected to a message, see [`utils::start_thread`].
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The thread.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let thread = channel
+    ///     .start_thread(StartThread::new("Standup").channel_type(ChannelType::PublicThread))
+    ///     .await?;
+    /// ```
+    pub async fn start_thread(&self, data: StartThread) -> Result<Channel, DescordError> {
+        Http::with(&self.http, utils::start_thread(&self.id, data)).await
+    }
+
+    /// Creates a post in the forum or media channel, see [`utils::create_forum_post`].
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The post.
+    pub async fn create_forum_post(&self, data: CreateForumPost) -> Result<Channel, DescordError> {
+        Http::with(&self.http, utils::create_forum_post(&self.id, data)).await
+    }
+
+    /// Returns the tag of the forum or media channel with the given name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the tag.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let tags = forum.tag("bug").map(|tag| vec![tag.id.clone()]).unwrap_or_default();
+    /// ```
+    pub fn tag(&self, name: &str) -> Option<&ForumTag> {
+        self.available_tags.as_ref()?.iter().find(|tag| tag.name == name)
+    }
+
+    /// Adds the bot to the thread.
+    pub async fn join_thread(&self) -> Result<(), DescordError> {
+        Http::with(&self.http, utils::join_thread(&self.id)).await
+    }
+
+    /// Removes the bot from the thread.
+    pub async fn leave_thread(&self) -> Result<(), DescordError> {
+        Http::with(&self.http, utils::leave_thread(&self.id)).await
+    }
 }
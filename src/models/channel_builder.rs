@@ -0,0 +1,396 @@
+use json::{object, JsonValue};
+
+use crate::consts::ChannelType;
+
+use super::channel::{Channel, Overwrite};
+
+/// A builder for creating a guild channel, see [`utils::create_channel`](crate::utils::create_channel).
+#[derive(Debug, Clone)]
+pub struct CreateChannel {
+    pub(crate) body: JsonValue,
+    pub(crate) reason: Option<String>,
+}
+
+impl CreateChannel {
+    /// Creates a new `CreateChannel`, a text channel by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the channel, 1-100 characters.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let channel = utils::create_channel(
+    ///     &guild_id,
+    ///     CreateChannel::new("ticket-42")
+    ///         .parent(&tickets_category)
+    ///         .topic("Support ticket of @user")
+    ///         .reason("Ticket opened"),
+    /// )
+    /// .await?;
+    /// ```
+    pub fn new(name: &str) -> Self {
+        Self {
+            body: object! { name: name },
+            reason: None,
+        }
+    }
+
+    /// Sets the type of the channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel_type` - The type, e.g. `ChannelType::GuildVoice`.
+    pub fn channel_type(mut self, channel_type: ChannelType) -> Self {
+        self.body["type"] = (channel_type as u32).into();
+        self
+    }
+
+    /// Sets the topic of the channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic, 0-1024 characters.
+    pub fn topic(mut self, topic: &str) -> Self {
+        self.body["topic"] = topic.into();
+        self
+    }
+
+    /// Sets the category the channel is created in.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent_id` - The ID of the category.
+    pub fn parent(mut self, parent_id: &str) -> Self {
+        self.body["parent_id"] = parent_id.into();
+        self
+    }
+
+    /// Sets the sorting position of the channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position.
+    pub fn position(mut self, position: usize) -> Self {
+        self.body["position"] = position.into();
+        self
+    }
+
+    /// Sets the permission overwrites of the channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `overwrites` - The overwrites, see [`Overwrite::role`] and [`Overwrite::member`].
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let builder = CreateChannel::new("ticket-42").permission_overwrites(vec![
+    ///     Overwrite::role(&guild_id, 0, permissions::VIEW_CHANNEL),
+    ///     Overwrite::member(&user_id, permissions::VIEW_CHANNEL, 0),
+    /// ]);
+    /// ```
+    pub fn permission_overwrites(mut self, overwrites: Vec<Overwrite>) -> Self {
+        self.body["permission_overwrites"] = overwrites_json(&overwrites);
+        self
+    }
+
+    /// Sets the slowmode of the channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `seconds` - Seconds a user has to wait between messages, 0-21600.
+    pub fn rate_limit_per_user(mut self, seconds: u32) -> Self {
+        self.body["rate_limit_per_user"] = seconds.into();
+        self
+    }
+
+    /// Sets the bitrate of a voice channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `bitrate` - The bitrate in bits per second, at least 8000.
+    pub fn bitrate(mut self, bitrate: u32) -> Self {
+        self.body["bitrate"] = bitrate.into();
+        self
+    }
+
+    /// Sets the user limit of a voice channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The maximum number of users, 0 for no limit.
+    pub fn user_limit(mut self, limit: u32) -> Self {
+        self.body["user_limit"] = limit.into();
+        self
+    }
+
+    /// Sets whether the channel is age-restricted.
+    ///
+    /// # Arguments
+    ///
+    /// * `nsfw` - Whether the channel is NSFW.
+    pub fn nsfw(mut self, nsfw: bool) -> Self {
+        self.body["nsfw"] = nsfw.into();
+        self
+    }
+
+    /// Sets the reason shown in the audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - The reason.
+    pub fn reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_owned());
+        self
+    }
+}
+
+/// A builder for modifying a guild channel, see [`utils::modify_channel`](crate::utils::modify_channel).
+///
+/// Only the fields that were set are changed.
+#[derive(Debug, Clone)]
+pub struct ModifyChannel {
+    pub(crate) body: JsonValue,
+    pub(crate) reason: Option<String>,
+}
+
+impl Default for ModifyChannel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModifyChannel {
+    /// Creates a new `ModifyChannel` that doesn't change anything yet.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// utils::modify_channel(
+    ///     &channel_id,
+    ///     ModifyChannel::new()
+    ///         .name("closed-ticket-42")
+    ///         .rate_limit_per_user(30)
+    ///         .reason("Ticket closed"),
+    /// )
+    /// .await?;
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            body: object! {},
+            reason: None,
+        }
+    }
+
+    /// Sets the name of the channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name, 1-100 characters.
+    pub fn name(mut self, name: &str) -> Self {
+        self.body["name"] = name.into();
+        self
+    }
+
+    /// Converts the channel between a text and an announcement channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel_type` - `ChannelType::GuildText` or `ChannelType::GuildAnnouncement`.
+    pub fn channel_type(mut self, channel_type: ChannelType) -> Self {
+        self.body["type"] = (channel_type as u32).into();
+        self
+    }
+
+    /// Sets the topic of the channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `topic` - The topic, 0-1024 characters.
+    pub fn topic(mut self, topic: &str) -> Self {
+        self.body["topic"] = topic.into();
+        self
+    }
+
+    /// Moves the channel into a category.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent_id` - The ID of the category, `None` to move the channel out of its category.
+    pub fn parent(mut self, parent_id: Option<&str>) -> Self {
+        self.body["parent_id"] = parent_id.into();
+        self
+    }
+
+    /// Sets the sorting position of the channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position.
+    pub fn position(mut self, position: usize) -> Self {
+        self.body["position"] = position.into();
+        self
+    }
+
+    /// Replaces every permission overwrite of the channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `overwrites` - The overwrites, see [`Overwrite::role`] and [`Overwrite::member`].
+    pub fn permission_overwrites(mut self, overwrites: Vec<Overwrite>) -> Self {
+        self.body["permission_overwrites"] = overwrites_json(&overwrites);
+        self
+    }
+
+    /// Sets the slowmode of the channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `seconds` - Seconds a user has to wait between messages, 0-21600.
+    pub fn rate_limit_per_user(mut self, seconds: u32) -> Self {
+        self.body["rate_limit_per_user"] = seconds.into();
+        self
+    }
+
+    /// Sets the bitrate of a voice channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `bitrate` - The bitrate in bits per second, at least 8000.
+    pub fn bitrate(mut self, bitrate: u32) -> Self {
+        self.body["bitrate"] = bitrate.into();
+        self
+    }
+
+    /// Sets the user limit of a voice channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The maximum number of users, 0 for no limit.
+    pub fn user_limit(mut self, limit: u32) -> Self {
+        self.body["user_limit"] = limit.into();
+        self
+    }
+
+    /// Sets whether the channel is age-restricted.
+    ///
+    /// # Arguments
+    ///
+    /// * `nsfw` - Whether the channel is NSFW.
+    pub fn nsfw(mut self, nsfw: bool) -> Self {
+        self.body["nsfw"] = nsfw.into();
+        self
+    }
+
+    /// Sets the reason shown in the audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - The reason.
+    pub fn reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_owned());
+        self
+    }
+}
+
+impl From<&Channel> for ModifyChannel {
+    /// Takes the modifiable fields of a channel, read-only fields are left out.
+    fn from(channel: &Channel) -> Self {
+        let mut modify = Self::new();
+
+        if let Some(name) = &channel.name {
+            modify = modify.name(name);
+        }
+
+        if let Some(topic) = &channel.topic {
+            modify = modify.topic(topic);
+        }
+
+        if let Some(position) = channel.position {
+            modify = modify.position(position);
+        }
+
+        if let Some(overwrites) = &channel.permission_overwrites {
+            modify.body["permission_overwrites"] = overwrites_json(overwrites);
+        }
+
+        if let Some(nsfw) = channel.nsfw {
+            modify = modify.nsfw(nsfw);
+        }
+
+        if let Some(seconds) = channel.rate_limit_per_user {
+            modify.body["rate_limit_per_user"] = seconds.into();
+        }
+
+        if let Some(bitrate) = channel.bitrate {
+            modify = modify.bitrate(bitrate);
+        }
+
+        if let Some(limit) = channel.user_limit {
+            modify = modify.user_limit(limit);
+        }
+
+        if channel.parent_id.is_some() {
+            modify = modify.parent(channel.parent_id.as_deref());
+        }
+
+        modify
+    }
+}
+
+/// The new position of a channel, see [`utils::reorder_channels`](crate::utils::reorder_channels).
+#[derive(Debug, Clone, Default)]
+pub struct ChannelPosition {
+    /// The ID of the channel.
+    pub id: String,
+    /// The new sorting position, unchanged if `None`.
+    pub position: Option<usize>,
+    /// Whether to sync the permissions with the new category.
+    pub lock_permissions: Option<bool>,
+    /// The new category, unchanged if `None`.
+    pub parent_id: Option<String>,
+}
+
+impl ChannelPosition {
+    /// Creates a new `ChannelPosition` moving a channel to a position.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the channel.
+    /// * `position` - The new sorting position.
+    pub fn new(id: &str, position: usize) -> Self {
+        Self {
+            id: id.to_owned(),
+            position: Some(position),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> JsonValue {
+        let mut json = object! { id: self.id.as_str() };
+
+        if let Some(position) = self.position {
+            json["position"] = position.into();
+        }
+
+        if let Some(lock_permissions) = self.lock_permissions {
+            json["lock_permissions"] = lock_permissions.into();
+        }
+
+        if let Some(parent_id) = &self.parent_id {
+            json["parent_id"] = parent_id.as_str().into();
+        }
+
+        json
+    }
+}
+
+fn overwrites_json(overwrites: &[Overwrite]) -> JsonValue {
+    overwrites
+        .iter()
+        .map(Overwrite::to_json)
+        .collect::<Vec<_>>()
+        .into()
+}
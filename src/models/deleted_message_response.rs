@@ -1,4 +1,8 @@
 use nanoserde::{DeJson, SerJson};
+use std::sync::Arc;
+
+use super::message_response::Message;
+use crate::client::{Attach, Http};
 
 /// Represents a response for a deleted message.
 #[derive(DeJson, SerJson, Debug)]
@@ -19,3 +23,23 @@ pub struct DeletedMessage {
     /// The ID of the guild where the message was deleted.
     pub guild_id: String,
 }
+
+/// Represents messages deleted at once, e.g. with [`utils::bulk_delete_messages`](crate::utils::bulk_delete_messages).
+#[derive(DeJson, SerJson, Debug, Clone)]
+pub struct BulkDeletedMessages {
+    /// The IDs of the deleted messages.
+    pub ids: Vec<String>,
+    /// The ID of the channel where the messages were deleted.
+    pub channel_id: String,
+    /// The ID of the guild where the messages were deleted.
+    pub guild_id: Option<String>,
+    /// The deleted messages that were in the message cache.
+    #[nserde(default)]
+    pub cached: Vec<Message>,
+}
+
+impl Attach for BulkDeletedMessages {
+    fn attach(&mut self, http: &Arc<Http>) {
+        self.cached.attach(http);
+    }
+}
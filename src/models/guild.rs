@@ -1,11 +1,13 @@
 use super::message_response::CreateMessageData;
-use super::{channel::Channel, user::User};
+use super::{channel::Channel, presence::MemberPresence, user::User};
+use crate::client::{Attach, Http};
 use crate::consts::DISCORD_CDN;
 use crate::internals::DescordError;
 use crate::prelude::Role;
 use crate::{prelude::ImageFormat, utils};
 use nanoserde::{DeJson, SerJson};
 use reqwest::Method;
+use std::sync::Arc;
 
 use crate::models::message_response::Message;
 
@@ -78,9 +80,18 @@ pub struct Guild {
     pub boost_progress_bar: bool,
     /// The ID of the safety alerts channel.
     pub safety_alerts_channel_id: Option<String>,
+    /// The client that received the guild.
+    #[nserde(skip)]
+    pub(crate) http: Option<Arc<Http>>,
     // TODO: permissions, roles, welcome_screen, sticker
 }
 
+impl Attach for Guild {
+    fn attach(&mut self, http: &Arc<Http>) {
+        self.http = Some(Arc::clone(http));
+    }
+}
+
 /// Represents the response for a guild creation.
 #[derive(DeJson, SerJson)]
 pub struct GuildCreateResponse {
@@ -174,6 +185,14 @@ pub struct GuildCreate {
     pub threads: Vec<Channel>,
 }
 
+impl Attach for GuildCreate {
+    fn attach(&mut self, http: &Arc<Http>) {
+        self.members.attach(http);
+        self.channels.attach(http);
+        self.threads.attach(http);
+    }
+}
+
 /// Represents a partial guild (unavailable guild).
 #[doc(alias = "UnavailableGuild")]
 #[derive(DeJson, SerJson, Debug, Clone)]
@@ -242,6 +261,43 @@ pub struct Member {
     /// Id of the guild, available in guild member update events.
     #[nserde(default)]
     pub guild_id: Option<String>,
+
+    /// The presence of the member, only available when requested
+    /// with [`GuildMembersRequest::presences`](crate::GuildMembersRequest::presences).
+    #[nserde(default)]
+    pub presence: Option<MemberPresence>,
+
+    /// The client that received the member.
+    #[nserde(skip)]
+    pub(crate) http: Option<Arc<Http>>,
+}
+
+impl Attach for Member {
+    fn attach(&mut self, http: &Arc<Http>) {
+        self.http = Some(Arc::clone(http));
+    }
+}
+
+/// A chunk of members sent in response to a guild members request.
+#[derive(DeJson, SerJson, Debug, Clone)]
+pub struct GuildMembersChunk {
+    pub guild_id: String,
+    pub members: Vec<Member>,
+    pub chunk_index: u32,
+    pub chunk_count: u32,
+    /// Ids passed in the request that weren't found.
+    #[nserde(default)]
+    pub not_found: Vec<String>,
+    #[nserde(default)]
+    pub presences: Vec<MemberPresence>,
+    #[nserde(default)]
+    pub nonce: Option<String>,
+}
+
+impl Attach for GuildMembersChunk {
+    fn attach(&mut self, http: &Arc<Http>) {
+        self.members.attach(http);
+    }
 }
 
 impl Guild {
@@ -257,7 +313,25 @@ impl Guild {
     /// let member = guild.fetch_member("user_id").await?;
     /// ```
     pub async fn fetch_member(&self, user_id: &str) -> Result<Member, DescordError> {
-        utils::fetch_member(&self.id, user_id).await
+        Http::with(&self.http, utils::fetch_member(&self.id, user_id)).await
+    }
+
+    /// Requests members of the guild over the gateway.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - Which members to request.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let members = guild.request_members(GuildMembersRequest::query("ab", 10)).await?;
+    /// ```
+    pub async fn request_members(
+        &self,
+        request: crate::GuildMembersRequest,
+    ) -> Result<Vec<Member>, DescordError> {
+        crate::request_guild_members(&self.id, request).await
     }
 
     /// Fetches a role of the guild by role ID.
@@ -272,7 +346,7 @@ impl Guild {
     /// let role = guild.fetch_role("role_id").await?;
     /// ```
     pub async fn fetch_role(&self, role_id: &str) -> Result<Role, DescordError> {
-        utils::fetch_role(&self.id, role_id).await
+        Http::with(&self.http, utils::fetch_role(&self.id, role_id)).await
     }
 
     /// Fetches the default role of the guild.
@@ -283,7 +357,7 @@ impl Guild {
     /// let default_role = guild.default_role().await?;
     /// ```
     pub async fn default_role(&self) -> Result<Role, DescordError> {
-        utils::fetch_role(&self.id, &self.id).await
+        Http::with(&self.http, utils::fetch_role(&self.id, &self.id)).await
     }
 }
 
@@ -332,6 +406,6 @@ impl Member {
     /// member.send_dm("Hello, world!").await;
     /// ```
     pub async fn send_dm(&self, data: impl Into<CreateMessageData>) -> Result<Message, DescordError> {
-        utils::send_dm(&self.user.as_ref().unwrap().id, data).await
+        Http::with(&self.http, utils::send_dm(&self.user.as_ref().unwrap().id, data)).await
     }
 }
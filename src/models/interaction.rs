@@ -1,12 +1,14 @@
 use json::JsonValue;
 use std::collections::HashMap;
+use std::sync::Arc;
 
+use crate::client::{Attach, Http};
 use crate::consts::*;
 use crate::internals::DescordError;
 use crate::models::allowed_mentions::AllowedMentions;
 use crate::models::guild::Member;
 use crate::prelude::{Component, Embed};
-use crate::utils::request;
+use crate::utils::{request, request_message};
 use nanoserde::{DeJson, SerJson};
 use reqwest::Method;
 
@@ -56,6 +58,18 @@ pub struct Interaction {
     pub guild_id: String,
     /// The user who invoked the interaction, if in a DM.
     pub user: Option<User>,
+    /// The client that received the interaction.
+    #[nserde(skip)]
+    pub(crate) http: Option<Arc<Http>>,
+}
+
+impl Attach for Interaction {
+    fn attach(&mut self, http: &Arc<Http>) {
+        self.http = Some(Arc::clone(http));
+        self.channel.attach(http);
+        self.member.attach(http);
+        self.message.attach(http);
+    }
 }
 
 impl Interaction {
@@ -81,16 +95,19 @@ impl Interaction {
             message_data.flags = Some(64);
         }
 
-        let response = InteractionResponse {
-            type_: 4,
-            data: Some(message_data),
+        let body = json::object! {
+            type: 4,
+            data: message_data.to_json_value(),
         };
-        let json_response = SerJson::serialize_json(&response);
 
-        request(
-            Method::POST,
-            format!("interactions/{}/{}/callback", self.id, self.token),
-            Some(json_response),
+        Http::with(
+            &self.http,
+            request_message(
+                Method::POST,
+                &format!("interactions/{}/{}/callback", self.id, self.token),
+                body,
+                &message_data.attachments,
+            ),
         )
         .await?;
         Ok(())
@@ -110,10 +127,13 @@ impl Interaction {
         };
         let json_response = SerJson::serialize_json(&response);
 
-        request(
-            Method::POST,
-            format!("interactions/{}/{}/callback", self.id, self.token),
-            Some(json_response),
+        Http::with(
+            &self.http,
+            request(
+                Method::POST,
+                format!("interactions/{}/{}/callback", self.id, self.token),
+                Some(json_response),
+            ),
         )
         .await?;
         Ok(())
@@ -123,25 +143,30 @@ impl Interaction {
     ///
     /// # Arguments
     ///
-    /// * `response` - The follow-up message content.
+    /// * `response` - The follow-up message data, it can have attachments.
     ///
     /// # Examples
     ///
     /// ```
     /// interaction.followup("Follow-up message").await;
     /// ```
-    pub async fn followup<S: AsRef<str>>(&self, response: S) -> Result<(), DescordError> {
-        request(
-            Method::POST,
-            format!("webhooks/{}/{}", self.application_id, self.token),
-            Some(
-                json::object! {
-                    content: response.as_ref(),
-                }
-                .dump(),
+    pub async fn followup(
+        &self,
+        response: impl Into<CreateMessageData>,
+    ) -> Result<(), DescordError> {
+        let response: CreateMessageData = response.into();
+
+        Http::with(
+            &self.http,
+            request_message(
+                Method::POST,
+                &format!("webhooks/{}/{}", self.application_id, self.token),
+                response.to_json_value(),
+                &response.attachments,
             ),
         )
         .await?;
+
         Ok(())
     }
 
@@ -162,13 +187,17 @@ impl Interaction {
     ) -> Result<(), DescordError> {
         let response: CreateMessageData = response.into();
 
-        request(
-            Method::PATCH,
-            format!(
-                "webhooks/{}/{}/messages/@original",
-                self.application_id, self.token
+        Http::with(
+            &self.http,
+            request_message(
+                Method::PATCH,
+                &format!(
+                    "webhooks/{}/{}/messages/@original",
+                    self.application_id, self.token
+                ),
+                response.to_json_value(),
+                &response.attachments,
             ),
-            Some(response.serialize_json()),
         )
         .await?;
 
@@ -183,14 +212,17 @@ impl Interaction {
     /// interaction.delete_original().await;
     /// ```
     pub async fn delete_original(&self) -> Result<(), DescordError> {
-        request(
-            Method::DELETE,
-            format!(
-                "webhooks/{}/{}/messages/@original",
-                self.application_id, self.token
-            )
-            .as_str(),
-            None,
+        Http::with(
+            &self.http,
+            request(
+                Method::DELETE,
+                format!(
+                    "webhooks/{}/{}/messages/@original",
+                    self.application_id, self.token
+                )
+                .as_str(),
+                None,
+            ),
         )
         .await?;
         Ok(())
@@ -1,6 +1,7 @@
 use json::object;
 use nanoserde::{DeJson, SerJson};
 use std::error::Error;
+use std::sync::Arc;
 
 use super::allowed_mentions::AllowedMentions;
 use super::attachment::{Attachment, AttachmentPayload};
@@ -8,6 +9,8 @@ use super::channel::Channel;
 use super::components::Component;
 use super::embed::Embed;
 use super::guild::{Guild, Member};
+use super::thread::StartThread;
+use crate::client::{Attach, Http};
 use crate::internals::DescordError;
 use crate::prelude::User;
 use crate::utils;
@@ -64,10 +67,21 @@ pub struct Message {
     pub attachments: Vec<Attachment>,
     /// The components of the message.
     pub components: Vec<Component>,
+    /// The client that received the message.
+    #[nserde(skip)]
+    pub(crate) http: Option<Arc<Http>>,
     // TODO
     // mentions, mention_roles, member, etc.
 }
 
+impl Attach for Message {
+    fn attach(&mut self, http: &Arc<Http>) {
+        self.http = Some(Arc::clone(http));
+        self.referenced_message.attach(http);
+        self.member.attach(http);
+    }
+}
+
 impl Message {
     /// Reply to the message.
     ///
@@ -81,7 +95,7 @@ impl Message {
     /// message.reply("Hello, world!").await;
     /// ```
     pub async fn reply(&self, data: impl Into<CreateMessageData>) -> Result<Message, DescordError> {
-        utils::send(&self.channel_id, Some(&self.id), data).await
+        Http::with(&self.http, utils::send(&self.channel_id, Some(&self.id), data)).await
     }
 
     /// Send a message in the same channel.
@@ -96,7 +110,7 @@ impl Message {
     /// message.send_in_channel("Hello, world!").await;
     /// ```
     pub async fn send_in_channel(&self, data: impl Into<CreateMessageData>) -> Result<Message, DescordError> {
-        utils::send(&self.channel_id, None, data).await
+        Http::with(&self.http, utils::send(&self.channel_id, None, data)).await
     }
 
     /// Get the current channel.
@@ -107,12 +121,12 @@ impl Message {
     /// let channel = message.get_channel().await?;
     /// ```
     pub async fn get_channel(&self) -> Result<Channel, DescordError> {
-        utils::fetch_channel(&self.channel_id).await
+        Http::with(&self.http, utils::fetch_channel(&self.channel_id)).await
     }
 
     /// Sends typing indicator.
     pub async fn send_typing(&self) -> Result<(), DescordError> {
-        utils::send_typing(&self.channel_id).await
+        Http::with(&self.http, utils::send_typing(&self.channel_id)).await
     }
 
     /// Get the message author.
@@ -123,9 +137,12 @@ impl Message {
     /// let author = message.get_author().await?;
     /// ```
     pub async fn get_author(&self) -> Result<Member, DescordError> {
-        utils::fetch_member(
-            self.guild_id.as_ref().unwrap(),
-            &self.author.as_ref().unwrap().id,
+        Http::with(
+            &self.http,
+            utils::fetch_member(
+                self.guild_id.as_ref().unwrap(),
+                &self.author.as_ref().unwrap().id,
+            ),
         )
         .await
     }
@@ -138,7 +155,8 @@ impl Message {
     /// let guild = message.get_guild().await?;
     /// ```
     pub async fn get_guild(&self) -> Result<Guild, DescordError> {
-        utils::fetch_guild(self.guild_id.as_ref().unwrap()).await
+        Http::with(&self.http, utils::fetch_guild(self.guild_id.as_ref().unwrap()))
+            .await
     }
 
     /// Get the message that is being replied to.
@@ -164,7 +182,7 @@ impl Message {
     /// message.delete().await;
     /// ```
     pub async fn delete(&self) -> Result<(), DescordError> {
-        utils::delete_message(&self.channel_id, &self.id).await
+        Http::with(&self.http, utils::delete_message(&self.channel_id, &self.id)).await
     }
 
     /// Delete this message after a certain amount of time.
@@ -195,7 +213,7 @@ impl Message {
     /// message.edit("Edited message").await;
     /// ```
     pub async fn edit(&self, data: impl Into<CreateMessageData>) -> Result<(), DescordError> {
-        utils::edit_message(&self.channel_id, &self.id, data).await
+        Http::with(&self.http, utils::edit_message(&self.channel_id, &self.id, data)).await
     }
 
     /// React to the message with an emoji.
@@ -210,7 +228,26 @@ impl Message {
     /// message.react("👍").await;
     /// ```
     pub async fn react(&self, emoji: &str) -> Result<(), DescordError> {
-        utils::react(&self.channel_id, &self.id, emoji).await
+        Http::with(&self.http, utils::react(&self.channel_id, &self.id, emoji)).await
+    }
+
+    /// Starts a thread from the message, see [`utils::start_thread_from_message`].
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The thread.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let thread = message.start_thread(StartThread::new("Discussion")).await?;
+    /// ```
+    pub async fn start_thread(&self, data: StartThread) -> Result<Channel, DescordError> {
+        Http::with(
+            &self.http,
+            utils::start_thread_from_message(&self.channel_id, &self.id, data),
+        )
+        .await
     }
 }
 
@@ -243,23 +280,15 @@ impl CreateMessageData {
     /// let json = message_data.to_json();
     /// ```
     pub fn to_json(&self) -> String {
-        let mut json = json::parse(&self.serialize_json()).unwrap();
+        self.to_json_value().dump()
+    }
 
+    /// Converts the message data to a json value, e.g. to add fields before sending it.
+    pub(crate) fn to_json_value(&self) -> json::JsonValue {
+        let mut json = json::parse(&self.serialize_json()).unwrap();
         json.remove("attachments");
 
-        let components = self
-            .components
-            .iter()
-            .map(|column| {
-                let components = json::parse(&column.serialize_json()).unwrap();
-                json::object! {
-                    type: 1,
-                    components: components,
-                }
-            })
-            .collect::<Vec<_>>();
-
-        json::stringify(json)
+        json
     }
 
     /// Adds components to the message data.
@@ -2,6 +2,7 @@ mod allowed_mentions;
 pub mod application_command;
 pub mod attachment;
 pub mod channel;
+pub mod channel_builder;
 pub mod component_builder;
 pub mod components;
 pub mod deleted_message_response;
@@ -9,14 +10,18 @@ pub mod dm_channel;
 pub mod embed;
 pub mod embed_builder;
 pub mod emoji;
+pub mod gateway;
 pub mod guild;
 pub mod guild_create;
 pub mod interaction;
+pub mod presence;
 pub mod message_response;
 pub mod reaction_response;
 pub mod ready_response;
 pub mod role;
 pub mod role_response;
+pub mod thread;
 pub mod user;
+pub mod webhook;
 
 pub(crate) mod misc;
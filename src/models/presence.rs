@@ -0,0 +1,170 @@
+use nanoserde::{DeJson, SerJson};
+
+/// The online status shown for the bot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnlineStatus {
+    #[default]
+    Online,
+    Idle,
+    DoNotDisturb,
+    Invisible,
+}
+
+impl OnlineStatus {
+    /// Returns the name discord uses for this status.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Online => "online",
+            Self::Idle => "idle",
+            Self::DoNotDisturb => "dnd",
+            Self::Invisible => "invisible",
+        }
+    }
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityType {
+    Playing = 0,
+    Streaming = 1,
+    Listening = 2,
+    Watching = 3,
+    Custom = 4,
+    Competing = 5,
+}
+
+/// An activity shown in the bot's presence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Activity {
+    pub name: String,
+    pub kind: ActivityType,
+    /// Stream url, only used by `ActivityType::Streaming`.
+    pub url: Option<String>,
+    /// Text of a custom status.
+    pub state: Option<String>,
+}
+
+impl Activity {
+    fn new(kind: ActivityType, name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            url: None,
+            state: None,
+        }
+    }
+
+    /// Creates a "Playing {name}" activity.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let activity = Activity::playing("with the API");
+    /// ```
+    pub fn playing(name: impl Into<String>) -> Self {
+        Self::new(ActivityType::Playing, name)
+    }
+
+    /// Creates a "Streaming {name}" activity.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the stream.
+    /// * `url` - The twitch or youtube url of the stream.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let activity = Activity::streaming("descord", "https://twitch.tv/descord");
+    /// ```
+    pub fn streaming(name: impl Into<String>, url: impl Into<String>) -> Self {
+        Self {
+            url: Some(url.into()),
+            ..Self::new(ActivityType::Streaming, name)
+        }
+    }
+
+    /// Creates a "Listening to {name}" activity.
+    pub fn listening(name: impl Into<String>) -> Self {
+        Self::new(ActivityType::Listening, name)
+    }
+
+    /// Creates a "Watching {name}" activity.
+    pub fn watching(name: impl Into<String>) -> Self {
+        Self::new(ActivityType::Watching, name)
+    }
+
+    /// Creates a "Competing in {name}" activity.
+    pub fn competing(name: impl Into<String>) -> Self {
+        Self::new(ActivityType::Competing, name)
+    }
+
+    /// Creates a custom status.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let activity = Activity::custom("3 songs in queue");
+    /// ```
+    pub fn custom(state: impl Into<String>) -> Self {
+        Self {
+            state: Some(state.into()),
+            ..Self::new(ActivityType::Custom, "Custom Status")
+        }
+    }
+}
+
+/// The presence of the bot, that is its status and activities.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Presence {
+    pub status: OnlineStatus,
+    pub activities: Vec<Activity>,
+    pub afk: bool,
+    /// Unix time in milliseconds of when the bot went idle.
+    pub since: Option<u64>,
+}
+
+impl Presence {
+    /// Creates a new presence with the given status and no activities.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let presence = Presence::new(OnlineStatus::Idle);
+    /// ```
+    pub fn new(status: OnlineStatus) -> Self {
+        Self {
+            status,
+            ..Default::default()
+        }
+    }
+
+    /// Adds an activity to the presence.
+    ///
+    /// # Arguments
+    ///
+    /// * `activity` - The activity to add.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let presence = Presence::new(OnlineStatus::Online).activity(Activity::watching("the queue"));
+    /// ```
+    pub fn activity(mut self, activity: Activity) -> Self {
+        self.activities.push(activity);
+        self
+    }
+}
+
+/// The presence of a guild member, as sent by discord.
+#[derive(DeJson, SerJson, Debug, Clone)]
+pub struct MemberPresence {
+    pub user: PresenceUser,
+    /// Either "idle", "dnd", "online" or "offline".
+    pub status: String,
+}
+
+#[derive(DeJson, SerJson, Debug, Clone)]
+pub struct PresenceUser {
+    pub id: String,
+}
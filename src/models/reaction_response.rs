@@ -1,5 +1,7 @@
 use nanoserde::{DeJson, SerJson};
+use std::sync::Arc;
 
+use crate::client::{Attach, Http};
 use crate::internals::DescordError;
 use crate::utils;
 
@@ -32,6 +34,16 @@ pub struct Reaction {
     /// The ID of the guild where the reaction occurred.
     #[nserde(default)]
     pub guild_id: Option<String>,
+    /// The client that received the reaction.
+    #[nserde(skip)]
+    pub(crate) http: Option<Arc<Http>>,
+}
+
+impl Attach for Reaction {
+    fn attach(&mut self, http: &Arc<Http>) {
+        self.http = Some(Arc::clone(http));
+        self.member.attach(http);
+    }
 }
 
 impl Reaction {
@@ -43,7 +55,7 @@ impl Reaction {
     /// let channel = reaction.get_channel().await?;
     /// ```
     pub async fn get_channel(&self) -> Result<Channel, DescordError> {
-        utils::fetch_channel(&self.channel_id).await
+        Http::with(&self.http, utils::fetch_channel(&self.channel_id)).await
     }
 
     /// Fetches the user who reacted.
@@ -54,7 +66,7 @@ impl Reaction {
     /// let user = reaction.get_user().await?;
     /// ```
     pub async fn get_user(&self) -> Result<User, DescordError> {
-        utils::fetch_user(&self.user_id).await
+        Http::with(&self.http, utils::fetch_user(&self.user_id)).await
     }
 
     /// Fetches the message that was reacted to.
@@ -65,7 +77,11 @@ impl Reaction {
     /// let message = reaction.get_message().await?;
     /// ```
     pub async fn get_message(&self) -> Result<Message, DescordError> {
-        utils::fetch_message(&self.channel_id, &self.message_id).await
+        Http::with(
+            &self.http,
+            utils::fetch_message(&self.channel_id, &self.message_id),
+        )
+        .await
     }
 
     /// Removes the reaction.
@@ -76,15 +92,15 @@ impl Reaction {
     /// reaction.remove_reaction().await;
     /// ```
     pub async fn remove_reaction(&self) -> Result<(), DescordError> {
-        utils::remove_reaction(
-            &self.channel_id,
-            &self.message_id,
-            &self.user_id,
-            &if let Some(ref id) = self.emoji.id {
-                format!("{name}:{id}", name = self.emoji.name)
-            } else {
-                self.emoji.name.clone()
-            },
+        let emoji = if let Some(ref id) = self.emoji.id {
+            format!("{name}:{id}", name = self.emoji.name)
+        } else {
+            self.emoji.name.clone()
+        };
+
+        Http::with(
+            &self.http,
+            utils::remove_reaction(&self.channel_id, &self.message_id, &self.user_id, &emoji),
         )
         .await
     }
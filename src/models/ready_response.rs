@@ -19,6 +19,8 @@ pub struct ReadyData {
     pub guilds: Vec<PartialGuild>,
     pub geo_ordered_rtc_regions: Vec<String>,
     pub application: ApplicationData,
+    /// `[shard_id, num_shards]` if the session is sharded.
+    pub shard: Option<Vec<u32>>,
 }
 
 /// Represents data about the bot application.
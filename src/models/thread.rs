@@ -0,0 +1,303 @@
+use json::{object, JsonValue};
+use nanoserde::{DeJson, SerJson};
+use std::sync::Arc;
+
+use super::channel::Channel;
+use super::guild::Member;
+use super::message_response::CreateMessageData;
+use crate::client::{Attach, Http};
+use crate::consts::ChannelType;
+
+/// Represents the thread specific fields of a thread channel.
+#[derive(DeJson, SerJson, Debug, Clone, Default)]
+pub struct ThreadMetadata {
+    /// Whether the thread is archived.
+    #[nserde(default)]
+    pub archived: bool,
+    /// Minutes of inactivity after which the thread is archived, 60, 1440, 4320 or 10080.
+    #[nserde(default)]
+    pub auto_archive_duration: u32,
+    /// The timestamp of when the thread was archived or unarchived last.
+    #[nserde(default)]
+    pub archive_timestamp: String,
+    /// Whether only moderators can unarchive the thread.
+    #[nserde(default)]
+    pub locked: bool,
+    /// Whether members who aren't moderators can add others to a private thread.
+    pub invitable: Option<bool>,
+    /// The timestamp of when the thread was created, only set for threads created after 2022-01-09.
+    pub create_timestamp: Option<String>,
+}
+
+/// Represents a member of a thread.
+#[derive(DeJson, SerJson, Debug, Clone, Default)]
+pub struct ThreadMember {
+    /// The ID of the thread, not sent in `GUILD_CREATE`.
+    pub id: Option<String>,
+    /// The ID of the user, not sent in `GUILD_CREATE`.
+    pub user_id: Option<String>,
+    /// The timestamp of when the user joined the thread.
+    #[nserde(default)]
+    pub join_timestamp: String,
+    /// The notification settings of the member.
+    #[nserde(default)]
+    pub flags: usize,
+    /// The guild member, only sent when requested.
+    pub member: Option<Member>,
+    /// The ID of the guild, only sent in `THREAD_MEMBER_UPDATE`.
+    pub guild_id: Option<String>,
+}
+
+impl Attach for ThreadMember {
+    fn attach(&mut self, http: &Arc<Http>) {
+        self.member.attach(http);
+    }
+}
+
+/// Represents a tag that can be applied to posts of a forum channel.
+#[derive(DeJson, SerJson, Debug, Clone, Default)]
+pub struct ForumTag {
+    /// The unique ID of the tag, empty when creating a tag.
+    #[nserde(default)]
+    pub id: String,
+    /// The name of the tag.
+    pub name: String,
+    /// Whether only moderators can apply the tag.
+    #[nserde(default)]
+    pub moderated: bool,
+    /// The ID of the custom emoji of the tag.
+    pub emoji_id: Option<String>,
+    /// The unicode emoji of the tag.
+    pub emoji_name: Option<String>,
+}
+
+/// Represents the emoji reacted to new forum posts by default.
+#[derive(DeJson, SerJson, Debug, Clone, Default)]
+pub struct DefaultReaction {
+    /// The ID of the custom emoji.
+    pub emoji_id: Option<String>,
+    /// The unicode emoji.
+    pub emoji_name: Option<String>,
+}
+
+/// Represents a list of threads, e.g. the active threads of a guild.
+#[derive(DeJson, SerJson, Debug, Clone, Default)]
+pub struct ThreadList {
+    /// The threads.
+    pub threads: Vec<Channel>,
+    /// The thread members of the bot, for the threads it joined.
+    pub members: Vec<ThreadMember>,
+    /// Whether there are more threads, only sent for archived threads.
+    #[nserde(default)]
+    pub has_more: bool,
+}
+
+impl Attach for ThreadList {
+    fn attach(&mut self, http: &Arc<Http>) {
+        self.threads.attach(http);
+        self.members.attach(http);
+    }
+}
+
+/// Represents the threads of channels the bot gained access to.
+#[derive(DeJson, SerJson, Debug, Clone, Default)]
+pub struct ThreadListSync {
+    /// The ID of the guild.
+    pub guild_id: String,
+    /// The IDs of the channels whose threads are synced, every channel of the guild if `None`.
+    pub channel_ids: Option<Vec<String>>,
+    /// The active threads of the channels.
+    pub threads: Vec<Channel>,
+    /// The thread members of the bot, for the threads it joined.
+    pub members: Vec<ThreadMember>,
+}
+
+impl Attach for ThreadListSync {
+    fn attach(&mut self, http: &Arc<Http>) {
+        self.threads.attach(http);
+        self.members.attach(http);
+    }
+}
+
+/// Represents members being added to or removed from a thread.
+#[derive(DeJson, SerJson, Debug, Clone, Default)]
+pub struct ThreadMembersUpdate {
+    /// The ID of the thread.
+    pub id: String,
+    /// The ID of the guild.
+    pub guild_id: String,
+    /// The approximate number of members in the thread, stops counting at 50.
+    #[nserde(default)]
+    pub member_count: u32,
+    /// The members that were added.
+    #[nserde(default)]
+    pub added_members: Vec<ThreadMember>,
+    /// The IDs of the users that were removed.
+    #[nserde(default)]
+    pub removed_member_ids: Vec<String>,
+}
+
+impl Attach for ThreadMembersUpdate {
+    fn attach(&mut self, http: &Arc<Http>) {
+        self.added_members.attach(http);
+    }
+}
+
+/// A builder for starting a thread, see [`utils::start_thread`](crate::utils::start_thread)
+/// and [`utils::start_thread_from_message`](crate::utils::start_thread_from_message).
+#[derive(Debug, Clone)]
+pub struct StartThread {
+    pub(crate) body: JsonValue,
+    pub(crate) reason: Option<String>,
+}
+
+impl StartThread {
+    /// Creates a new `StartThread`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the thread, 1-100 characters.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let thread = utils::start_thread(
+    ///     &channel_id,
+    ///     StartThread::new("Incident 42")
+    ///         .channel_type(ChannelType::PrivateThread)
+    ///         .auto_archive_duration(1440),
+    /// )
+    /// .await?;
+    /// ```
+    pub fn new(name: &str) -> Self {
+        Self {
+            body: object! { name: name },
+            reason: None,
+        }
+    }
+
+    /// Sets the type of a thread started without a message.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel_type` - `ChannelType::PublicThread`, `ChannelType::PrivateThread` or `ChannelType::AnnouncementThread`.
+    pub fn channel_type(mut self, channel_type: ChannelType) -> Self {
+        self.body["type"] = (channel_type as u32).into();
+        self
+    }
+
+    /// Sets after how many minutes of inactivity the thread is archived.
+    ///
+    /// # Arguments
+    ///
+    /// * `minutes` - 60, 1440, 4320 or 10080.
+    pub fn auto_archive_duration(mut self, minutes: u32) -> Self {
+        self.body["auto_archive_duration"] = minutes.into();
+        self
+    }
+
+    /// Sets whether members who aren't moderators can add others to a private thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `invitable` - Whether members can invite others.
+    pub fn invitable(mut self, invitable: bool) -> Self {
+        self.body["invitable"] = invitable.into();
+        self
+    }
+
+    /// Sets the slowmode of the thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `seconds` - Seconds a user has to wait between messages, 0-21600.
+    pub fn rate_limit_per_user(mut self, seconds: u32) -> Self {
+        self.body["rate_limit_per_user"] = seconds.into();
+        self
+    }
+
+    /// Sets the reason shown in the audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - The reason.
+    pub fn reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_owned());
+        self
+    }
+}
+
+/// A builder for creating a post in a forum or media channel, see [`utils::create_forum_post`](crate::utils::create_forum_post).
+#[derive(Debug, Clone)]
+pub struct CreateForumPost {
+    pub(crate) body: JsonValue,
+    pub(crate) message: CreateMessageData,
+    pub(crate) reason: Option<String>,
+}
+
+impl CreateForumPost {
+    /// Creates a new `CreateForumPost`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The title of the post, 1-100 characters.
+    /// * `message` - The first message of the post, including its attachments.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let post = utils::create_forum_post(
+    ///     &forum_id,
+    ///     CreateForumPost::new("Crash on startup", "Steps to reproduce: ...")
+    ///         .applied_tags(vec![bug_tag_id]),
+    /// )
+    /// .await?;
+    /// ```
+    pub fn new(name: &str, message: impl Into<CreateMessageData>) -> Self {
+        Self {
+            body: object! { name: name },
+            message: message.into(),
+            reason: None,
+        }
+    }
+
+    /// Sets the tags of the post.
+    ///
+    /// # Arguments
+    ///
+    /// * `tag_ids` - The IDs of the tags, see [`Channel::available_tags`], at most 5.
+    pub fn applied_tags(mut self, tag_ids: Vec<String>) -> Self {
+        self.body["applied_tags"] = tag_ids.into();
+        self
+    }
+
+    /// Sets after how many minutes of inactivity the post is archived.
+    ///
+    /// # Arguments
+    ///
+    /// * `minutes` - 60, 1440, 4320 or 10080.
+    pub fn auto_archive_duration(mut self, minutes: u32) -> Self {
+        self.body["auto_archive_duration"] = minutes.into();
+        self
+    }
+
+    /// Sets the slowmode of the post.
+    ///
+    /// # Arguments
+    ///
+    /// * `seconds` - Seconds a user has to wait between messages, 0-21600.
+    pub fn rate_limit_per_user(mut self, seconds: u32) -> Self {
+        self.body["rate_limit_per_user"] = seconds.into();
+        self
+    }
+
+    /// Sets the reason shown in the audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - The reason.
+    pub fn reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_owned());
+        self
+    }
+}
@@ -0,0 +1,274 @@
+use json::{object, JsonValue};
+use nanoserde::{DeJson, SerJson};
+use std::sync::Arc;
+
+use super::message_response::{CreateMessageData, Message};
+use super::user::User;
+use crate::client::{Attach, Http};
+use crate::internals::DescordError;
+use crate::utils;
+
+/// Represents a webhook.
+#[derive(DeJson, SerJson, Clone, Default)]
+pub struct Webhook {
+    /// The unique ID of the webhook.
+    pub id: String,
+    /// The type of the webhook, 1 for incoming webhooks.
+    #[nserde(default, rename = "type")]
+    pub webhook_type: u32,
+    /// The ID of the guild the webhook is for.
+    pub guild_id: Option<String>,
+    /// The ID of the channel the webhook posts to.
+    pub channel_id: Option<String>,
+    /// The user who created the webhook, not sent when fetched with the token.
+    pub user: Option<User>,
+    /// The default name of the webhook.
+    pub name: Option<String>,
+    /// The hash of the default avatar of the webhook.
+    #[nserde(rename = "avatar")]
+    pub avatar_hash: Option<String>,
+    /// The secure token of the webhook, only sent for incoming webhooks.
+    pub token: Option<String>,
+    /// The ID of the application that created the webhook.
+    pub application_id: Option<String>,
+    /// The url used to execute the webhook, only sent for incoming webhooks.
+    pub url: Option<String>,
+    /// The client that received the webhook.
+    #[nserde(skip)]
+    pub(crate) http: Option<Arc<Http>>,
+}
+
+impl std::fmt::Debug for Webhook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // the token is a secret, it's also part of the url
+        let redact = |value: &str| match &self.token {
+            Some(token) if !token.is_empty() => value.replace(token.as_str(), ":token"),
+            _ => value.to_owned(),
+        };
+
+        f.debug_struct("Webhook")
+            .field("id", &self.id)
+            .field("webhook_type", &self.webhook_type)
+            .field("guild_id", &self.guild_id)
+            .field("channel_id", &self.channel_id)
+            .field("user", &self.user)
+            .field("name", &self.name)
+            .field("avatar_hash", &self.avatar_hash)
+            .field("token", &self.token.as_deref().map(redact))
+            .field("application_id", &self.application_id)
+            .field("url", &self.url.as_deref().map(redact))
+            .finish_non_exhaustive()
+    }
+}
+
+impl Attach for Webhook {
+    fn attach(&mut self, http: &Arc<Http>) {
+        self.http = Some(Arc::clone(http));
+    }
+}
+
+impl Webhook {
+    /// Executes the webhook, see [`utils::execute_webhook`].
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The message to send.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let webhook = utils::create_webhook(&channel_id, "Logs", None, None).await?;
+    /// webhook.execute(ExecuteWebhook::new("Started").username("Logger")).await?;
+    /// ```
+    pub async fn execute(&self, data: ExecuteWebhook) -> Result<Option<Message>, DescordError> {
+        Http::with(
+            &self.http,
+            utils::execute_webhook(&self.id, self.token()?, data),
+        )
+        .await
+    }
+
+    /// Modifies the webhook.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The changes.
+    pub async fn modify(&self, data: ModifyWebhook) -> Result<Webhook, DescordError> {
+        Http::with(&self.http, utils::modify_webhook(&self.id, data)).await
+    }
+
+    /// Deletes the webhook.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - The reason shown in the audit log.
+    pub async fn delete(&self, reason: Option<&str>) -> Result<(), DescordError> {
+        Http::with(&self.http, utils::delete_webhook(&self.id, reason)).await
+    }
+
+    fn token(&self) -> Result<&str, DescordError> {
+        self.token.as_deref().ok_or_else(|| DescordError::NotFound {
+            resource_type: "Webhook token".to_owned(),
+            id: self.id.clone(),
+        })
+    }
+}
+
+/// A builder for modifying a webhook, only the fields that were set are changed.
+#[derive(Debug, Clone)]
+pub struct ModifyWebhook {
+    pub(crate) body: JsonValue,
+    pub(crate) reason: Option<String>,
+}
+
+impl Default for ModifyWebhook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModifyWebhook {
+    /// Creates a new `ModifyWebhook` that doesn't change anything yet.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// utils::modify_webhook(&webhook_id, ModifyWebhook::new().name("Audit log")).await?;
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            body: object! {},
+            reason: None,
+        }
+    }
+
+    /// Sets the default name of the webhook.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name, 1-80 characters.
+    pub fn name(mut self, name: &str) -> Self {
+        self.body["name"] = name.into();
+        self
+    }
+
+    /// Sets the default avatar of the webhook.
+    ///
+    /// # Arguments
+    ///
+    /// * `avatar` - The image as a data uri, e.g. `data:image/png;base64,...`, `None` to remove it.
+    pub fn avatar(mut self, avatar: Option<&str>) -> Self {
+        self.body["avatar"] = avatar.into();
+        self
+    }
+
+    /// Moves the webhook to another channel, this needs the bot token.
+    ///
+    /// # Arguments
+    ///
+    /// * `channel_id` - The ID of the new channel.
+    pub fn channel(mut self, channel_id: &str) -> Self {
+        self.body["channel_id"] = channel_id.into();
+        self
+    }
+
+    /// Sets the reason shown in the audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - The reason.
+    pub fn reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_owned());
+        self
+    }
+}
+
+/// A builder for a message sent by a webhook, see [`utils::execute_webhook`].
+#[derive(Debug, Clone)]
+pub struct ExecuteWebhook {
+    pub(crate) data: CreateMessageData,
+    pub(crate) username: Option<String>,
+    pub(crate) avatar_url: Option<String>,
+    pub(crate) wait: bool,
+    pub(crate) thread_id: Option<String>,
+}
+
+impl ExecuteWebhook {
+    /// Creates a new `ExecuteWebhook`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The message, including its attachments.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let message = ExecuteWebhook::new("Deploy finished")
+    ///     .username("CI")
+    ///     .avatar_url("https://example.com/ci.png")
+    ///     .wait(true);
+    /// ```
+    pub fn new(data: impl Into<CreateMessageData>) -> Self {
+        Self {
+            data: data.into(),
+            username: None,
+            avatar_url: None,
+            wait: false,
+            thread_id: None,
+        }
+    }
+
+    /// Overrides the default name of the webhook for this message.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The name to show.
+    pub fn username(mut self, username: &str) -> Self {
+        self.username = Some(username.to_owned());
+        self
+    }
+
+    /// Overrides the default avatar of the webhook for this message.
+    ///
+    /// # Arguments
+    ///
+    /// * `avatar_url` - The url of the avatar to show.
+    pub fn avatar_url(mut self, avatar_url: &str) -> Self {
+        self.avatar_url = Some(avatar_url.to_owned());
+        self
+    }
+
+    /// Waits for the message to be sent and returns it, disabled by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `wait` - Whether to wait for the message.
+    pub fn wait(mut self, wait: bool) -> Self {
+        self.wait = wait;
+        self
+    }
+
+    /// Sends the message to a thread of the webhook's channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `thread_id` - The ID of the thread.
+    pub fn thread_id(mut self, thread_id: &str) -> Self {
+        self.thread_id = Some(thread_id.to_owned());
+        self
+    }
+
+    pub(crate) fn to_json_value(&self) -> JsonValue {
+        let mut json = self.data.to_json_value();
+
+        if let Some(username) = &self.username {
+            json["username"] = username.as_str().into();
+        }
+
+        if let Some(avatar_url) = &self.avatar_url {
+            json["avatar_url"] = avatar_url.as_str().into();
+        }
+
+        json
+    }
+}
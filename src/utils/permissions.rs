@@ -4,7 +4,7 @@ use chrono::{DateTime, Utc};
 
 enum MemberOrId {
     Id(u32),
-    MemberObj(Member),
+    MemberObj(Box<Member>),
 }
 
 pub async fn fetch_permissions(member: &Member, guild: &Guild, channel: Option<&Channel>) -> u64 {
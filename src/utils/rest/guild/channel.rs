@@ -1,4 +1,10 @@
-use crate::consts::ChannelType;
+use std::collections::VecDeque;
+
+use futures_util::stream::{self, BoxStream, StreamExt};
+
+use crate::consts::{ChannelType, DISCORD_EPOCH, MESSAGE_PAGE_SIZE};
+use crate::models::channel::Overwrite;
+use crate::models::channel_builder::{ChannelPosition, CreateChannel, ModifyChannel};
 
 use super::*;
 
@@ -18,24 +24,149 @@ pub async fn fetch_channel(channel_id: &str) -> Result<Channel, DescordError> {
 
     // CHANNEL_CACHE.lock().unwrap().put(channel_id.to_string(), channel.clone());
 
-    Ok(channel)
+    Ok(attach_current(channel))
 }
 
 /// Deletes a channel by ID
 /// Deleting a guild channel cannot be undone.
-pub async fn delete_channel(channel_id: &str) -> Result<(), DescordError> {
+///
+/// # Arguments
+/// `channel_id` - The ID of the channel to delete
+/// `reason` - The reason shown in the audit log
+pub async fn delete_channel(channel_id: &str, reason: Option<&str>) -> Result<(), DescordError> {
     let url = format!("channels/{channel_id}");
-    request(Method::DELETE, &url, None).await?;
+    request_with_headers(Method::DELETE, url, None, audit_log_headers(reason)).await?;
 
     // CHANNEL_CACHE.lock().unwrap().pop_entry(channel_id);
 
     Ok(())
 }
 
-/// Create a channel in a guild
-pub async fn create_channel() -> Result<(), DescordError> {
-    let endpoint = "guilds/{guild.id}/channels";
-    todo!();
+/// Create a channel in a guild.
+/// Requires MANAGE_CHANNELS permission.
+///
+/// # Arguments
+/// `guild_id` - The ID of the guild
+/// `data` - The channel to create, see [`CreateChannel`]
+pub async fn create_channel(guild_id: &str, data: CreateChannel) -> Result<Channel, DescordError> {
+    let url = format!("guilds/{guild_id}/channels");
+    let headers = audit_log_headers(data.reason.as_deref());
+
+    let resp = request_with_headers(Method::POST, url, Some(data.body.dump()), headers)
+        .await?
+        .text()
+        .await
+        .map_err(DescordError::Http)?;
+
+    parse_channel(&resp)
+}
+
+/// Modify a channel, only the fields set in `data` are changed.
+/// Requires MANAGE_CHANNELS permission.
+///
+/// # Arguments
+/// `channel_id` - The ID of the channel
+/// `data` - The changes, see [`ModifyChannel`]
+pub async fn modify_channel(
+    channel_id: &str,
+    data: ModifyChannel,
+) -> Result<Channel, DescordError> {
+    let url = format!("channels/{channel_id}");
+    let headers = audit_log_headers(data.reason.as_deref());
+
+    let resp = request_with_headers(Method::PATCH, url, Some(data.body.dump()), headers)
+        .await?
+        .text()
+        .await
+        .map_err(DescordError::Http)?;
+
+    parse_channel(&resp)
+}
+
+/// Change the positions of multiple channels in a guild at once.
+/// Requires MANAGE_CHANNELS permission.
+///
+/// # Arguments
+/// `guild_id` - The ID of the guild
+/// `positions` - The new positions, see [`ChannelPosition`]
+/// `reason` - The reason shown in the audit log
+pub async fn reorder_channels(
+    guild_id: &str,
+    positions: Vec<ChannelPosition>,
+    reason: Option<&str>,
+) -> Result<(), DescordError> {
+    let url = format!("guilds/{guild_id}/channels");
+    let body = JsonValue::from(
+        positions
+            .iter()
+            .map(ChannelPosition::to_json)
+            .collect::<Vec<_>>(),
+    );
+
+    request_with_headers(
+        Method::PATCH,
+        url,
+        Some(body.dump()),
+        audit_log_headers(reason),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Create or replace a permission overwrite of a channel.
+/// Requires MANAGE_ROLES permission.
+///
+/// # Arguments
+/// `channel_id` - The ID of the channel
+/// `overwrite` - The overwrite, see [`Overwrite::role`] and [`Overwrite::member`]
+/// `reason` - The reason shown in the audit log
+pub async fn edit_channel_permissions(
+    channel_id: &str,
+    overwrite: Overwrite,
+    reason: Option<&str>,
+) -> Result<(), DescordError> {
+    let url = format!("channels/{channel_id}/permissions/{}", overwrite.id);
+    let body = object! {
+        type: overwrite.overwrite_type,
+        allow: overwrite.allow,
+        deny: overwrite.deny,
+    };
+
+    request_with_headers(
+        Method::PUT,
+        url,
+        Some(body.dump()),
+        audit_log_headers(reason),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Delete a permission overwrite of a channel.
+/// Requires MANAGE_ROLES permission.
+///
+/// # Arguments
+/// `channel_id` - The ID of the channel
+/// `overwrite_id` - The ID of the role or member the overwrite belongs to
+/// `reason` - The reason shown in the audit log
+pub async fn delete_channel_permission(
+    channel_id: &str,
+    overwrite_id: &str,
+    reason: Option<&str>,
+) -> Result<(), DescordError> {
+    let url = format!("channels/{channel_id}/permissions/{overwrite_id}");
+    request_with_headers(Method::DELETE, url, None, audit_log_headers(reason)).await?;
+
+    Ok(())
+}
+
+pub(crate) fn parse_channel(json: &str) -> Result<Channel, DescordError> {
+    let mut channel = Channel::deserialize_json(json).map_err(DescordError::DeserializeJson)?;
+    channel.mention = format!("<#{}>", channel.id);
+
+    Ok(attach_current(channel))
 }
 
 /// Post a typing indicator for the specified channel, which expires after 10 seconds.
@@ -46,31 +177,191 @@ pub async fn send_typing(channel_id: &str) -> Result<(), DescordError> {
 }
 
 /// Update channel info.
+///
+/// Only the fields that can be modified are sent, see [`modify_channel`] to change specific fields.
 pub async fn update_channel(channel: Channel) -> Result<(), DescordError> {
-    let channel_id = &channel.id;
-    let url = format!("channels/{channel_id}");
-    request(Method::PATCH, &url, Some(&channel.serialize_json())).await?;
+    modify_channel(&channel.id, ModifyChannel::from(&channel)).await?;
 
     Ok(())
 }
 
 type DateTime = chrono::DateTime<chrono::Utc>;
 
+/// The position of the messages to fetch, relative to a message id.
+///
+/// Use [`snowflake_from_timestamp`] to page from a point in time instead of a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageCursor {
+    /// Messages sent before the message, newest first.
+    Before(String),
+    /// Messages sent after the message, newest first.
+    After(String),
+    /// Messages sent around the message.
+    Around(String),
+}
+
+/// Returns the smallest snowflake created at the given time.
+///
+/// # Arguments
+///
+/// * `timestamp` - The time, must not be earlier than 2015.
+///
+/// # Examples
+///
+/// ```ignore
+/// let yesterday = chrono::Utc::now() - chrono::Duration::days(1);
+/// let cursor = MessageCursor::After(utils::snowflake_from_timestamp(yesterday));
+/// ```
+pub fn snowflake_from_timestamp(timestamp: DateTime) -> String {
+    let millis = (timestamp.timestamp_millis() as u64).saturating_sub(DISCORD_EPOCH);
+    (millis << 22).to_string()
+}
+
+/// Returns the time a snowflake was created at, `None` if the snowflake is invalid.
+///
+/// # Arguments
+///
+/// * `snowflake` - The ID, e.g. of a message.
+pub fn timestamp_from_snowflake(snowflake: &str) -> Option<DateTime> {
+    let millis = (snowflake.parse::<u64>().ok()? >> 22) + DISCORD_EPOCH;
+    DateTime::from_timestamp_millis(millis as i64)
+}
+
 /// Retrieves the messages in a channel.
-/// Default limit is 50.
+///
+/// # Arguments
+///
+/// * `channel_id` - The ID of the channel.
+/// * `cursor` - Which messages to fetch, the latest messages if `None`.
+/// * `limit` - The number of messages, between 1 and 100, 50 by default.
+///
+/// # Examples
+///
+/// ```ignore
+/// let messages = utils::get_channel_messages(
+///     &channel_id,
+///     Some(MessageCursor::Before(message_id)),
+///     Some(100),
+/// )
+/// .await?;
+/// ```
 pub async fn get_channel_messages(
     channel_id: &str,
-    before: Option<DateTime>,
-    around: Option<DateTime>,
-    after: Option<DateTime>,
+    cursor: Option<MessageCursor>,
     limit: Option<usize>,
-) -> Vec<Message> {
-    let limit = limit.unwrap_or(50);
-    assert!((1..=100).contains(&limit));
+) -> Result<Vec<Message>, DescordError> {
+    let limit = limit.unwrap_or(50).clamp(1, MESSAGE_PAGE_SIZE);
+    let mut url = format!("channels/{channel_id}/messages?limit={limit}");
+
+    match cursor {
+        Some(MessageCursor::Before(id)) => url += &format!("&before={id}"),
+        Some(MessageCursor::After(id)) => url += &format!("&after={id}"),
+        Some(MessageCursor::Around(id)) => url += &format!("&around={id}"),
+        None => {}
+    }
+
+    let resp = request(Method::GET, &url, None).await?.text().await.map_err(DescordError::Http)?;
+
+    Vec::<Message>::deserialize_json(&resp)
+        .map(attach_current)
+        .map_err(DescordError::DeserializeJson)
+}
+
+/// Returns a stream of the messages in a channel, newest first.
+///
+/// The messages are fetched 100 at a time as the stream is polled, so the whole
+/// history can be read without keeping it in memory. The stream ends after the
+/// oldest message, or after the first error.
+///
+/// # Arguments
+///
+/// * `channel_id` - The ID of the channel.
+/// * `before` - Only stream messages sent before this message, the whole history if `None`.
+///
+/// # Examples
+///
+/// ```ignore
+/// let mut history = utils::channel_history(&channel_id, None);
+///
+/// while let Some(message) = history.next().await {
+///     archive(message?).await;
+/// }
+/// ```
+pub fn channel_history(
+    channel_id: &str,
+    before: Option<&str>,
+) -> BoxStream<'static, Result<Message, DescordError>> {
+    history(channel_id, before, None)
+}
+
+/// Streams the history of a channel with the client a model was received by.
+pub(crate) fn history(
+    channel_id: &str,
+    before: Option<&str>,
+    http: Option<Arc<Http>>,
+) -> BoxStream<'static, Result<Message, DescordError>> {
+    struct State {
+        channel_id: String,
+        before: Option<String>,
+        page: VecDeque<Message>,
+        done: bool,
+        http: Option<Arc<Http>>,
+    }
+
+    let state = State {
+        channel_id: channel_id.to_owned(),
+        before: before.map(str::to_owned),
+        page: VecDeque::new(),
+        done: false,
+        http,
+    };
+
+    stream::unfold(state, |mut state| async move {
+        if state.page.is_empty() && !state.done {
+            let cursor = state.before.clone().map(MessageCursor::Before);
+
+            let messages = get_channel_messages(&state.channel_id, cursor, Some(MESSAGE_PAGE_SIZE));
+
+            match Http::with(&state.http, messages).await {
+                Ok(messages) => {
+                    state.done = messages.len() < MESSAGE_PAGE_SIZE;
+                    state.before = messages.last().map(|message| message.id.clone());
+                    state.page = messages.into();
+                }
+                Err(e) => {
+                    state.done = true;
+                    return Some((Err(e), state));
+                }
+            }
+        }
+
+        let message = state.page.pop_front()?;
+        Some((Ok(message), state))
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snowflakes_round_trip_to_timestamps() {
+        let timestamp = DateTime::from_timestamp_millis(1_700_000_000_123).unwrap();
+        let snowflake = snowflake_from_timestamp(timestamp);
 
-    let mut payload = object! {};
+        assert_eq!(timestamp_from_snowflake(&snowflake), Some(timestamp));
+        assert!(snowflake.parse::<u64>().unwrap().trailing_zeros() >= 22);
+    }
 
-    if let Some(before) = before {}
+    #[test]
+    fn snowflakes_are_decoded_relative_to_the_discord_epoch() {
+        // the example of the discord documentation
+        let timestamp = timestamp_from_snowflake("175928847299117063").unwrap();
+        assert_eq!(timestamp.timestamp_millis(), 1_462_015_105_796);
 
-    todo!();
+        assert_eq!(timestamp_from_snowflake("not a snowflake"), None);
+        let epoch = DateTime::from_timestamp_millis(DISCORD_EPOCH as i64).unwrap();
+        assert_eq!(snowflake_from_timestamp(epoch), "0");
+    }
 }
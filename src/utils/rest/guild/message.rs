@@ -1,7 +1,8 @@
-use std::{borrow::Cow, io::Read};
+use std::sync::Arc;
 
-use reqwest::multipart::{Form, Part};
+use futures_util::StreamExt;
 
+use crate::consts::{BULK_DELETE_LIMIT, BULK_DELETE_MAX_AGE};
 use crate::models::attachment::AttachmentPayload;
 
 use super::*;
@@ -22,7 +23,7 @@ pub async fn edit_message(
     let url = format!("channels/{channel_id}/messages/{message_id}");
     let data: CreateMessageData = data.into();
 
-    request(Method::PATCH, &url, Some(&data.to_json())).await?;
+    request_message(Method::PATCH, &url, data.to_json_value(), &data.attachments).await?;
     Ok(())
 }
 
@@ -35,25 +36,253 @@ pub async fn fetch_message(
     channel_id: &str,
     message_id: &str,
 ) -> Result<Message, DescordError> {
-    if let Some(message) = MESSAGE_CACHE.lock().await.get(message_id).cloned() {
-        return Ok(message);
+    let cached = Http::current()?.cache().messages.lock().await.get(message_id).cloned();
+    if let Some(message) = cached {
+        return Ok(attach_current(message));
     }
 
     let url = format!("channels/{channel_id}/messages/{message_id}");
     let resp = request(Method::GET, &url, None).await?.text().await.map_err(DescordError::Http)?;
 
-    Message::deserialize_json(&resp).map_err(DescordError::DeserializeJson)
+    Message::deserialize_json(&resp)
+        .map(attach_current)
+        .map_err(DescordError::DeserializeJson)
 }
 
-/// Returns true if the operation was successful, false otherwise.
-/// This function requires the MANAGE_MESSAGES permission.
+/// Deletes a message.
+/// Deleting a message of another user requires the MANAGE_MESSAGES permission.
+///
+/// # Arguments
+/// `channel_id` - The ID of the channel the message is in
+/// `message_id` - The ID of the message to delete
 pub async fn delete_message(channel_id: &str, message_id: &str) -> Result<(), DescordError> {
+    delete_message_request(channel_id, message_id, None).await
+}
+
+/// Deletes a message, showing a reason in the audit log.
+/// Deleting a message of another user requires the MANAGE_MESSAGES permission.
+///
+/// # Arguments
+/// `channel_id` - The ID of the channel the message is in
+/// `message_id` - The ID of the message to delete
+/// `reason` - The reason shown in the audit log
+pub async fn delete_message_with_reason(
+    channel_id: &str,
+    message_id: &str,
+    reason: &str,
+) -> Result<(), DescordError> {
+    delete_message_request(channel_id, message_id, Some(reason)).await
+}
+
+async fn delete_message_request(
+    channel_id: &str,
+    message_id: &str,
+    reason: Option<&str>,
+) -> Result<(), DescordError> {
     let url = format!("channels/{channel_id}/messages/{message_id}");
-    // request() already checks for API errors, so if we get Ok, it succeeded
-    request(Method::DELETE, &url, None).await?;
+    // request_with_headers() already checks for API errors, so if we get Ok, it succeeded
+    request_with_headers(Method::DELETE, url, None, audit_log_headers(reason)).await?;
+    Ok(())
+}
+
+/// Deletes 2 to 100 messages at once.
+/// Requires MANAGE_MESSAGES permission, messages older than 2 weeks can't be bulk deleted.
+///
+/// # Arguments
+/// `channel_id` - The ID of the channel the messages are in
+/// `message_ids` - The IDs of the messages to delete
+/// `reason` - The reason shown in the audit log
+pub async fn bulk_delete_messages(
+    channel_id: &str,
+    message_ids: Vec<String>,
+    reason: Option<&str>,
+) -> Result<(), DescordError> {
+    if !(2..=BULK_DELETE_LIMIT).contains(&message_ids.len()) {
+        return Err(DescordError::InvalidArgument {
+            param: "message_ids".to_owned(),
+            expected: format!("2 to {BULK_DELETE_LIMIT} message ids"),
+            got: message_ids.len().to_string(),
+        });
+    }
+
+    let url = format!("channels/{channel_id}/messages/bulk-delete");
+    let body = object! { messages: message_ids };
+
+    request_with_headers(
+        Method::POST,
+        url,
+        Some(body.dump()),
+        audit_log_headers(reason),
+    )
+    .await?;
+
     Ok(())
 }
 
+type MessagePredicate = Arc<dyn Fn(&Message) -> bool + Send + Sync>;
+
+/// Selects the messages deleted by [`purge_messages`].
+///
+/// Every filter that is set must match for a message to be deleted.
+#[derive(Clone)]
+pub struct Purge {
+    limit: usize,
+    author_id: Option<String>,
+    predicate: Option<MessagePredicate>,
+    newer_than: Option<Duration>,
+    reason: Option<String>,
+}
+
+impl Purge {
+    /// Creates a new `Purge` checking the latest messages of a channel.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The number of messages to check, starting at the newest message.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let deleted = utils::purge_messages(
+    ///     &channel_id,
+    ///     Purge::new(500)
+    ///         .author(&spammer_id)
+    ///         .newer_than(Duration::from_secs(60 * 60))
+    ///         .reason("Spam"),
+    /// )
+    /// .await?;
+    /// ```
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            author_id: None,
+            predicate: None,
+            newer_than: None,
+            reason: None,
+        }
+    }
+
+    /// Only deletes messages sent by a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` - The ID of the author.
+    pub fn author(mut self, user_id: &str) -> Self {
+        self.author_id = Some(user_id.to_owned());
+        self
+    }
+
+    /// Only deletes messages the predicate returns `true` for.
+    ///
+    /// # Arguments
+    ///
+    /// * `predicate` - The filter, e.g. checking the content.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let purge = Purge::new(100).matching(|message| message.content.contains("discord.gg/"));
+    /// ```
+    pub fn matching(
+        mut self,
+        predicate: impl Fn(&Message) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.predicate = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Only deletes messages sent within the duration, the purge stops at the first older message.
+    ///
+    /// # Arguments
+    ///
+    /// * `age` - The maximum age of the deleted messages.
+    pub fn newer_than(mut self, age: Duration) -> Self {
+        self.newer_than = Some(age);
+        self
+    }
+
+    /// Sets the reason shown in the audit log.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - The reason.
+    pub fn reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_owned());
+        self
+    }
+
+    fn matches(&self, message: &Message) -> bool {
+        let author_matches = match &self.author_id {
+            Some(author_id) => message
+                .author
+                .as_ref()
+                .is_some_and(|author| &author.id == author_id),
+            None => true,
+        };
+
+        let predicate_matches = match &self.predicate {
+            Some(predicate) => predicate(message),
+            None => true,
+        };
+
+        author_matches && predicate_matches
+    }
+}
+
+/// Deletes the messages of a channel selected by a [`Purge`], returns how many were deleted.
+///
+/// Messages up to 2 weeks old are deleted with [`bulk_delete_messages`],
+/// older messages are deleted one by one.
+///
+/// # Arguments
+/// `channel_id` - The ID of the channel
+/// `purge` - Which messages to delete
+pub async fn purge_messages(channel_id: &str, purge: Purge) -> Result<usize, DescordError> {
+    let now = chrono::Utc::now();
+    let mut history = channel_history(channel_id, None).take(purge.limit);
+
+    let mut recent = Vec::new();
+    let mut old = Vec::new();
+
+    while let Some(message) = history.next().await {
+        let message = message?;
+        // a message with an unreadable id is deleted on its own, so it can't fail a bulk delete
+        let age = timestamp_from_snowflake(&message.id)
+            .map(|created| (now - created).to_std().unwrap_or_default());
+
+        // the history is newest first, so every following message is older too
+        if let (Some(max_age), Some(age)) = (purge.newer_than, age) {
+            if age > max_age {
+                break;
+            }
+        }
+
+        if !purge.matches(&message) {
+            continue;
+        }
+
+        if age.is_some_and(|age| age < BULK_DELETE_MAX_AGE) {
+            recent.push(message.id);
+        } else {
+            old.push(message.id);
+        }
+    }
+
+    for chunk in recent.chunks(BULK_DELETE_LIMIT) {
+        if let [message_id] = chunk {
+            delete_message_request(channel_id, message_id, purge.reason.as_deref()).await?;
+        } else {
+            bulk_delete_messages(channel_id, chunk.to_vec(), purge.reason.as_deref()).await?;
+        }
+    }
+
+    for message_id in &old {
+        delete_message_request(channel_id, message_id, purge.reason.as_deref()).await?;
+    }
+
+    Ok(recent.len() + old.len())
+}
+
 /// Removes a reaction from a message.
 ///
 /// # Arguments
@@ -99,9 +328,7 @@ pub async fn send(
     data: impl Into<CreateMessageData>,
 ) -> Result<Message, DescordError> {
     let data: CreateMessageData = data.into();
-
-    let mut body = json::parse(&data.to_json())
-        .map_err(|e| DescordError::JsonParse(format!("Failed to parse message data: {}", e)))?;
+    let mut body = data.to_json_value();
 
     if let Some(message_id) = reference_message_id {
         body.insert(
@@ -114,84 +341,98 @@ pub async fn send(
     }
 
     let endpoint = format!("channels/{channel_id}/messages");
-    let multipart = get_message_multipart(channel_id, data.attachments, Some(body.dump()))
-        .await
-        .map_err(|e| DescordError::Other(format!("Failed to build multipart: {}", e)))?;
-
-    let mut headers = get_headers();
-    headers.remove("Content-Type");
-
-    let response = reqwest::Client::new()
-        .post(&format!("{API}/{endpoint}"))
-        .headers(headers)
-        .multipart(multipart)
-        .send()
-        .await
-        .map_err(DescordError::Http)?;
-
-    let status = response.status();
-    let response_text = response.text().await.map_err(DescordError::Http)?;
-
-    if status.is_client_error() || status.is_server_error() {
-        let (code, message) = if let Ok(parsed) = json::parse(&response_text) {
-            let code = parsed["code"].as_u64();
-            let message = parsed["message"]
-                .as_str()
-                .unwrap_or(&response_text)
-                .to_string();
-            (code, message)
-        } else {
-            (None, response_text)
-        };
+    let response = request_message(Method::POST, &endpoint, body, &data.attachments).await?;
+    let text = response.text().await.map_err(DescordError::Http)?;
+
+    Message::deserialize_json(&text)
+        .map(attach_current)
+        .map_err(DescordError::DeserializeJson)
+}
 
-        return Err(DescordError::Api(DiscordApiError {
-            status: status.as_u16(),
-            code,
-            message,
-        }));
+/// Sends message data to an endpoint.
+///
+/// The request is sent as `multipart/form-data` if there are attachments,
+/// and as plain json otherwise.
+pub(crate) async fn request_message(
+    method: Method,
+    endpoint: &str,
+    mut body: JsonValue,
+    attachments: &[AttachmentPayload],
+) -> Result<Response, DescordError> {
+    if !attachments.is_empty() {
+        body["attachments"] = attachments_json(attachments);
     }
 
-    Message::deserialize_json(&response_text).map_err(|e| {
-        DescordError::DeserializeJson(e)
-    })
+    request_with_attachments(method, endpoint, body, attachments, HeaderMap::new()).await
 }
 
-pub async fn get_message_multipart(
-    channel_id: &str,
-    attachments: Vec<AttachmentPayload>,
-    payload_json: Option<String>,
-) -> Result<Form, DescordError> {
-    let mut form = Form::new();
+/// Sends `body` as json, or as the `payload_json` of a multipart form if there are attachments.
+/// The attachment metadata must already be part of `body`, see [`attachments_json`].
+pub(crate) async fn request_with_attachments(
+    method: Method,
+    endpoint: &str,
+    body: JsonValue,
+    attachments: &[AttachmentPayload],
+    headers: HeaderMap,
+) -> Result<Response, DescordError> {
+    let body = if attachments.is_empty() {
+        RequestBody::Json(body.dump())
+    } else {
+        RequestBody::Multipart {
+            payload_json: body.dump(),
+            files: load_attachments(attachments).await?,
+        }
+    };
 
-    if let Some(payload_json) = payload_json {
-        let msg = Part::text(Cow::Owned(payload_json.to_string()))
-            .mime_str("application/json")
-            .unwrap();
+    request_with_body(method, endpoint, body, headers).await
+}
 
-        form = form.part("payload_json", msg);
-    }
+/// The `attachments` field of a message, linking each file part to its name and description.
+pub(crate) fn attachments_json(attachments: &[AttachmentPayload]) -> JsonValue {
+    attachments
+        .iter()
+        .enumerate()
+        .map(|(i, attachment)| {
+            let mut value = object! {
+                id: i,
+                filename: attachment.upload_name(),
+            };
+
+            if let Some(description) = &attachment.description {
+                value["description"] = description.as_str().into();
+            }
 
-    for (
-        file_idx,
-        AttachmentPayload {
-            file_name,
-            file_path,
-            mime_type,
-        },
-    ) in attachments.iter().enumerate()
-    {
-        let mut file = std::fs::File::open(file_path)?;
-        let mut buf = Vec::new();
-
-        file.read_to_end(&mut buf)?;
-
-        let file_part = Part::bytes(Cow::Owned(buf))
-            .file_name(Cow::Owned(file_name.to_string()))
-            .mime_str(&mime_type)
-            .unwrap();
-
-        form = form.part(Cow::Owned(format!("file[{file_idx}]")), file_part);
+            value
+        })
+        .collect::<Vec<_>>()
+        .into()
+}
+
+async fn load_attachments(
+    attachments: &[AttachmentPayload],
+) -> Result<Vec<FilePart>, DescordError> {
+    let mut files = Vec::with_capacity(attachments.len());
+
+    for attachment in attachments {
+        files.push(FilePart {
+            file_name: attachment.upload_name(),
+            data: attachment.load().await?,
+            mime_type: attachment.guess_mime_type(),
+        });
     }
 
-    Ok(form)
+    Ok(files)
+}
+
+/// Builds the multipart form of a message with attachments.
+///
+/// # Arguments
+/// `attachments` - The files of the message
+/// `payload_json` - The json body of the message
+pub async fn get_message_multipart(
+    attachments: Vec<AttachmentPayload>,
+    payload_json: Option<String>,
+) -> Result<Form, DescordError> {
+    let files = load_attachments(&attachments).await?;
+    multipart_form(payload_json.as_deref(), &files)
 }
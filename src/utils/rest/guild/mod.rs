@@ -1,6 +1,7 @@
 mod channel;
 mod message;
 mod role;
+mod thread;
 mod user;
 
 use super::*;
@@ -8,6 +9,7 @@ use super::*;
 pub use channel::*;
 pub use message::*;
 pub use role::*;
+pub use thread::*;
 pub use user::*;
 
 /* Misc functions */
@@ -30,17 +32,19 @@ pub async fn fetch_application_commands(bot_id: &str) -> Result<Vec<ApplicationC
 }
 
 pub async fn fetch_guild(guild_id: &str) -> Result<Guild, DescordError> {
-    if let Some(guild) = GUILD_CACHE.lock().await.get(guild_id).cloned() {
-        return Ok(guild);
+    let http = Http::current()?;
+    if let Some(guild) = http.cache().guilds.lock().await.get(guild_id).cloned() {
+        return Ok(attach_current(guild));
     }
 
     let url = format!("guilds/{guild_id}");
     let resp = request(Method::GET, &url, None).await?.text().await.map_err(DescordError::Http)?;
     let guild = Guild::deserialize_json(&resp).map_err(DescordError::DeserializeJson)?;
 
-    GUILD_CACHE
+    http.cache()
+        .guilds
         .lock()
         .await
         .put(guild_id.to_string(), guild.clone());
-    Ok(guild)
+    Ok(attach_current(guild))
 }
@@ -9,9 +9,12 @@ pub async fn fetch_roles(guild_id: &str) -> Result<Vec<Role>, DescordError> {
     let resp = request(Method::GET, &url, None).await?.text().await.map_err(DescordError::Http)?;
     let roles: Vec<Role> = DeJson::deserialize_json(&resp).map_err(DescordError::DeserializeJson)?;
 
+    let http = Http::current()?;
+    let mut cache = http.cache().roles.lock().await;
     for role in &roles {
-        ROLE_CACHE.lock().await.put(role.id.clone(), role.clone());
+        cache.put(role.id.clone(), role.clone());
     }
+    drop(cache);
 
     Ok(roles)
 }
@@ -22,7 +25,8 @@ pub async fn fetch_roles(guild_id: &str) -> Result<Vec<Role>, DescordError> {
 /// guild_id - The ID of the guild the role is in
 /// role_id - The ID of the role to get
 pub async fn fetch_role(guild_id: &str, role_id: &str) -> Result<Role, DescordError> {
-    if let Some(role) = ROLE_CACHE.lock().await.get(role_id).cloned() {
+    let http = Http::current()?;
+    if let Some(role) = http.cache().roles.lock().await.get(role_id).cloned() {
         info!("Role cache hit");
         return Ok(role);
     }
@@ -31,7 +35,7 @@ pub async fn fetch_role(guild_id: &str, role_id: &str) -> Result<Role, DescordEr
     let roles: Vec<Role> = DeJson::deserialize_json(&resp).map_err(DescordError::DeserializeJson)?;
     let mut answer = None;
     for role in &roles {
-        ROLE_CACHE.lock().await.put(role.id.clone(), role.clone());
+        http.cache().roles.lock().await.put(role.id.clone(), role.clone());
         if role.id == role_id {
             answer = Some(role.clone());
         }
@@ -54,7 +58,7 @@ pub async fn fetch_role(guild_id: &str, role_id: &str) -> Result<Role, DescordEr
 pub async fn delete_role(guild_id: &str, role_id: &str) -> Result<(), DescordError> {
     let url = format!("guilds/{guild_id}/roles/{role_id}");
     request(Method::DELETE, &url, None).await?;
-    ROLE_CACHE.lock().await.pop_entry(role_id);
+    Http::current()?.cache().roles.lock().await.pop_entry(role_id);
     Ok(())
 }
 
@@ -82,7 +86,7 @@ pub async fn edit_role_position(
         .await
         .map_err(DescordError::Http)?;
     let role: Role = DeJson::deserialize_json(&resp).map_err(DescordError::DeserializeJson)?;
-    ROLE_CACHE.lock().await.put(role.id.clone(), role.clone());
+    Http::current()?.cache().roles.lock().await.put(role.id.clone(), role.clone());
     Ok(role)
 }
 
@@ -121,7 +125,7 @@ pub async fn create_role(
         .map_err(DescordError::Http)?;
     let role: Role = DeJson::deserialize_json(&resp).map_err(DescordError::DeserializeJson)?;
 
-    ROLE_CACHE.lock().await.put(role.id.clone(), role.clone());
+    Http::current()?.cache().roles.lock().await.put(role.id.clone(), role.clone());
     Ok(role)
 }
 
@@ -0,0 +1,274 @@
+use crate::models::thread::{CreateForumPost, StartThread, ThreadList, ThreadMember};
+
+use super::channel::parse_channel;
+use super::*;
+
+/// Start a thread from an existing message.
+/// Requires CREATE_PUBLIC_THREADS permission.
+///
+/// # Arguments
+/// `channel_id` - The ID of the channel the message is in
+/// `message_id` - The ID of the message
+/// `data` - The thread, see [`StartThread`]
+pub async fn start_thread_from_message(
+    channel_id: &str,
+    message_id: &str,
+    data: StartThread,
+) -> Result<Channel, DescordError> {
+    let url = format!("channels/{channel_id}/messages/{message_id}/threads");
+    start_thread_int(url, data).await
+}
+
+/// Start a thread that isn't connected to a message, a private thread by default.
+/// Requires CREATE_PUBLIC_THREADS or CREATE_PRIVATE_THREADS permission.
+///
+/// # Arguments
+/// `channel_id` - The ID of the channel
+/// `data` - The thread, see [`StartThread`]
+pub async fn start_thread(channel_id: &str, data: StartThread) -> Result<Channel, DescordError> {
+    let url = format!("channels/{channel_id}/threads");
+    start_thread_int(url, data).await
+}
+
+async fn start_thread_int(url: String, data: StartThread) -> Result<Channel, DescordError> {
+    let resp = request_with_headers(
+        Method::POST,
+        url,
+        Some(data.body.dump()),
+        audit_log_headers(data.reason.as_deref()),
+    )
+    .await?
+    .text()
+    .await
+    .map_err(DescordError::Http)?;
+
+    parse_channel(&resp)
+}
+
+/// Create a post in a forum or media channel, the post is a thread with its first message.
+/// Requires SEND_MESSAGES permission.
+///
+/// # Arguments
+/// `channel_id` - The ID of the forum or media channel
+/// `data` - The post, see [`CreateForumPost`]
+pub async fn create_forum_post(
+    channel_id: &str,
+    data: CreateForumPost,
+) -> Result<Channel, DescordError> {
+    let url = format!("channels/{channel_id}/threads");
+    let attachments = &data.message.attachments;
+
+    let mut message = data.message.to_json_value();
+    if !attachments.is_empty() {
+        message["attachments"] = attachments_json(attachments);
+    }
+
+    let mut body = data.body;
+    body["message"] = message;
+
+    let resp = request_with_attachments(
+        Method::POST,
+        &url,
+        body,
+        attachments,
+        audit_log_headers(data.reason.as_deref()),
+    )
+    .await?
+    .text()
+    .await
+    .map_err(DescordError::Http)?;
+
+    parse_channel(&resp)
+}
+
+/// Add the bot to a thread, the thread must not be archived.
+///
+/// # Arguments
+/// `thread_id` - The ID of the thread
+pub async fn join_thread(thread_id: &str) -> Result<(), DescordError> {
+    let url = format!("channels/{thread_id}/thread-members/@me");
+    request(Method::PUT, &url, None).await?;
+
+    Ok(())
+}
+
+/// Remove the bot from a thread, the thread must not be archived.
+///
+/// # Arguments
+/// `thread_id` - The ID of the thread
+pub async fn leave_thread(thread_id: &str) -> Result<(), DescordError> {
+    let url = format!("channels/{thread_id}/thread-members/@me");
+    request(Method::DELETE, &url, None).await?;
+
+    Ok(())
+}
+
+/// Add a member to a thread, the bot must be able to send messages in the thread.
+///
+/// # Arguments
+/// `thread_id` - The ID of the thread
+/// `user_id` - The ID of the member
+pub async fn add_thread_member(thread_id: &str, user_id: &str) -> Result<(), DescordError> {
+    let url = format!("channels/{thread_id}/thread-members/{user_id}");
+    request(Method::PUT, &url, None).await?;
+
+    Ok(())
+}
+
+/// Remove a member from a thread.
+/// Requires MANAGE_THREADS permission, unless the bot created the private thread.
+///
+/// # Arguments
+/// `thread_id` - The ID of the thread
+/// `user_id` - The ID of the member
+pub async fn remove_thread_member(thread_id: &str, user_id: &str) -> Result<(), DescordError> {
+    let url = format!("channels/{thread_id}/thread-members/{user_id}");
+    request(Method::DELETE, &url, None).await?;
+
+    Ok(())
+}
+
+/// Get a member of a thread.
+///
+/// # Arguments
+/// `thread_id` - The ID of the thread
+/// `user_id` - The ID of the member
+/// `with_member` - Whether to include the guild member
+pub async fn fetch_thread_member(
+    thread_id: &str,
+    user_id: &str,
+    with_member: bool,
+) -> Result<ThreadMember, DescordError> {
+    let url = format!("channels/{thread_id}/thread-members/{user_id}?with_member={with_member}");
+    let resp = request(Method::GET, &url, None)
+        .await?
+        .text()
+        .await
+        .map_err(DescordError::Http)?;
+
+    ThreadMember::deserialize_json(&resp)
+        .map(attach_current)
+        .map_err(DescordError::DeserializeJson)
+}
+
+/// Get the members of a thread, this needs the GUILD_MEMBERS intent.
+///
+/// # Arguments
+/// `thread_id` - The ID of the thread
+/// `with_member` - Whether to include the guild members
+pub async fn get_thread_members(
+    thread_id: &str,
+    with_member: bool,
+) -> Result<Vec<ThreadMember>, DescordError> {
+    let url = format!("channels/{thread_id}/thread-members?with_member={with_member}");
+    let resp = request(Method::GET, &url, None)
+        .await?
+        .text()
+        .await
+        .map_err(DescordError::Http)?;
+
+    Vec::<ThreadMember>::deserialize_json(&resp)
+        .map(attach_current)
+        .map_err(DescordError::DeserializeJson)
+}
+
+/// Get every active thread of a guild, public and private.
+///
+/// # Arguments
+/// `guild_id` - The ID of the guild
+pub async fn get_active_threads(guild_id: &str) -> Result<ThreadList, DescordError> {
+    let url = format!("guilds/{guild_id}/threads/active");
+    parse_thread_list(request(Method::GET, &url, None).await?).await
+}
+
+/// Get the archived public threads of a channel, most recently archived first.
+/// Requires READ_MESSAGE_HISTORY permission.
+///
+/// # Arguments
+/// `channel_id` - The ID of the channel
+/// `before` - Only threads archived before this ISO8601 timestamp
+/// `limit` - The maximum number of threads
+pub async fn get_public_archived_threads(
+    channel_id: &str,
+    before: Option<&str>,
+    limit: Option<usize>,
+) -> Result<ThreadList, DescordError> {
+    let url = archived_threads_url(
+        format!("channels/{channel_id}/threads/archived/public"),
+        before,
+        limit,
+    );
+    parse_thread_list(request(Method::GET, &url, None).await?).await
+}
+
+/// Get the archived private threads of a channel, most recently archived first.
+/// Requires READ_MESSAGE_HISTORY and MANAGE_THREADS permissions.
+///
+/// # Arguments
+/// `channel_id` - The ID of the channel
+/// `before` - Only threads archived before this ISO8601 timestamp
+/// `limit` - The maximum number of threads
+pub async fn get_private_archived_threads(
+    channel_id: &str,
+    before: Option<&str>,
+    limit: Option<usize>,
+) -> Result<ThreadList, DescordError> {
+    let url = archived_threads_url(
+        format!("channels/{channel_id}/threads/archived/private"),
+        before,
+        limit,
+    );
+    parse_thread_list(request(Method::GET, &url, None).await?).await
+}
+
+/// Get the archived private threads of a channel the bot joined, newest first.
+/// Requires READ_MESSAGE_HISTORY permission.
+///
+/// # Arguments
+/// `channel_id` - The ID of the channel
+/// `before` - Only threads with an ID lower than this thread ID
+/// `limit` - The maximum number of threads
+pub async fn get_joined_private_archived_threads(
+    channel_id: &str,
+    before: Option<&str>,
+    limit: Option<usize>,
+) -> Result<ThreadList, DescordError> {
+    let url = archived_threads_url(
+        format!("channels/{channel_id}/users/@me/threads/archived/private"),
+        before,
+        limit,
+    );
+    parse_thread_list(request(Method::GET, &url, None).await?).await
+}
+
+fn archived_threads_url(mut url: String, before: Option<&str>, limit: Option<usize>) -> String {
+    let mut params = Vec::new();
+
+    if let Some(before) = before {
+        params.push(format!(
+            "before={}",
+            utf8_percent_encode(before, NON_ALPHANUMERIC)
+        ));
+    }
+
+    if let Some(limit) = limit {
+        params.push(format!("limit={limit}"));
+    }
+
+    if !params.is_empty() {
+        url += &format!("?{}", params.join("&"));
+    }
+
+    url
+}
+
+async fn parse_thread_list(resp: Response) -> Result<ThreadList, DescordError> {
+    let text = resp.text().await.map_err(DescordError::Http)?;
+    let mut list = ThreadList::deserialize_json(&text).map_err(DescordError::DeserializeJson)?;
+
+    for thread in &mut list.threads {
+        thread.mention = format!("<#{}>", thread.id);
+    }
+
+    Ok(attach_current(list))
+}
@@ -25,25 +25,23 @@ pub async fn fetch_member(
     let resp = request(Method::GET, &url, None).await?;
     let mut member = Member::deserialize_json(&resp.text().await.map_err(DescordError::Http)?).map_err(DescordError::DeserializeJson)?;
     member.mention = format!("<@{}>", user_id);
-    Ok(member)
+    Ok(attach_current(member))
 }
 
 /// Kick a user.
 /// Requires KICK_MEMBERS permission.
+///
+/// # Arguments
+/// `guild_id` - The ID of the guild
+/// `user_id` - The ID of the member to kick
+/// `reason` - The reason shown in the audit log
 pub async fn kick_member(
     guild_id: &str,
     user_id: &str,
-    reason: Option<String>,
+    reason: Option<&str>,
 ) -> Result<(), DescordError> {
     let url = format!("guilds/{guild_id}/members/{user_id}");
-
-    if let Some(reason) = reason {
-        let mut header = HeaderMap::new();
-        header.insert("X-Audit-Log-Reason", reason.parse().unwrap());
-        request_with_headers(Method::DELETE, url, None, header).await?;
-    } else {
-        request(Method::DELETE, url, None).await?;
-    }
+    request_with_headers(Method::DELETE, url, None, audit_log_headers(reason)).await?;
 
     Ok(())
 }
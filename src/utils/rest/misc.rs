@@ -1,4 +1,104 @@
 use super::*;
+use std::path::PathBuf;
+
+use futures_util::{stream, Stream, TryStreamExt};
+use reqwest::Body;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::consts::ATTACHMENT_CHUNK_SIZE;
+use crate::models::attachment::AttachmentReader;
+
+/// The body of a rest request.
+pub(crate) enum RequestBody {
+    Empty,
+    Json(String),
+    /// A json payload along with files, sent as `multipart/form-data`.
+    Multipart {
+        payload_json: String,
+        files: Vec<FilePart>,
+    },
+}
+
+impl RequestBody {
+    /// Whether the body can be sent again, streamed readers are only read once.
+    pub fn is_replayable(&self) -> bool {
+        match self {
+            Self::Multipart { files, .. } => files
+                .iter()
+                .all(|file| !matches!(file.data, FileData::Reader(_))),
+            _ => true,
+        }
+    }
+}
+
+impl<T: AsRef<str>> From<Option<T>> for RequestBody {
+    fn from(data: Option<T>) -> Self {
+        match data {
+            Some(data) => Self::Json(data.as_ref().to_owned()),
+            None => Self::Empty,
+        }
+    }
+}
+
+/// A file uploaded in a multipart request.
+pub(crate) struct FilePart {
+    pub file_name: String,
+    pub data: FileData,
+    pub mime_type: String,
+}
+
+/// The content of a [`FilePart`].
+pub(crate) enum FileData {
+    Bytes(Bytes),
+    /// A file streamed from disk, it's opened again for every attempt.
+    File {
+        path: PathBuf,
+        length: u64,
+    },
+    /// A stream, it can only be sent once.
+    Reader(AttachmentReader),
+}
+
+impl FileData {
+    fn part(&self) -> Result<Part, DescordError> {
+        Ok(match self {
+            Self::Bytes(data) => Part::stream_with_length(data.clone(), data.len() as u64),
+            Self::File { path, length } => {
+                let path = path.clone();
+                let file = stream::once(async move { tokio::fs::File::open(path).await })
+                    .map_ok(read_chunks)
+                    .try_flatten();
+
+                Part::stream_with_length(Body::wrap_stream(file), *length)
+            }
+            Self::Reader(reader) => {
+                let reader = reader.take().ok_or_else(|| {
+                    DescordError::Other("An attachment stream can only be sent once".to_owned())
+                })?;
+
+                Part::stream(Body::wrap_stream(read_chunks(reader)))
+            }
+        })
+    }
+}
+
+/// Reads a stream in chunks, without buffering all of it.
+fn read_chunks<R>(reader: R) -> impl Stream<Item = std::io::Result<Bytes>> + Send + Sync
+where
+    R: AsyncRead + Unpin + Send + Sync + 'static,
+{
+    stream::try_unfold(reader, |mut reader| async move {
+        let mut chunk = vec![0; ATTACHMENT_CHUNK_SIZE];
+        let read = reader.read(&mut chunk).await?;
+
+        if read == 0 {
+            return Ok(None);
+        }
+
+        chunk.truncate(read);
+        Ok(Some((Bytes::from(chunk), reader)))
+    })
+}
 
 pub async fn request_with_headers<T: AsRef<str>>(
     method: Method,
@@ -6,14 +106,7 @@ pub async fn request_with_headers<T: AsRef<str>>(
     data: Option<T>,
     headers: HeaderMap<HeaderValue>,
 ) -> Result<Response, DescordError> {
-    let mut h = get_headers();
-    for (k, v) in headers.into_iter() {
-        if let Some(k) = k {
-            h.insert(k, v);
-        }
-    }
-
-    request_int(method, endpoint, data, h).await
+    request_with_body(method, endpoint.as_ref(), data.into(), headers).await
 }
 
 pub async fn request<T: AsRef<str>>(
@@ -21,75 +114,92 @@ pub async fn request<T: AsRef<str>>(
     endpoint: T,
     data: Option<T>,
 ) -> Result<Response, DescordError> {
-    request_int(method, endpoint, data, get_headers()).await
+    let http = Http::current()?;
+    request_int(
+        &http,
+        method,
+        endpoint.as_ref(),
+        data.into(),
+        get_headers(&http),
+    )
+    .await
 }
 
-async fn request_int<T: AsRef<str>>(
+/// Sends a request with the given body, see [`RequestBody`], and extra headers.
+pub(crate) async fn request_with_body(
     method: Method,
-    endpoint: T,
-    data: Option<T>,
+    endpoint: &str,
+    body: RequestBody,
     headers: HeaderMap<HeaderValue>,
 ) -> Result<Response, DescordError> {
-    let client = Client::new();
-    let url = format!("{}/{}", API, endpoint.as_ref());
+    let http = Http::current()?;
+    let mut h = get_headers(&http);
+    for (k, v) in headers.into_iter() {
+        if let Some(k) = k {
+            h.insert(k, v);
+        }
+    }
 
-    let mut request_builder = client.request(method, &url);
-    request_builder = request_builder.headers(headers);
+    request_int(&http, method, endpoint, body, h).await
+}
 
-    if let Some(body) = data {
-        request_builder = request_builder.body(body.as_ref().to_string());
-    }
+async fn request_int(
+    http: &Http,
+    method: Method,
+    endpoint: &str,
+    body: RequestBody,
+    mut headers: HeaderMap<HeaderValue>,
+) -> Result<Response, DescordError> {
+    let url = format!("{}/{endpoint}", http.api_url());
 
-    let bucket = ENDPOINT_BUCKET_MAP
-        .lock()
-        .await
-        .get(endpoint.as_ref())
-        .cloned();
-    let seen;
-    if let Some(bucket) = bucket {
-        wait_for_rate_limit(&bucket).await;
-        seen = true;
-    } else {
-        seen = false;
+    if let RequestBody::Multipart { .. } = body {
+        // reqwest sets the content type along with the form boundary
+        headers.remove("Content-Type");
     }
 
-    let cloned = request_builder
-        .try_clone()
-        .ok_or_else(|| DescordError::Other("Failed to clone request builder".to_string()))?;
-    let mut response = cloned.send().await?;
-
-    while response.status() == StatusCode::TOO_MANY_REQUESTS {
-        let retry_after = response
-            .headers()
-            .get("retry-after")
-            .and_then(|v| v.to_str().ok())
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(0.0);
-
-        log::warn!(
-            "Rate limited on endpoint: {}, retrying after {} seconds",
-            endpoint.as_ref(),
-            retry_after
-        );
+    // multipart forms can't be cloned, so the request is built again for every attempt
+    let build = || -> Result<RequestBuilder, DescordError> {
+        let request = http
+            .client()
+            .request(method.clone(), &url)
+            .headers(headers.clone());
 
-        sleep(Duration::from_secs_f32(retry_after)).await;
+        Ok(match &body {
+            RequestBody::Empty => request,
+            RequestBody::Json(json) => request.body(json.clone()),
+            RequestBody::Multipart {
+                payload_json,
+                files,
+            } => request.multipart(multipart_form(Some(payload_json), files)?),
+        })
+    };
 
-        let cloned = request_builder
-            .try_clone()
-            .ok_or_else(|| DescordError::Other("Failed to clone request builder".to_string()))?;
-        response = cloned.send().await?;
-    }
+    let route = Route::new(&method, endpoint);
+    let policy = http.retry_policy();
+    let mut retries = 0;
+
+    let response = loop {
+        let result = http.ratelimiter().send(&route, build).await;
+
+        if retries + 1 < policy.max_attempts
+            && policy.applies_to(&method)
+            && body.is_replayable()
+            && is_transient(&result)
+        {
+            retries += 1;
+            let delay = policy.delay(retries);
+
+            log::warn!(
+                "Request to {route} failed, retrying in {:.2} seconds (retry {retries})",
+                delay.as_secs_f32()
+            );
 
-    if let Some(bucket) = response.headers().get("x-ratelimit-bucket") {
-        let bucket = bucket.to_str().unwrap_or_default();
-        update_rate_limit_info(response.headers(), bucket).await;
-        if !seen {
-            ENDPOINT_BUCKET_MAP
-                .lock()
-                .await
-                .put(endpoint.as_ref().to_string(), bucket.to_string());
+            sleep(delay).await;
+            continue;
         }
-    }
+
+        break result?;
+    };
 
     // Check for API errors (4xx/5xx)
     let status = response.status();
@@ -100,10 +210,7 @@ async fn request_int<T: AsRef<str>>(
         // Try to parse Discord's error JSON: {"code": ..., "message": ...}
         let (code, message) = if let Ok(parsed) = json::parse(&body) {
             let code = parsed["code"].as_u64();
-            let message = parsed["message"]
-                .as_str()
-                .unwrap_or(&body)
-                .to_string();
+            let message = parsed["message"].as_str().unwrap_or(&body).to_string();
             (code, message)
         } else {
             (None, body)
@@ -119,50 +226,55 @@ async fn request_int<T: AsRef<str>>(
     Ok(response)
 }
 
-pub async fn update_rate_limit_info(headers: &HeaderMap<HeaderValue>, bucket: &str) {
-    let remaining = headers
-        .get("x-ratelimit-remaining")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(0);
-    let reset = headers
-        .get("x-ratelimit-reset")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(0.0);
-
-    let rate_limit_info = RateLimitInfo { remaining, reset };
-
-    RATE_LIMITS
-        .lock()
-        .await
-        .put(bucket.to_string(), rate_limit_info);
-}
-
-pub(crate) async fn wait_for_rate_limit(bucket: &str) {
-    if let Some(rate_limit_info) = RATE_LIMITS.lock().await.get(bucket) {
-        log::info!("Rate limit hit: {:?}", rate_limit_info);
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs_f64();
-        if rate_limit_info.remaining == 0 && rate_limit_info.reset > now {
-            let delay = Duration::from_secs_f64(rate_limit_info.reset - now);
-            sleep(delay).await;
-        }
+pub(crate) fn multipart_form(
+    payload_json: Option<&str>,
+    files: &[FilePart],
+) -> Result<Form, DescordError> {
+    let mut form = Form::new();
+
+    if let Some(payload_json) = payload_json {
+        let payload = Part::text(payload_json.to_owned()).mime_str("application/json")?;
+        form = form.part("payload_json", payload);
+    }
+
+    for (i, file) in files.iter().enumerate() {
+        let part = file
+            .data
+            .part()?
+            .file_name(file.file_name.clone())
+            .mime_str(&file.mime_type)?;
+
+        form = form.part(format!("files[{i}]"), part);
     }
+
+    Ok(form)
 }
 
-pub fn get_headers() -> HeaderMap {
+/// Returns the headers adding a reason to the audit log entry of a request.
+pub(crate) fn audit_log_headers(reason: Option<&str>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+
+    if let Some(reason) = reason {
+        // the reason is url encoded, so it may contain any character
+        let reason = utf8_percent_encode(reason, NON_ALPHANUMERIC).to_string();
+        headers.insert("X-Audit-Log-Reason", reason.parse().unwrap());
+    }
+
+    headers
+}
+
+pub fn get_headers(http: &Http) -> HeaderMap {
     let mut map = HeaderMap::new();
 
     map.insert("Content-Type", "application/json".parse().unwrap());
-    map.insert(
-        "Authorization",
-        format!("Bot {}", TOKEN.lock().unwrap().as_ref().unwrap())
-            .parse()
-            .unwrap(),
-    );
+
+    // webhook clients don't have a token
+    if !http.token().is_empty() {
+        map.insert(
+            "Authorization",
+            format!("Bot {}", http.token()).parse().unwrap(),
+        );
+    }
 
     map
 }
@@ -179,6 +291,14 @@ pub async fn fetch_bot_id() -> Result<String, DescordError> {
         .ok_or_else(|| DescordError::JsonParse("Missing 'id' field in bot info".to_string()))
 }
 
+/// Returns the gateway url along with the recommended shard count
+/// and the session start limit of the bot.
+pub async fn fetch_gateway_bot() -> Result<GatewayBot, DescordError> {
+    let response = request(Method::GET, "gateway/bot", None).await?;
+    let text = response.text().await.map_err(DescordError::Http)?;
+    GatewayBot::deserialize_json(&text).map_err(DescordError::DeserializeJson)
+}
+
 /// Returns a new DM channel with a user (or return
 /// an existing one). Returns a `DirectMessageChannel` object.
 pub async fn fetch_dm(user_id: &str) -> Result<DirectMessageChannel, DescordError> {
@@ -1,30 +1,42 @@
 mod guild;
 mod misc;
+mod observer;
+mod ratelimit;
+mod retry;
+mod webhook;
 
 pub use guild::*;
 pub(crate) use misc::*;
+pub(crate) use ratelimit::{RateLimiter, Route};
+pub(crate) use retry::is_transient;
+pub use observer::*;
+pub use retry::RetryPolicy;
+pub use webhook::*;
 
 pub use misc::*;
 
-use crate::cache::*;
-use crate::client::TOKEN;
-use crate::consts::API;
+use crate::client::{attach_current, Attach, Http};
 use crate::internals::{DescordError, DiscordApiError};
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use crate::models::application_command::ApplicationCommand;
 use crate::models::channel::Channel;
 use crate::models::dm_channel::DirectMessageChannel;
+use crate::models::gateway::GatewayBot;
 use crate::models::message_response::CreateMessageData;
 
 use crate::prelude::{Guild, Member, Message};
 use crate::prelude::{Role, User};
 
+use bytes::Bytes;
 use futures_util::TryFutureExt;
 use json::{object, JsonValue};
 use log::info;
 use nanoserde::{DeJson, SerJson};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use reqwest::header::HeaderValue;
-use reqwest::{header::HeaderMap, Client, Error, Method, Response, StatusCode};
+use reqwest::multipart::{Form, Part};
+use reqwest::{header::HeaderMap, Error, Method, RequestBuilder, Response, StatusCode};
 use tokio::time::sleep;
@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use reqwest::Method;
+
+/// Receives a notification for every rest request a client sends.
+///
+/// Both methods do nothing by default, implement the ones you need and add
+/// the observer with [`ClientBuilder::rest_observer`](crate::ClientBuilder::rest_observer).
+/// They are called from the task sending the request, so they should return quickly.
+///
+/// # Examples
+///
+/// ```ignore
+/// struct Metrics;
+///
+/// impl RestObserver for Metrics {
+///     fn on_request(&self, event: &RequestEvent) {
+///         println!("{} {} -> {:?} in {:?}", event.method, event.route, event.status, event.latency);
+///     }
+///
+///     fn on_rate_limit(&self, event: &RateLimitEvent) {
+///         println!("{} delayed by {:?} ({:?})", event.route, event.delay, event.kind);
+///     }
+/// }
+/// ```
+pub trait RestObserver: Send + Sync {
+    /// Called once a request got a response, or failed to get one.
+    fn on_request(&self, event: &RequestEvent) {
+        let _ = event;
+    }
+
+    /// Called before a request waits for a rate limit.
+    fn on_rate_limit(&self, event: &RateLimitEvent) {
+        let _ = event;
+    }
+}
+
+/// A request sent to the rest api, retries are reported as separate requests.
+#[derive(Debug, Clone)]
+pub struct RequestEvent {
+    pub method: Method,
+    /// The endpoint with the ids other than the major parameter replaced, e.g. `channels/123/messages/:id`.
+    ///
+    /// Webhook and interaction tokens are replaced by `:token`, e.g. `webhooks/123/:token`.
+    pub route: String,
+    /// The status of the response, `None` if the request failed before getting one.
+    pub status: Option<u16>,
+    /// Time between sending the request and receiving the response headers.
+    pub latency: Duration,
+    /// The rate limit bucket of the route, as returned by discord.
+    pub bucket: Option<String>,
+    /// Requests left in the bucket.
+    pub remaining: Option<u32>,
+    /// Time until the bucket resets.
+    pub reset_after: Option<Duration>,
+}
+
+/// A request delayed by a rate limit.
+#[derive(Debug, Clone)]
+pub struct RateLimitEvent {
+    pub method: Method,
+    /// The endpoint with the ids other than the major parameter and tokens replaced, see [`RequestEvent::route`].
+    pub route: String,
+    /// The rate limit bucket of the route, if known.
+    pub bucket: Option<String>,
+    /// How long the request waits.
+    pub delay: Duration,
+    pub kind: RateLimitKind,
+}
+
+/// The limit that delayed a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitKind {
+    /// The bucket of the route has no requests left.
+    Bucket,
+    /// The global limit of the bot was reached.
+    Global,
+    /// A limit shared by everyone using the resource, e.g. a busy channel.
+    Shared,
+}
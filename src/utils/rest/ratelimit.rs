@@ -0,0 +1,447 @@
+//! Rate limiting of rest requests.
+//!
+//! https://discord.com/developers/docs/topics/rate-limits
+
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use lru::LruCache;
+use reqwest::header::HeaderMap;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use tokio::sync::Mutex;
+use tokio::time::{sleep, sleep_until, Instant};
+
+use super::{RateLimitEvent, RateLimitKind, RequestEvent, RestObserver};
+use crate::consts::{GLOBAL_RATE_LIMIT, GLOBAL_RATE_LIMIT_WINDOW};
+use crate::internals::DescordError;
+
+/// Parameters that get their own rate limit bucket.
+const MAJOR_PARAMETERS: [&str; 4] = ["channels", "guilds", "webhooks", "interactions"];
+
+/// A rest route, the endpoint with every id except the major parameter removed.
+///
+/// Webhook and interaction tokens are secrets, they are only part of the bucket key.
+/// The route is displayed (and reported to observers) with them replaced by `:token`.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct Route {
+    method: Method,
+    /// The path of the route without tokens, e.g. `webhooks/123/:token/messages/:id`.
+    path: String,
+    /// The method and path of the route including webhook tokens, e.g. `POST channels/123/messages/:id`.
+    key: String,
+    /// The major parameter, e.g. `channels/123`.
+    major: String,
+    /// Whether the endpoint contains a webhook or interaction token.
+    secret: bool,
+    /// Interaction responses are not bound to the global limit.
+    global: bool,
+}
+
+impl Route {
+    pub fn new(method: &Method, endpoint: &str) -> Self {
+        let path = endpoint.split('?').next().unwrap_or_default();
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        let mut major = String::new();
+        let mut secret = false;
+        let mut normalized = Vec::with_capacity(segments.len());
+        let mut redacted = Vec::with_capacity(segments.len());
+
+        for (i, segment) in segments.iter().enumerate() {
+            let previous = i.checked_sub(1).map(|i| segments[i]);
+            let before_previous = i.checked_sub(2).map(|i| segments[i]);
+
+            let is_id = segment.bytes().all(|b| b.is_ascii_digit());
+            let is_major = is_id
+                && major.is_empty()
+                && previous.is_some_and(|previous| MAJOR_PARAMETERS.contains(&previous));
+
+            let (segment, shown) = if is_major {
+                major = format!("{}/{segment}", previous.unwrap());
+                // every interaction has its own bucket, its id is only shown as `:id`
+                let shown = if previous == Some("interactions") {
+                    ":id"
+                } else {
+                    *segment
+                };
+                (*segment, shown)
+            } else if before_previous == Some("webhooks") && !major.is_empty() {
+                // the webhook token is part of the major parameter
+                major = format!("{major}/{segment}");
+                secret = true;
+                (*segment, ":token")
+            } else if is_id {
+                (":id", ":id")
+            } else if previous == Some("reactions") {
+                (":emoji", ":emoji")
+            } else if before_previous == Some("interactions") {
+                secret = true;
+                (":token", ":token")
+            } else {
+                (*segment, *segment)
+            };
+
+            normalized.push(segment);
+            redacted.push(shown);
+        }
+
+        Self {
+            method: method.clone(),
+            key: format!("{method} {}", normalized.join("/")),
+            path: redacted.join("/"),
+            major,
+            secret,
+            global: segments.first() != Some(&"interactions"),
+        }
+    }
+
+    /// Whether the endpoint contains a token that must not be logged.
+    pub fn is_secret(&self) -> bool {
+        self.secret
+    }
+}
+
+impl std::fmt::Display for Route {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.method, self.path)
+    }
+}
+
+impl std::fmt::Debug for Route {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // the key and major parameter may contain a token
+        f.debug_struct("Route")
+            .field("method", &self.method)
+            .field("path", &self.path)
+            .field("global", &self.global)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The state of a rate limit bucket.
+///
+/// The mutex is held while a request of the bucket is in flight,
+/// so requests sharing a bucket are sent one after another.
+#[derive(Debug, Default)]
+struct Bucket {
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug, Default)]
+struct BucketState {
+    /// The hash discord returned for the bucket.
+    hash: Option<String>,
+    remaining: Option<u32>,
+    reset: Option<Instant>,
+}
+
+impl BucketState {
+    /// Returns how long to wait until the bucket allows another request.
+    fn delay(&self) -> Option<Duration> {
+        match (self.remaining, self.reset) {
+            (Some(0), Some(reset)) => Some(reset.saturating_duration_since(Instant::now())),
+            _ => None,
+        }
+        .filter(|delay| !delay.is_zero())
+    }
+
+    fn update(&mut self, headers: &HeaderMap) {
+        if let Some(hash) = headers
+            .get("x-ratelimit-bucket")
+            .and_then(|v| v.to_str().ok())
+        {
+            self.hash = Some(hash.to_owned());
+        }
+
+        if let Some(remaining) = header(headers, "x-ratelimit-remaining") {
+            self.remaining = Some(remaining as u32);
+        }
+
+        if let Some(reset_after) = header(headers, "x-ratelimit-reset-after") {
+            self.reset = Some(Instant::now() + Duration::from_secs_f64(reset_after));
+        }
+    }
+}
+
+/// The global limit of the bot, shared by every route.
+#[derive(Debug)]
+struct GlobalLimit {
+    /// Start of the current window and the number of requests sent in it.
+    window: Mutex<(Instant, u32)>,
+    /// Set when discord reports that the global limit was hit.
+    blocked_until: Mutex<Option<Instant>>,
+}
+
+impl GlobalLimit {
+    /// Waits until the global limit allows another request, `on_delay` is called before waiting.
+    async fn acquire(&self, mut on_delay: impl FnMut(Duration)) {
+        let blocked_until = *self.blocked_until.lock().await;
+        if let Some(until) = blocked_until.filter(|until| *until > Instant::now()) {
+            on_delay(until - Instant::now());
+            sleep_until(until).await;
+        }
+
+        let mut window = self.window.lock().await;
+        let now = Instant::now();
+
+        if now.duration_since(window.0) >= GLOBAL_RATE_LIMIT_WINDOW {
+            *window = (now, 0);
+        }
+
+        if window.1 >= GLOBAL_RATE_LIMIT {
+            // holding the lock makes the other requests wait for the next window too
+            let next_window = window.0 + GLOBAL_RATE_LIMIT_WINDOW;
+            on_delay(next_window.saturating_duration_since(now));
+            sleep_until(next_window).await;
+            *window = (Instant::now(), 0);
+        }
+
+        window.1 += 1;
+    }
+
+    async fn block(&self, retry_after: Duration) {
+        *self.blocked_until.lock().await = Some(Instant::now() + retry_after);
+    }
+}
+
+/// Rate limiter of a client, queues requests before they would hit a limit.
+pub(crate) struct RateLimiter {
+    /// The bucket hash discord returned for each route.
+    hashes: Mutex<LruCache<String, String>>,
+    /// Buckets by hash and major parameter, or by route while the hash isn't known.
+    buckets: Mutex<LruCache<String, Arc<Bucket>>>,
+    global: GlobalLimit,
+    observer: Option<Arc<dyn RestObserver>>,
+}
+
+impl RateLimiter {
+    pub fn new(capacity: usize, observer: Option<Arc<dyn RestObserver>>) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+
+        Self {
+            hashes: Mutex::new(LruCache::new(capacity)),
+            buckets: Mutex::new(LruCache::new(capacity)),
+            global: GlobalLimit {
+                window: Mutex::new((Instant::now(), 0)),
+                blocked_until: Mutex::new(None),
+            },
+            observer,
+        }
+    }
+
+    /// Sends a request once the limits allow it, retrying it after a 429.
+    ///
+    /// `build` creates the request, it is called again for every retry.
+    pub async fn send(
+        &self,
+        route: &Route,
+        build: impl Fn() -> Result<RequestBuilder, DescordError>,
+    ) -> Result<Response, DescordError> {
+        let bucket = self.bucket(route).await;
+        let mut state = bucket.state.lock().await;
+
+        loop {
+            if let Some(delay) = state.delay() {
+                log::info!("Bucket of {route} exhausted, waiting {delay:?}");
+                self.report_delay(route, &state, delay, RateLimitKind::Bucket);
+                sleep(delay).await;
+            }
+
+            if route.global {
+                self.global
+                    .acquire(|delay| self.report_delay(route, &state, delay, RateLimitKind::Global))
+                    .await;
+            }
+
+            let request = build()?;
+
+            let sent = Instant::now();
+            let result = request.send().await;
+            let latency = sent.elapsed();
+
+            let response = match result {
+                Ok(response) => response,
+                Err(e) => {
+                    self.report_request(route, None, latency, &state);
+
+                    // the error would print the url along with the token
+                    let e = if route.is_secret() {
+                        e.without_url()
+                    } else {
+                        e
+                    };
+                    return Err(e.into());
+                }
+            };
+
+            let headers = response.headers();
+            state.update(headers);
+            self.report_request(route, Some(response.status()), latency, &state);
+
+            if let Some(hash) = headers
+                .get("x-ratelimit-bucket")
+                .and_then(|v| v.to_str().ok())
+            {
+                self.learn_hash(route, hash, &bucket).await;
+            }
+
+            if response.status() != StatusCode::TOO_MANY_REQUESTS {
+                return Ok(response);
+            }
+
+            let retry_after =
+                Duration::from_secs_f64(header(headers, "retry-after").unwrap_or_default());
+            let global = headers
+                .get("x-ratelimit-global")
+                .is_some_and(|v| v.as_bytes() == b"true");
+            let scope = headers
+                .get("x-ratelimit-scope")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("user");
+
+            if global {
+                log::warn!("Hit the global rate limit, retrying after {retry_after:?}");
+                self.global.block(retry_after).await;
+            } else if scope == "shared" {
+                // shared limits belong to the resource, they don't say anything about our bucket
+                log::warn!("Hit a shared rate limit on {route}, retrying after {retry_after:?}");
+                self.report_delay(route, &state, retry_after, RateLimitKind::Shared);
+                sleep(retry_after).await;
+            } else {
+                log::warn!("Rate limited on {route}, retrying after {retry_after:?}");
+                state.remaining = Some(0);
+                state.reset = Some(Instant::now() + retry_after);
+            }
+        }
+    }
+
+    fn report_request(
+        &self,
+        route: &Route,
+        status: Option<StatusCode>,
+        latency: Duration,
+        state: &BucketState,
+    ) {
+        let Some(observer) = &self.observer else {
+            return;
+        };
+
+        observer.on_request(&RequestEvent {
+            method: route.method.clone(),
+            route: route.path.clone(),
+            status: status.map(|status| status.as_u16()),
+            latency,
+            bucket: state.hash.clone(),
+            remaining: state.remaining,
+            reset_after: state
+                .reset
+                .map(|reset| reset.saturating_duration_since(Instant::now())),
+        });
+    }
+
+    fn report_delay(
+        &self,
+        route: &Route,
+        state: &BucketState,
+        delay: Duration,
+        kind: RateLimitKind,
+    ) {
+        let Some(observer) = &self.observer else {
+            return;
+        };
+
+        observer.on_rate_limit(&RateLimitEvent {
+            method: route.method.clone(),
+            route: route.path.clone(),
+            bucket: state.hash.clone(),
+            delay,
+            kind,
+        });
+    }
+
+    async fn bucket(&self, route: &Route) -> Arc<Bucket> {
+        let key = match self.hashes.lock().await.get(&route.key) {
+            Some(hash) => format!("{hash}:{}", route.major),
+            None => route.key.clone(),
+        };
+
+        let mut buckets = self.buckets.lock().await;
+        Arc::clone(buckets.get_or_insert(key, Default::default))
+    }
+
+    /// Remembers the hash of a route, the bucket keeps being used for the hash
+    /// so requests that were queued on the route still share it.
+    async fn learn_hash(&self, route: &Route, hash: &str, bucket: &Arc<Bucket>) {
+        let mut hashes = self.hashes.lock().await;
+        if hashes.get(&route.key).is_some_and(|known| known == hash) {
+            return;
+        }
+
+        hashes.put(route.key.clone(), hash.to_owned());
+        drop(hashes);
+
+        let mut buckets = self.buckets.lock().await;
+        buckets.get_or_insert(format!("{hash}:{}", route.major), || Arc::clone(bucket));
+    }
+}
+
+fn header(headers: &HeaderMap, name: &str) -> Option<f64> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn route_keeps_major_parameter() {
+        let route = Route::new(
+            &Method::POST,
+            "channels/123/messages/456/reactions/%F0%9F%91%8D/@me",
+        );
+
+        assert_eq!(route.path, "channels/123/messages/:id/reactions/:emoji/@me");
+        assert_eq!(route.major, "channels/123");
+        assert!(route.global);
+        assert!(!route.is_secret());
+    }
+
+    #[test]
+    fn route_redacts_webhook_token() {
+        let route = Route::new(
+            &Method::PATCH,
+            "webhooks/123/s3cr3t/messages/@original?wait=true",
+        );
+
+        assert_eq!(route.path, "webhooks/123/:token/messages/@original");
+        assert_eq!(
+            route.to_string(),
+            "PATCH webhooks/123/:token/messages/@original"
+        );
+        assert!(!format!("{route:?}").contains("s3cr3t"));
+        assert!(route.is_secret());
+
+        // the bucket of a webhook still depends on its token
+        assert_eq!(route.major, "webhooks/123/s3cr3t");
+        assert!(route.key.contains("s3cr3t"));
+    }
+
+    #[test]
+    fn route_redacts_interaction_token() {
+        let route = Route::new(&Method::POST, "interactions/123/s3cr3t/callback");
+
+        assert_eq!(route.to_string(), "POST interactions/:id/:token/callback");
+        assert!(!route.key.contains("s3cr3t"));
+        assert!(!route.global);
+
+        // interactions don't share a bucket
+        let other = Route::new(&Method::POST, "interactions/456/s3cr3t/callback");
+        assert_eq!(route.major, "interactions/123");
+        assert_ne!(route.key, other.key);
+        assert_ne!(route.major, other.major);
+    }
+}
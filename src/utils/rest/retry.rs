@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::{Method, Response};
+
+use crate::internals::DescordError;
+
+/// Controls how rest requests are retried after a server error or a transport error.
+///
+/// Only idempotent requests (`GET`, `PUT` and `DELETE`) are retried unless
+/// `retry_non_idempotent` is set, since retrying a `POST` could e.g. send a message twice.
+/// The delay between attempts grows exponentially from `base_delay` up to `max_delay`.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Number of attempts including the first one, `1` disables retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub base_delay: Duration,
+    /// Upper bound for the delay between two attempts.
+    pub max_delay: Duration,
+    /// Whether `POST` and `PATCH` requests are retried too.
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn disabled() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the delay before the given retry, starting at 1.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+
+        // jitter the delay between 50% and 100%
+        delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+
+    /// Returns whether requests with the given method are retried.
+    pub fn applies_to(&self, method: &Method) -> bool {
+        match *method {
+            Method::GET | Method::PUT | Method::DELETE | Method::HEAD => true,
+            _ => self.retry_non_idempotent,
+        }
+    }
+}
+
+/// Returns whether the request failed in a way that may succeed when retried.
+///
+/// Request errors are only transient if the connection failed while sending, e.g. it was reset.
+/// Other request errors, like an invalid body, fail again on every attempt.
+pub(crate) fn is_transient(result: &Result<Response, DescordError>) -> bool {
+    match result {
+        Ok(response) => response.status().is_server_error(),
+        Err(DescordError::Http(e)) => {
+            e.is_connect() || e.is_timeout() || (e.is_request() && caused_by_io(e))
+        }
+        Err(_) => false,
+    }
+}
+
+fn caused_by_io(error: &reqwest::Error) -> bool {
+    let mut source = std::error::Error::source(error);
+
+    while let Some(error) = source {
+        if error.is::<std::io::Error>() {
+            return true;
+        }
+
+        source = error.source();
+    }
+
+    false
+}
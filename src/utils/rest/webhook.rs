@@ -0,0 +1,259 @@
+use crate::models::webhook::{ExecuteWebhook, ModifyWebhook, Webhook};
+
+use super::*;
+
+/// Create a webhook in a channel.
+/// Requires MANAGE_WEBHOOKS permission.
+///
+/// # Arguments
+/// `channel_id` - The ID of the channel
+/// `name` - The name of the webhook, 1-80 characters
+/// `avatar` - The avatar as a data uri, e.g. `data:image/png;base64,...`
+/// `reason` - The reason shown in the audit log
+pub async fn create_webhook(
+    channel_id: &str,
+    name: &str,
+    avatar: Option<&str>,
+    reason: Option<&str>,
+) -> Result<Webhook, DescordError> {
+    let url = format!("channels/{channel_id}/webhooks");
+    let mut body = object! { name: name };
+
+    if let Some(avatar) = avatar {
+        body["avatar"] = avatar.into();
+    }
+
+    let resp = request_with_headers(
+        Method::POST,
+        url,
+        Some(body.dump()),
+        audit_log_headers(reason),
+    )
+    .await?;
+
+    parse_response(resp).await
+}
+
+/// Get the webhooks of a channel.
+/// Requires MANAGE_WEBHOOKS permission.
+///
+/// # Arguments
+/// `channel_id` - The ID of the channel
+pub async fn get_channel_webhooks(channel_id: &str) -> Result<Vec<Webhook>, DescordError> {
+    let url = format!("channels/{channel_id}/webhooks");
+    parse_response(request(Method::GET, &url, None).await?).await
+}
+
+/// Get the webhooks of a guild.
+/// Requires MANAGE_WEBHOOKS permission.
+///
+/// # Arguments
+/// `guild_id` - The ID of the guild
+pub async fn get_guild_webhooks(guild_id: &str) -> Result<Vec<Webhook>, DescordError> {
+    let url = format!("guilds/{guild_id}/webhooks");
+    parse_response(request(Method::GET, &url, None).await?).await
+}
+
+/// Get a webhook by ID.
+///
+/// # Arguments
+/// `webhook_id` - The ID of the webhook
+pub async fn fetch_webhook(webhook_id: &str) -> Result<Webhook, DescordError> {
+    let url = format!("webhooks/{webhook_id}");
+    parse_response(request(Method::GET, &url, None).await?).await
+}
+
+/// Get a webhook by ID and token, this doesn't need a bot token.
+///
+/// # Arguments
+/// `webhook_id` - The ID of the webhook
+/// `token` - The token of the webhook
+pub async fn fetch_webhook_with_token(
+    webhook_id: &str,
+    token: &str,
+) -> Result<Webhook, DescordError> {
+    let url = format!("webhooks/{webhook_id}/{token}");
+    parse_response(request(Method::GET, &url, None).await?).await
+}
+
+/// Modify a webhook, only the fields set in `data` are changed.
+/// Requires MANAGE_WEBHOOKS permission.
+///
+/// # Arguments
+/// `webhook_id` - The ID of the webhook
+/// `data` - The changes, see [`ModifyWebhook`]
+pub async fn modify_webhook(
+    webhook_id: &str,
+    data: ModifyWebhook,
+) -> Result<Webhook, DescordError> {
+    let url = format!("webhooks/{webhook_id}");
+    modify_webhook_int(url, data).await
+}
+
+/// Modify a webhook by ID and token, this doesn't need a bot token.
+/// The channel of the webhook can't be changed this way.
+///
+/// # Arguments
+/// `webhook_id` - The ID of the webhook
+/// `token` - The token of the webhook
+/// `data` - The changes, see [`ModifyWebhook`]
+pub async fn modify_webhook_with_token(
+    webhook_id: &str,
+    token: &str,
+    data: ModifyWebhook,
+) -> Result<Webhook, DescordError> {
+    let url = format!("webhooks/{webhook_id}/{token}");
+    modify_webhook_int(url, data).await
+}
+
+async fn modify_webhook_int(url: String, data: ModifyWebhook) -> Result<Webhook, DescordError> {
+    let resp = request_with_headers(
+        Method::PATCH,
+        url,
+        Some(data.body.dump()),
+        audit_log_headers(data.reason.as_deref()),
+    )
+    .await?;
+
+    parse_response(resp).await
+}
+
+/// Delete a webhook.
+/// Requires MANAGE_WEBHOOKS permission.
+///
+/// # Arguments
+/// `webhook_id` - The ID of the webhook
+/// `reason` - The reason shown in the audit log
+pub async fn delete_webhook(webhook_id: &str, reason: Option<&str>) -> Result<(), DescordError> {
+    let url = format!("webhooks/{webhook_id}");
+    request_with_headers(Method::DELETE, url, None, audit_log_headers(reason)).await?;
+
+    Ok(())
+}
+
+/// Delete a webhook by ID and token, this doesn't need a bot token.
+///
+/// # Arguments
+/// `webhook_id` - The ID of the webhook
+/// `token` - The token of the webhook
+pub async fn delete_webhook_with_token(webhook_id: &str, token: &str) -> Result<(), DescordError> {
+    let url = format!("webhooks/{webhook_id}/{token}");
+    request(Method::DELETE, &url, None).await?;
+
+    Ok(())
+}
+
+/// Send a message with a webhook.
+///
+/// Returns the message if [`ExecuteWebhook::wait`] is set, `None` otherwise.
+///
+/// # Arguments
+/// `webhook_id` - The ID of the webhook
+/// `token` - The token of the webhook
+/// `data` - The message, see [`ExecuteWebhook`]
+pub async fn execute_webhook(
+    webhook_id: &str,
+    token: &str,
+    data: ExecuteWebhook,
+) -> Result<Option<Message>, DescordError> {
+    let mut url = format!("webhooks/{webhook_id}/{token}?wait={}", data.wait);
+
+    if let Some(thread_id) = &data.thread_id {
+        url += &format!("&thread_id={thread_id}");
+    }
+
+    let resp = request_message(
+        Method::POST,
+        &url,
+        data.to_json_value(),
+        &data.data.attachments,
+    )
+    .await?;
+
+    if data.wait {
+        parse_response(resp).await.map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Get a message sent by a webhook.
+///
+/// # Arguments
+/// `webhook_id` - The ID of the webhook
+/// `token` - The token of the webhook
+/// `message_id` - The ID of the message
+/// `thread_id` - The ID of the thread the message is in
+pub async fn fetch_webhook_message(
+    webhook_id: &str,
+    token: &str,
+    message_id: &str,
+    thread_id: Option<&str>,
+) -> Result<Message, DescordError> {
+    let url = webhook_message_url(webhook_id, token, message_id, thread_id);
+    parse_response(request(Method::GET, &url, None).await?).await
+}
+
+/// Edit a message sent by a webhook.
+///
+/// # Arguments
+/// `webhook_id` - The ID of the webhook
+/// `token` - The token of the webhook
+/// `message_id` - The ID of the message
+/// `data` - The new content of the message
+/// `thread_id` - The ID of the thread the message is in
+pub async fn edit_webhook_message(
+    webhook_id: &str,
+    token: &str,
+    message_id: &str,
+    data: impl Into<CreateMessageData>,
+    thread_id: Option<&str>,
+) -> Result<Message, DescordError> {
+    let url = webhook_message_url(webhook_id, token, message_id, thread_id);
+    let data: CreateMessageData = data.into();
+
+    let resp =
+        request_message(Method::PATCH, &url, data.to_json_value(), &data.attachments).await?;
+    parse_response(resp).await
+}
+
+/// Delete a message sent by a webhook.
+///
+/// # Arguments
+/// `webhook_id` - The ID of the webhook
+/// `token` - The token of the webhook
+/// `message_id` - The ID of the message
+/// `thread_id` - The ID of the thread the message is in
+pub async fn delete_webhook_message(
+    webhook_id: &str,
+    token: &str,
+    message_id: &str,
+    thread_id: Option<&str>,
+) -> Result<(), DescordError> {
+    let url = webhook_message_url(webhook_id, token, message_id, thread_id);
+    request(Method::DELETE, &url, None).await?;
+
+    Ok(())
+}
+
+fn webhook_message_url(
+    webhook_id: &str,
+    token: &str,
+    message_id: &str,
+    thread_id: Option<&str>,
+) -> String {
+    let mut url = format!("webhooks/{webhook_id}/{token}/messages/{message_id}");
+
+    if let Some(thread_id) = thread_id {
+        url += &format!("?thread_id={thread_id}");
+    }
+
+    url
+}
+
+async fn parse_response<T: DeJson + Attach>(resp: Response) -> Result<T, DescordError> {
+    let text = resp.text().await.map_err(DescordError::Http)?;
+    T::deserialize_json(&text)
+        .map(attach_current)
+        .map_err(DescordError::DeserializeJson)
+}
@@ -0,0 +1,103 @@
+use flate2::{Decompress, FlushDecompress};
+
+use crate::internals::DescordError;
+
+/// Every complete zlib-stream message ends with this suffix.
+const ZLIB_SUFFIX: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// Decoder for `compress=zlib-stream`.
+///
+/// The whole connection is a single zlib stream, so one context has to be
+/// kept for its lifetime and a new one created for every new connection.
+pub(crate) struct ZlibStream {
+    inflate: Decompress,
+    buffer: Vec<u8>,
+}
+
+impl ZlibStream {
+    pub fn new() -> Self {
+        Self {
+            inflate: Decompress::new(true),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Adds a binary frame to the stream, returns the decompressed payload
+    /// once a complete message was received.
+    pub fn push(&mut self, frame: &[u8]) -> Result<Option<Vec<u8>>, DescordError> {
+        self.buffer.extend_from_slice(frame);
+
+        if !self.buffer.ends_with(&ZLIB_SUFFIX) {
+            return Ok(None);
+        }
+
+        let mut output = Vec::with_capacity(self.buffer.len() * 4);
+        let mut offset = 0;
+
+        loop {
+            let (total_in, total_out) = (self.inflate.total_in(), self.inflate.total_out());
+            let result = self.inflate.decompress_vec(
+                &self.buffer[offset..],
+                &mut output,
+                FlushDecompress::Sync,
+            );
+
+            if let Err(e) = result {
+                self.buffer.clear();
+                return Err(DescordError::Decompress(e.to_string()));
+            }
+
+            // there is always room for output here, e.g. input left after the end of the stream
+            if self.inflate.total_in() == total_in && self.inflate.total_out() == total_out {
+                self.buffer.clear();
+                return Err(DescordError::Decompress(
+                    "the zlib stream stopped making progress".to_owned(),
+                ));
+            }
+
+            offset += (self.inflate.total_in() - total_in) as usize;
+
+            // stop once everything was consumed and there is no output left
+            if offset >= self.buffer.len() && output.len() < output.capacity() {
+                break;
+            }
+
+            output.reserve(output.capacity().max(1024));
+        }
+
+        self.buffer.clear();
+        Ok(Some(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::{Compress, Compression, FlushCompress};
+
+    use super::*;
+
+    fn compress(deflate: &mut Compress, data: &[u8], flush: FlushCompress) -> Vec<u8> {
+        let mut output = Vec::with_capacity(data.len() + 64);
+        deflate.compress_vec(data, &mut output, flush).unwrap();
+        output
+    }
+
+    #[test]
+    fn garbage_after_the_stream_is_an_error() {
+        let mut deflate = Compress::new(Compression::default(), true);
+        let mut stream = ZlibStream::new();
+
+        let frame = compress(&mut deflate, b"{\"op\":10}", FlushCompress::Sync);
+        assert_eq!(stream.push(&frame).unwrap().unwrap(), b"{\"op\":10}");
+
+        // the stream ends, the bytes after it can't be decompressed
+        let mut frame = compress(&mut deflate, b"{\"op\":11}", FlushCompress::Finish);
+        frame.extend_from_slice(b"garbage");
+        frame.extend_from_slice(&ZLIB_SUFFIX);
+
+        assert!(matches!(
+            stream.push(&frame),
+            Err(DescordError::Decompress(_))
+        ));
+    }
+}
@@ -0,0 +1,699 @@
+//! Erlang external term format, used by the gateway with `encoding=etf`.
+//!
+//! Received terms are decoded to and sent payloads are encoded from [`JsonValue`],
+//! so the rest of the library handles them like json payloads.
+//!
+//! Since the models are deserialized from text, a decoded payload is written back as json
+//! and parsed again, see [`Payload::parse_etf`](super::payload::Payload::parse_etf).
+//! ETF saves bandwidth, but it takes more parsing work than json, not less.
+//! https://www.erlang.org/doc/apps/erts/erl_ext_dist.html
+
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+use json::JsonValue;
+
+use crate::internals::DescordError;
+
+const VERSION: u8 = 131;
+
+/// The most memory reserved up front for a compressed term, its size is sent by the peer.
+const MAX_PREALLOCATION: usize = 1 << 20;
+
+/// The deepest nesting of terms that is decoded, the decoder recurses for every level.
+const MAX_DEPTH: usize = 128;
+
+const NEW_FLOAT_EXT: u8 = 70;
+const COMPRESSED: u8 = 80;
+const SMALL_INTEGER_EXT: u8 = 97;
+const INTEGER_EXT: u8 = 98;
+const FLOAT_EXT: u8 = 99;
+const ATOM_EXT: u8 = 100;
+const SMALL_TUPLE_EXT: u8 = 104;
+const LARGE_TUPLE_EXT: u8 = 105;
+const NIL_EXT: u8 = 106;
+const STRING_EXT: u8 = 107;
+const LIST_EXT: u8 = 108;
+const BINARY_EXT: u8 = 109;
+const SMALL_BIG_EXT: u8 = 110;
+const LARGE_BIG_EXT: u8 = 111;
+const SMALL_ATOM_EXT: u8 = 115;
+const MAP_EXT: u8 = 116;
+const ATOM_UTF8_EXT: u8 = 118;
+const SMALL_ATOM_UTF8_EXT: u8 = 119;
+
+/// Decodes an ETF term to a [`JsonValue`].
+///
+/// Atoms are decoded as strings except for `nil`, `true` and `false`.
+/// Discord sends snowflakes as integers, big integers and the integers of snowflake
+/// fields are decoded as strings, like they are in json payloads. Big integers that
+/// aren't snowflakes, e.g. the `version` of guilds, are decoded as strings too.
+pub(crate) fn decode(data: &[u8]) -> Result<JsonValue, DescordError> {
+    let mut reader = Reader { data, position: 0 };
+
+    let version = reader.u8()?;
+    if version != VERSION {
+        return Err(DescordError::Etf(format!("Unsupported version: {version}")));
+    }
+
+    reader.term(0)
+}
+
+/// Encodes a value as an ETF term.
+pub(crate) fn encode(value: &JsonValue) -> Vec<u8> {
+    let mut output = vec![VERSION];
+    write_term(&mut output, value);
+    output
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DescordError> {
+        let bytes = self
+            .position
+            .checked_add(len)
+            .and_then(|end| self.data.get(self.position..end))
+            .ok_or_else(|| DescordError::Etf("Unexpected end of data".to_string()))?;
+
+        self.position += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, DescordError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, DescordError> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, DescordError> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn str(&mut self, len: usize) -> Result<&'a str, DescordError> {
+        std::str::from_utf8(self.take(len)?)
+            .map_err(|e| DescordError::Etf(format!("Invalid utf-8: {e}")))
+    }
+
+    fn term(&mut self, depth: usize) -> Result<JsonValue, DescordError> {
+        if depth > MAX_DEPTH {
+            return Err(DescordError::Etf(format!(
+                "Terms are nested deeper than {MAX_DEPTH} levels"
+            )));
+        }
+
+        let tag = self.u8()?;
+
+        let value = match tag {
+            SMALL_INTEGER_EXT => self.u8()?.into(),
+            // widened first, json can't negate `i32::MIN`
+            INTEGER_EXT => i64::from(self.u32()? as i32).into(),
+            NEW_FLOAT_EXT => float(f64::from_be_bytes(self.take(8)?.try_into().unwrap())),
+            FLOAT_EXT => {
+                let value = self
+                    .str(31)?
+                    .trim_end_matches('\0')
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|e| DescordError::Etf(format!("Invalid float: {e}")))?;
+                float(value)
+            }
+
+            ATOM_EXT | ATOM_UTF8_EXT => {
+                let len = self.u16()? as usize;
+                atom(self.str(len)?)
+            }
+
+            SMALL_ATOM_EXT | SMALL_ATOM_UTF8_EXT => {
+                let len = self.u8()? as usize;
+                atom(self.str(len)?)
+            }
+
+            SMALL_TUPLE_EXT => {
+                let arity = self.u8()? as usize;
+                self.array(arity, depth)?
+            }
+
+            LARGE_TUPLE_EXT => {
+                let arity = self.u32()? as usize;
+                self.array(arity, depth)?
+            }
+
+            NIL_EXT => JsonValue::new_array(),
+            STRING_EXT => {
+                let len = self.u16()? as usize;
+                self.str(len)?.into()
+            }
+
+            LIST_EXT => {
+                let len = self.u32()? as usize;
+                let mut list = self.array(len, depth)?;
+
+                // proper lists end with an empty list, others get the tail as last element
+                let tail = self.term(depth + 1)?;
+                if tail != JsonValue::new_array() {
+                    list.push(tail).unwrap();
+                }
+
+                list
+            }
+
+            BINARY_EXT => {
+                let len = self.u32()? as usize;
+                self.str(len)?.into()
+            }
+
+            SMALL_BIG_EXT => {
+                let len = self.u8()? as usize;
+                self.big(len)?
+            }
+
+            LARGE_BIG_EXT => {
+                let len = self.u32()? as usize;
+                self.big(len)?
+            }
+
+            MAP_EXT => {
+                let arity = self.u32()?;
+                let mut object = JsonValue::new_object();
+
+                for _ in 0..arity {
+                    // json keys are strings, other keys are written as their json text
+                    let key = self.term(depth + 1)?;
+                    let mut value = self.term(depth + 1)?;
+
+                    match key.as_str() {
+                        Some(key) => {
+                            if is_snowflake_field(key) {
+                                value = snowflakes(value);
+                            }
+                            object.insert(key, value).unwrap()
+                        }
+                        None => object.insert(&key.dump(), value).unwrap(),
+                    }
+                }
+
+                object
+            }
+
+            COMPRESSED => {
+                let size = self.u32()? as usize;
+
+                // the size comes from the peer, it is only trusted once the data was inflated
+                let mut data = Vec::with_capacity(size.min(MAX_PREALLOCATION));
+                ZlibDecoder::new(&self.data[self.position..])
+                    .take(size as u64 + 1)
+                    .read_to_end(&mut data)
+                    .map_err(|e| DescordError::Etf(format!("Invalid compressed term: {e}")))?;
+                self.position = self.data.len();
+
+                if data.len() != size {
+                    return Err(DescordError::Etf(format!(
+                        "Compressed term is not {size} bytes long"
+                    )));
+                }
+
+                Reader {
+                    data: &data,
+                    position: 0,
+                }
+                .term(depth + 1)?
+            }
+
+            tag => return Err(DescordError::Etf(format!("Unsupported term: {tag}"))),
+        };
+
+        Ok(value)
+    }
+
+    fn array(&mut self, len: usize, depth: usize) -> Result<JsonValue, DescordError> {
+        // the length comes from the peer, every element takes at least a byte
+        let mut array = Vec::with_capacity(len.min(self.data.len() - self.position));
+
+        for _ in 0..len {
+            array.push(self.term(depth + 1)?);
+        }
+
+        Ok(JsonValue::Array(array))
+    }
+
+    fn big(&mut self, len: usize) -> Result<JsonValue, DescordError> {
+        let negative = self.u8()? != 0;
+        let digits = self.take(len)?;
+
+        if len > 8 {
+            return Err(DescordError::Etf(format!("Integer too large: {len} bytes")));
+        }
+
+        let value = digits
+            .iter()
+            .rev()
+            .fold(0u64, |value, &digit| (value << 8) | digit as u64);
+
+        // the models expect snowflakes as strings, a float would also lose precision
+        let sign = if negative && value != 0 { "-" } else { "" };
+        Ok(format!("{sign}{value}").into())
+    }
+}
+
+/// Whether a field holds snowflakes, e.g. `guild_id`, `mention_roles` or `ids`.
+fn is_snowflake_field(key: &str) -> bool {
+    ["id", "ids", "roles", "applied_tags"].contains(&key)
+        || key.ends_with("_id")
+        || key.ends_with("_ids")
+        || key.ends_with("_roles")
+}
+
+/// Writes the integers of a snowflake field as strings, the field may also be a list of them.
+fn snowflakes(value: JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Number(_) => value.dump().into(),
+        JsonValue::Array(values) => values
+            .into_iter()
+            .map(snowflakes)
+            .collect::<Vec<_>>()
+            .into(),
+        value => value,
+    }
+}
+
+fn atom(name: &str) -> JsonValue {
+    match name {
+        "nil" | "null" => JsonValue::Null,
+        "true" => true.into(),
+        "false" => false.into(),
+        _ => name.into(),
+    }
+}
+
+fn float(value: f64) -> JsonValue {
+    if value.is_finite() {
+        value.into()
+    } else {
+        JsonValue::Null
+    }
+}
+
+fn write_term(output: &mut Vec<u8>, value: &JsonValue) {
+    match value {
+        JsonValue::Null => write_atom(output, "nil"),
+        JsonValue::Boolean(value) => write_atom(output, if *value { "true" } else { "false" }),
+        JsonValue::Short(_) | JsonValue::String(_) => {
+            write_binary(output, value.as_str().unwrap_or_default())
+        }
+
+        JsonValue::Number(number) => {
+            let (positive, mantissa, exponent) = number.as_parts();
+            let integer = (exponent == 0).then(|| {
+                i64::try_from(mantissa)
+                    .ok()
+                    .map(|value| if positive { value } else { -value })
+            });
+
+            match integer {
+                Some(Some(value @ 0..=255)) => {
+                    output.push(SMALL_INTEGER_EXT);
+                    output.push(value as u8);
+                }
+                Some(Some(value)) if i32::try_from(value).is_ok() => {
+                    output.push(INTEGER_EXT);
+                    output.extend_from_slice(&(value as i32).to_be_bytes());
+                }
+                // the integer does not fit in 32 bits, or not even in an i64
+                Some(_) => write_big(output, !positive, mantissa),
+                None => {
+                    output.push(NEW_FLOAT_EXT);
+                    output.extend_from_slice(&value.as_f64().unwrap_or_default().to_be_bytes());
+                }
+            }
+        }
+
+        JsonValue::Object(object) => {
+            output.push(MAP_EXT);
+            output.extend_from_slice(&(object.len() as u32).to_be_bytes());
+
+            for (key, value) in object.iter() {
+                write_binary(output, key);
+                write_term(output, value);
+            }
+        }
+
+        JsonValue::Array(array) => {
+            if !array.is_empty() {
+                output.push(LIST_EXT);
+                output.extend_from_slice(&(array.len() as u32).to_be_bytes());
+                array.iter().for_each(|value| write_term(output, value));
+            }
+
+            output.push(NIL_EXT);
+        }
+    }
+}
+
+fn write_atom(output: &mut Vec<u8>, name: &str) {
+    output.push(SMALL_ATOM_UTF8_EXT);
+    output.push(name.len() as u8);
+    output.extend_from_slice(name.as_bytes());
+}
+
+fn write_binary(output: &mut Vec<u8>, value: &str) {
+    output.push(BINARY_EXT);
+    output.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    output.extend_from_slice(value.as_bytes());
+}
+
+fn write_big(output: &mut Vec<u8>, negative: bool, value: u64) {
+    let digits = value.to_le_bytes();
+    let len = 8 - value.leading_zeros() as usize / 8;
+
+    output.push(SMALL_BIG_EXT);
+    output.push(len as u8);
+    output.push(negative as u8);
+    output.extend_from_slice(&digits[..len]);
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use nanoserde::DeJson;
+    use std::io::Write;
+
+    use super::*;
+    use crate::models::guild::GuildCreateResponse;
+    use crate::models::message_response::MessageResponse;
+    use crate::models::ready_response::ReadyResponse;
+    use crate::ws::payload::Payload;
+
+    const READY: &str = include_str!("../../tests/fixtures/gateway/ready.json");
+    const MESSAGE_CREATE: &str = include_str!("../../tests/fixtures/gateway/message_create.json");
+    const GUILD_CREATE: &str = include_str!("../../tests/fixtures/gateway/guild_create.json");
+
+    fn round_trip(value: &JsonValue) -> JsonValue {
+        decode(&encode(value)).unwrap()
+    }
+
+    /// Turns the snowflakes of a json capture into integers, like discord sends them over ETF.
+    fn numeric_snowflakes(value: &JsonValue) -> JsonValue {
+        fn number(value: &JsonValue) -> JsonValue {
+            match value {
+                JsonValue::Array(values) => values.iter().map(number).collect::<Vec<_>>().into(),
+                value => match value.as_str().map(str::parse::<u64>) {
+                    Some(Ok(id)) => id.into(),
+                    _ => value.clone(),
+                },
+            }
+        }
+
+        match value {
+            JsonValue::Object(object) => {
+                let mut numeric = JsonValue::new_object();
+                for (key, value) in object.iter() {
+                    let value = if is_snowflake_field(key) {
+                        number(value)
+                    } else {
+                        numeric_snowflakes(value)
+                    };
+                    numeric.insert(key, value).unwrap();
+                }
+                numeric
+            }
+            JsonValue::Array(values) => values
+                .iter()
+                .map(numeric_snowflakes)
+                .collect::<Vec<_>>()
+                .into(),
+            value => value.clone(),
+        }
+    }
+
+    /// Writes the integers that don't fit in 32 bits as strings, like they are decoded.
+    fn big_integers_as_strings(value: &JsonValue) -> JsonValue {
+        match value {
+            JsonValue::Number(_) if value.as_i32().is_none() && value.as_i64().is_some() => {
+                value.dump().into()
+            }
+            JsonValue::Object(object) => {
+                let mut decoded = JsonValue::new_object();
+                for (key, value) in object.iter() {
+                    decoded.insert(key, big_integers_as_strings(value)).unwrap();
+                }
+                decoded
+            }
+            JsonValue::Array(values) => values
+                .iter()
+                .map(big_integers_as_strings)
+                .collect::<Vec<_>>()
+                .into(),
+            value => value.clone(),
+        }
+    }
+
+    /// Encodes a capture with integer snowflakes and decodes it to json text.
+    fn decode_capture(capture: &str) -> String {
+        let value = json::parse(capture).unwrap();
+        let term = encode(&numeric_snowflakes(&value));
+
+        let decoded = decode(&term).unwrap();
+        assert_eq!(decoded, big_integers_as_strings(&value));
+        decoded.dump()
+    }
+
+    #[test]
+    fn captures_round_trip() {
+        for capture in [READY, MESSAGE_CREATE, GUILD_CREATE] {
+            let value = json::parse(capture).unwrap();
+            assert_eq!(round_trip(&value), big_integers_as_strings(&value));
+        }
+    }
+
+    #[test]
+    fn snowflakes_are_sent_as_big_integers() {
+        let ready = numeric_snowflakes(&json::parse(READY).unwrap());
+        assert_eq!(encode(&ready["d"]["user"]["id"])[1], SMALL_BIG_EXT);
+        assert_eq!(encode(&ready["d"]["guilds"][1]["id"])[1], SMALL_BIG_EXT);
+    }
+
+    #[test]
+    fn captures_deserialize_like_json() {
+        let decoded = decode_capture(READY);
+        let ready = ReadyResponse::deserialize_json(&decoded).unwrap().data;
+        assert_eq!(ready.user.id, "1184612312940171314");
+        assert_eq!(ready.guilds[1].id, "281994236436103168");
+
+        let decoded = decode_capture(MESSAGE_CREATE);
+        let message = MessageResponse::deserialize_json(&decoded).unwrap().data;
+        // above 2^53, decoding it through a float would have rounded it
+        assert_eq!(message.author.unwrap().id, "9007199254740993123");
+        assert_eq!(message.content, "yes, connect with `encoding=etf` 🎉");
+        assert_eq!(
+            message
+                .referenced_message
+                .unwrap()
+                .author
+                .unwrap()
+                .global_name
+                .as_deref(),
+            Some("Alice \"al\" \\o/")
+        );
+
+        let decoded = decode_capture(GUILD_CREATE);
+        let guild = GuildCreateResponse::deserialize_json(&decoded)
+            .unwrap()
+            .data;
+        assert_eq!(guild.channels.len(), 2);
+        assert_eq!(guild.members[0].roles, ["1184613077616316478"]);
+    }
+
+    #[test]
+    fn numbers_round_trip() {
+        for number in [
+            JsonValue::from(0),
+            JsonValue::from(255),
+            JsonValue::from(-1),
+            JsonValue::from(i32::MIN as i64),
+            JsonValue::from(i32::MAX as i64),
+            JsonValue::from(0.62),
+        ] {
+            assert_eq!(round_trip(&number), number, "{number}");
+        }
+
+        // big integers are snowflakes, they are decoded as strings
+        for number in [
+            i32::MAX as i64 + 1,
+            (1 << 53) + 1,
+            1184612312940171314,
+            i64::MIN + 1,
+        ] {
+            assert_eq!(round_trip(&number.into()), number.to_string());
+        }
+        assert_eq!(round_trip(&u64::MAX.into()), u64::MAX.to_string());
+    }
+
+    #[test]
+    fn snowflake_fields_are_decoded_as_strings() {
+        let value = json::object! {
+            id: 1,
+            guild_id: 5,
+            mention_roles: [2, 3],
+            ids: [],
+            flags: 4,
+            session_id: "abc",
+        };
+
+        assert_eq!(
+            round_trip(&value),
+            json::object! {
+                id: "1",
+                guild_id: "5",
+                mention_roles: ["2", "3"],
+                ids: [],
+                flags: 4,
+                session_id: "abc",
+            }
+        );
+    }
+
+    #[test]
+    fn decodes_erlang_terms() {
+        // #{op => 0, t => 'MESSAGE_DELETE', s => 1152921504606846977,
+        //   d => #{ids => [<<"1">> | <<"2">>], pos => {-1, "ab"}, ok => nil}}
+        let mut term = vec![VERSION, MAP_EXT, 0, 0, 0, 4];
+        term.extend([SMALL_ATOM_UTF8_EXT, 2, b'o', b'p', SMALL_INTEGER_EXT, 0]);
+        term.extend([SMALL_ATOM_UTF8_EXT, 1, b't', ATOM_EXT, 0, 14]);
+        term.extend(b"MESSAGE_DELETE");
+        term.extend([
+            SMALL_ATOM_EXT,
+            1,
+            b's',
+            SMALL_BIG_EXT,
+            8,
+            0,
+            1,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            16,
+        ]);
+        term.extend([SMALL_ATOM_UTF8_EXT, 1, b'd', MAP_EXT, 0, 0, 0, 3]);
+        term.extend([
+            SMALL_ATOM_UTF8_EXT,
+            3,
+            b'i',
+            b'd',
+            b's',
+            LIST_EXT,
+            0,
+            0,
+            0,
+            1,
+        ]);
+        term.extend([BINARY_EXT, 0, 0, 0, 1, b'1', BINARY_EXT, 0, 0, 0, 1, b'2']);
+        term.extend([SMALL_ATOM_UTF8_EXT, 3, b'p', b'o', b's', SMALL_TUPLE_EXT, 2]);
+        term.extend([
+            INTEGER_EXT,
+            255,
+            255,
+            255,
+            255,
+            STRING_EXT,
+            0,
+            2,
+            b'a',
+            b'b',
+        ]);
+        term.extend([SMALL_ATOM_UTF8_EXT, 2, b'o', b'k', SMALL_ATOM_UTF8_EXT, 3]);
+        term.extend(b"nil");
+
+        let decoded = decode(&term).unwrap();
+        assert_eq!(
+            decoded,
+            json::object! {
+                op: 0,
+                t: "MESSAGE_DELETE",
+                s: "1152921504606846977",
+                d: { ids: ["1", "2"], pos: [-1, "ab"], ok: null },
+            }
+        );
+    }
+
+    #[test]
+    fn decodes_compressed_terms() {
+        let value = json::parse(GUILD_CREATE).unwrap();
+        let term = encode(&value);
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&term[1..]).unwrap();
+
+        let mut compressed = vec![VERSION, COMPRESSED];
+        compressed.extend_from_slice(&(term.len() as u32 - 1).to_be_bytes());
+        compressed.extend(encoder.finish().unwrap());
+
+        assert_eq!(
+            decode(&compressed).unwrap(),
+            big_integers_as_strings(&value)
+        );
+    }
+
+    #[test]
+    fn rejects_lying_sizes() {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&[SMALL_INTEGER_EXT, 1]).unwrap();
+        let data = encoder.finish().unwrap();
+
+        // claims to inflate to 4 GiB
+        let mut compressed = vec![VERSION, COMPRESSED, 255, 255, 255, 255];
+        compressed.extend(&data);
+        assert!(decode(&compressed).is_err());
+
+        // a list claiming 4 billion elements
+        let list = [VERSION, LIST_EXT, 255, 255, 255, 255, NIL_EXT];
+        assert!(decode(&list).is_err());
+
+        let binary = [VERSION, BINARY_EXT, 255, 255, 255, 255, b'a'];
+        assert!(decode(&binary).is_err());
+    }
+
+    #[test]
+    fn payloads_keep_the_decoded_data() {
+        let value = json::object! { op: 0, t: "MESSAGE_DELETE", s: 3, d: { id: "1" } };
+        let payload = Payload::parse_etf(&encode(&value)).unwrap();
+
+        assert_eq!(payload.type_name.as_deref(), Some("MESSAGE_DELETE"));
+        assert_eq!(payload.sequence, Some(3));
+        assert_eq!(payload.data, json::object! { id: "1" });
+        assert_eq!(json::parse(&payload.raw_json).unwrap(), value);
+
+        assert!(Payload::parse_etf(&[VERSION, 255]).is_none());
+    }
+
+    fn nested_lists(depth: usize) -> Vec<u8> {
+        let mut term = vec![VERSION];
+        for _ in 0..depth {
+            term.extend([LIST_EXT, 0, 0, 0, 1]);
+        }
+
+        term.extend([SMALL_INTEGER_EXT, 1]);
+        term.resize(term.len() + depth, NIL_EXT);
+        term
+    }
+
+    #[test]
+    fn rejects_deeply_nested_terms() {
+        let nested = decode(&nested_lists(MAX_DEPTH)).unwrap().dump();
+        assert_eq!(nested.len(), MAX_DEPTH * 2 + 1);
+
+        assert!(decode(&nested_lists(MAX_DEPTH + 1)).is_err());
+
+        // would overflow the stack without a limit
+        assert!(decode(&nested_lists(1_000_000)).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashSet;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_util::Stream;
+use log::warn;
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use super::ShardInfo;
+use crate::consts::events::Event;
+use crate::internals::HandlerValue;
+
+/// A gateway event received by the client.
+#[derive(Debug, Clone)]
+pub struct GatewayEvent {
+    pub event: Event,
+    pub data: HandlerValue,
+    /// The shard the event was received on.
+    pub shard: ShardInfo,
+}
+
+/// A stream of gateway events, created with [`Client::event_stream`](crate::Client::event_stream).
+///
+/// Events of a shard arrive in the order the gateway sent them, before their handlers run.
+/// The stream has a bounded buffer, events received while it's full are dropped for this
+/// stream only, see [`lagged`](Self::lagged). Dropping the stream unsubscribes it.
+pub struct EventStream {
+    receiver: mpsc::Receiver<GatewayEvent>,
+    lagged: Arc<AtomicU64>,
+}
+
+impl EventStream {
+    /// Returns the number of events dropped because the stream was full.
+    pub fn lagged(&self) -> u64 {
+        self.lagged.load(Ordering::Relaxed)
+    }
+}
+
+impl Stream for EventStream {
+    type Item = GatewayEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+/// The sending half of an [`EventStream`].
+pub(crate) struct EventSubscriber {
+    /// `None` to receive every event.
+    events: Option<HashSet<Event>>,
+    sender: mpsc::Sender<GatewayEvent>,
+    lagged: Arc<AtomicU64>,
+}
+
+impl EventSubscriber {
+    pub fn new(events: Option<HashSet<Event>>, capacity: usize) -> (Self, EventStream) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let lagged = Arc::new(AtomicU64::new(0));
+
+        let subscriber = Self {
+            events,
+            sender,
+            lagged: Arc::clone(&lagged),
+        };
+
+        (subscriber, EventStream { receiver, lagged })
+    }
+}
+
+/// Sends an event to every stream subscribed to it, the streams that are full miss it.
+/// Called from the read loop of the shard, so it never waits for a stream.
+pub(crate) fn publish(
+    subscribers: &[EventSubscriber],
+    event: Event,
+    data: &HandlerValue,
+    shard: ShardInfo,
+) {
+    for subscriber in subscribers {
+        if subscriber
+            .events
+            .as_ref()
+            .is_some_and(|events| !events.contains(&event))
+        {
+            continue;
+        }
+
+        let event = GatewayEvent {
+            event,
+            data: data.clone(),
+            shard,
+        };
+
+        match subscriber.sender.try_send(event) {
+            // the stream was dropped
+            Ok(()) | Err(TrySendError::Closed(_)) => {}
+            Err(TrySendError::Full(event)) => {
+                if subscriber.lagged.fetch_add(1, Ordering::Relaxed) == 0 {
+                    warn!(
+                        "An event stream is full, dropping {:?} and later events it can't keep up with",
+                        event.event
+                    );
+                }
+            }
+        }
+    }
+}
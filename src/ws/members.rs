@@ -0,0 +1,203 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::*;
+use tokio::sync::oneshot;
+
+use super::shard::{SharedGateway, CURRENT_SHARD};
+use crate::consts::payloads;
+use crate::internals::DescordError;
+use crate::models::guild::{GuildMembersChunk, Member};
+
+/// Most user ids a request can contain.
+const MAX_USER_IDS: usize = 100;
+
+/// Which members to request with [`request_guild_members`].
+#[derive(Debug, Clone)]
+pub struct GuildMembersRequest {
+    query: Option<String>,
+    user_ids: Vec<String>,
+    limit: u32,
+    presences: bool,
+    timeout: Duration,
+}
+
+impl GuildMembersRequest {
+    fn new(query: Option<String>, user_ids: Vec<String>, limit: u32) -> Self {
+        Self {
+            query,
+            user_ids,
+            limit,
+            presences: false,
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Requests the members whose username or nickname starts with `prefix`.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The start of the username or nickname.
+    /// * `limit` - The maximum number of members to return, at most 100.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let request = GuildMembersRequest::query("mag", 10);
+    /// ```
+    pub fn query(prefix: &str, limit: u32) -> Self {
+        Self::new(Some(prefix.to_owned()), vec![], limit.min(100))
+    }
+
+    /// Requests the members with the given user ids, at most 100.
+    ///
+    /// Sending a request with more ids fails with [`DescordError::InvalidArgument`].
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let request = GuildMembersRequest::user_ids(vec!["123".to_string()]);
+    /// ```
+    pub fn user_ids(user_ids: Vec<String>) -> Self {
+        let limit = user_ids.len() as u32;
+        Self::new(None, user_ids, limit)
+    }
+
+    /// Requests every member of the guild, this needs the `GUILD_MEMBERS` intent.
+    pub fn all() -> Self {
+        Self::new(Some(String::new()), vec![], 0)
+    }
+
+    /// Whether to include the presences of the members, this needs the `GUILD_PRESENCES` intent.
+    pub fn presences(mut self, presences: bool) -> Self {
+        self.presences = presences;
+        self
+    }
+
+    /// How long to wait for all the members to arrive, defaults to 10 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// A guild members request waiting for its chunks.
+pub(crate) struct PendingMembers {
+    members: Vec<Member>,
+    received: u32,
+    sender: oneshot::Sender<Vec<Member>>,
+}
+
+/// Requests members of a guild over the gateway and waits until all of them arrived.
+///
+/// Fails with [`DescordError::Timeout`] if the chunks don't arrive in time,
+/// and with [`DescordError::NotLoggedIn`] if the client stops first.
+///
+/// This is available inside event, command, slash command and component handlers,
+/// but not inside tasks spawned from them, use a [`GatewayHandle`](crate::GatewayHandle) there.
+///
+/// # Arguments
+///
+/// * `guild_id` - The ID of the guild.
+/// * `request` - Which members to request.
+///
+/// # Examples
+///
+/// ```ignore
+/// let members = request_guild_members(&guild_id, GuildMembersRequest::query("mag", 10)).await?;
+/// ```
+pub async fn request_guild_members(
+    guild_id: &str,
+    request: GuildMembersRequest,
+) -> Result<Vec<Member>, DescordError> {
+    let gateway = CURRENT_SHARD
+        .try_with(|shard| Arc::clone(&shard.gateway))
+        .map_err(|_| DescordError::NotInHandler)?;
+
+    request_members(&gateway, guild_id, request).await
+}
+
+/// Sends a guild members request on the shard of the guild and waits for its chunks.
+pub(crate) async fn request_members(
+    gateway: &SharedGateway,
+    guild_id: &str,
+    request: GuildMembersRequest,
+) -> Result<Vec<Member>, DescordError> {
+    if request.user_ids.len() > MAX_USER_IDS {
+        return Err(DescordError::InvalidArgument {
+            param: "user_ids".to_owned(),
+            expected: format!("at most {MAX_USER_IDS} user ids"),
+            got: request.user_ids.len().to_string(),
+        });
+    }
+
+    let nonce = gateway.nonce();
+    let (sender, receiver) = oneshot::channel();
+
+    gateway.member_requests.lock().unwrap().insert(
+        nonce.clone(),
+        PendingMembers {
+            members: vec![],
+            received: 0,
+            sender,
+        },
+    );
+
+    let payload = payloads::request_guild_members(
+        guild_id,
+        request.query.as_deref(),
+        &request.user_ids,
+        request.limit,
+        request.presences,
+        &nonce,
+    );
+
+    if let Err(e) = gateway.send_to_guild(guild_id, payload) {
+        gateway.member_requests.lock().unwrap().remove(&nonce);
+        return Err(e);
+    }
+
+    match tokio::time::timeout(request.timeout, receiver).await {
+        Ok(Ok(members)) => Ok(members),
+        // the gateway of the client was dropped, e.g. after a shutdown
+        Ok(Err(_)) => Err(DescordError::NotLoggedIn),
+        Err(_) => {
+            gateway.member_requests.lock().unwrap().remove(&nonce);
+            Err(DescordError::Timeout(format!("members of guild {guild_id}")))
+        }
+    }
+}
+
+/// Adds a chunk to the request it belongs to, completing it once every chunk arrived.
+pub(crate) fn receive_chunk(gateway: &SharedGateway, chunk: GuildMembersChunk) {
+    let Some(nonce) = chunk.nonce else {
+        return;
+    };
+
+    let mut requests = gateway.member_requests.lock().unwrap();
+    let Some(pending) = requests.get_mut(&nonce) else {
+        warn!("Received members chunk for an unknown request: {nonce}");
+        return;
+    };
+
+    let guild_id = chunk.guild_id;
+    let presences = chunk.presences;
+    pending
+        .members
+        .extend(chunk.members.into_iter().map(|mut member| {
+            if let Some(user) = &member.user {
+                member.mention = format!("<@{}>", user.id);
+                member.presence = presences.iter().find(|p| p.user.id == user.id).cloned();
+            }
+
+            member.guild_id = Some(guild_id.clone());
+            member
+        }));
+
+    // every chunk carries the total, the request is complete once all of them arrived
+    pending.received += 1;
+    if pending.received >= chunk.chunk_count {
+        let pending = requests.remove(&nonce).unwrap();
+        let _ = pending.sender.send(pending.members);
+    }
+}
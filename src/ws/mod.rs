@@ -1,4 +1,16 @@
+mod compression;
+mod etf;
+mod event_stream;
+mod members;
+mod reconnect;
+mod shard;
+mod shutdown;
 mod websocket_manager;
 
 pub mod payload;
+pub use event_stream::*;
+pub use members::*;
+pub use reconnect::*;
+pub use shard::*;
+pub use shutdown::*;
 pub use websocket_manager::*;
@@ -1,6 +1,44 @@
 use json::JsonValue;
+use log::error;
+use tokio_tungstenite::tungstenite::Message;
 
 use crate::consts::opcode::OpCode;
+use crate::ws::etf;
+
+/// The format of the payloads sent over the gateway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GatewayEncoding {
+    #[default]
+    Json,
+    /// Erlang term format, smaller on the wire but slower to parse than json.
+    Etf,
+}
+
+impl GatewayEncoding {
+    /// Returns the value of the `encoding` query parameter.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Etf => "etf",
+        }
+    }
+
+    /// Parses a payload received in a binary frame.
+    pub(crate) fn parse(&self, data: &[u8]) -> Option<Payload> {
+        match self {
+            Self::Json => Payload::parse(std::str::from_utf8(data).ok()?),
+            Self::Etf => Payload::parse_etf(data),
+        }
+    }
+
+    /// Encodes a payload to be sent to the gateway.
+    pub(crate) fn message(&self, payload: &JsonValue) -> Message {
+        match self {
+            Self::Json => Message::Text(json::stringify(payload.clone())),
+            Self::Etf => Message::Binary(etf::encode(payload)),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Payload {
@@ -14,14 +52,30 @@ pub struct Payload {
 impl Payload {
     pub fn parse(payload: &str) -> Option<Self> {
         let js = json::parse(payload).ok()?;
+        Self::from_value(js, payload.to_string())
+    }
+
+    /// Parses an ETF encoded payload, `raw_json` is the decoded payload written as json.
+    ///
+    /// Payloads that can't be decoded are logged and dropped.
+    pub fn parse_etf(payload: &[u8]) -> Option<Self> {
+        let js = etf::decode(payload)
+            .map_err(|e| error!("Dropping a gateway payload: {e}"))
+            .ok()?;
+
+        // nanoserde only deserializes from text
+        let raw_json = js.dump();
+        Self::from_value(js, raw_json)
+    }
 
+    fn from_value(mut js: JsonValue, raw_json: String) -> Option<Self> {
         let operation_code = OpCode::parse(js["op"].as_u8()?)?;
         let type_name = js["t"].as_str().map(|i| i.to_string());
         let sequence = js["s"].as_usize();
-        let data = js["d"].clone();
+        let data = js["d"].take();
 
         Some(Self {
-            raw_json: payload.to_string(),
+            raw_json,
             operation_code,
             type_name,
             sequence,
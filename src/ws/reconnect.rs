@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use rand::Rng;
+
+/// Controls how the gateway reconnects after losing the connection.
+///
+/// The delay between attempts grows exponentially from `base_delay`
+/// up to `max_delay`, with random jitter applied to every delay.
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// Delay before the first reconnect attempt.
+    pub base_delay: Duration,
+    /// Upper bound for the delay between two attempts.
+    pub max_delay: Duration,
+    /// Number of failed attempts in a row after which the bot gives up,
+    /// `None` to retry forever.
+    pub max_attempts: Option<u32>,
+    /// Number of failed attempts to resume the session after which
+    /// a new session is started instead.
+    pub max_resume_attempts: u32,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            max_attempts: None,
+            max_resume_attempts: 3,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Returns the delay before the given attempt, starting at 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+
+        // jitter the delay between 50% and 100%
+        delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use json::JsonValue;
+use log::*;
+use tokio::sync::{mpsc, watch, Mutex};
+
+use super::members::{GuildMembersRequest, PendingMembers};
+use super::shutdown::ShutdownHandle;
+use super::{GatewayOptions, Handlers, HeartbeatState, WsManager};
+use crate::consts::{self, IDENTIFY_INTERVAL};
+use crate::internals::{DescordError, DescordResult, StateMap};
+use crate::models::guild::Member;
+use crate::models::presence::Presence;
+use crate::utils;
+
+tokio::task_local! {
+    pub(crate) static CURRENT_SHARD: ShardContext;
+}
+
+/// Gateway state shared by all the shards of a client.
+pub(crate) struct SharedGateway {
+    /// The presence of the bot on each shard, a shard sends an update when its presence changes.
+    pub presence: Vec<watch::Sender<Option<Presence>>>,
+    /// The heartbeat state of each shard, updated by the connection of the shard.
+    pub heartbeats: Vec<Arc<std::sync::Mutex<HeartbeatState>>>,
+    /// Commands waiting to be sent over the connection of each shard.
+    commands: Vec<mpsc::UnboundedSender<JsonValue>>,
+    /// Guild member requests waiting for their chunks, by nonce.
+    pub member_requests: std::sync::Mutex<HashMap<String, PendingMembers>>,
+    next_nonce: AtomicU64,
+}
+
+impl SharedGateway {
+    /// Creates the shared state and the command receivers of every shard.
+    pub fn new(
+        total: u32,
+        presence: Option<Presence>,
+    ) -> (Arc<Self>, Vec<mpsc::UnboundedReceiver<JsonValue>>) {
+        let (commands, receivers) = (0..total.max(1)).map(|_| mpsc::unbounded_channel()).unzip();
+
+        let gateway = Self {
+            presence: (0..total.max(1))
+                .map(|_| watch::Sender::new(presence.clone()))
+                .collect(),
+            heartbeats: (0..total.max(1)).map(|_| Arc::default()).collect(),
+            commands,
+            member_requests: std::sync::Mutex::new(HashMap::new()),
+            next_nonce: AtomicU64::new(0),
+        };
+
+        (Arc::new(gateway), receivers)
+    }
+
+    /// Queues a command on the shard that receives the events of the given guild.
+    pub fn send_to_guild(&self, guild_id: &str, command: JsonValue) -> DescordResult {
+        let guild_id = guild_id
+            .parse::<u64>()
+            .map_err(|_| DescordError::Other(format!("Invalid guild id: {guild_id}")))?;
+
+        // https://discord.com/developers/docs/topics/gateway#sharding-sharding-formula
+        let shard_id = (guild_id >> 22) % self.commands.len() as u64;
+
+        self.commands[shard_id as usize]
+            .send(command)
+            .map_err(|_| DescordError::Other(format!("Shard {shard_id} is not running")))
+    }
+
+    /// Fails the member requests still waiting for chunks once the client stopped,
+    /// instead of letting them time out.
+    pub fn cancel_member_requests(&self) {
+        self.member_requests.lock().unwrap().clear();
+    }
+
+    pub fn nonce(&self) -> String {
+        self.next_nonce.fetch_add(1, Ordering::Relaxed).to_string()
+    }
+
+    /// Updates the presence of one shard, or of every shard if `shard_id` is `None`.
+    pub fn update_presence(&self, shard_id: Option<u32>, presence: Presence) -> DescordResult {
+        let Some(shard_id) = shard_id else {
+            for shard in &self.presence {
+                shard.send_replace(Some(presence.clone()));
+            }
+            return Ok(());
+        };
+
+        self.presence
+            .get(shard_id as usize)
+            .ok_or_else(|| DescordError::Other(format!("Shard {shard_id} is not running")))?
+            .send_replace(Some(presence));
+        Ok(())
+    }
+}
+
+/// A handle to the gateway connection of a client, it can be used from any task.
+///
+/// Its methods fail with [`DescordError::NotLoggedIn`] until the client logged in.
+///
+/// # Examples
+///
+/// ```ignore
+/// let gateway = client.gateway_handle();
+///
+/// tokio::spawn(async move {
+///     loop {
+///         tokio::time::sleep(Duration::from_secs(60)).await;
+///         let presence = Presence::new(OnlineStatus::Online).activity(Activity::watching("the queue"));
+///         gateway.update_presence(presence).ok();
+///     }
+/// });
+///
+/// client.login().await?;
+/// ```
+#[derive(Clone, Default)]
+pub struct GatewayHandle {
+    gateway: Arc<OnceLock<Arc<SharedGateway>>>,
+}
+
+impl GatewayHandle {
+    pub(crate) fn connect(&self, gateway: &Arc<SharedGateway>) {
+        let _ = self.gateway.set(Arc::clone(gateway));
+    }
+
+    fn gateway(&self) -> Result<&SharedGateway, DescordError> {
+        self.gateway
+            .get()
+            .map(Arc::as_ref)
+            .ok_or(DescordError::NotLoggedIn)
+    }
+
+    /// Returns the number of shards the client is running, `None` until it logged in.
+    pub fn shard_count(&self) -> Option<u32> {
+        self.gateway()
+            .ok()
+            .map(|gateway| gateway.commands.len() as u32)
+    }
+
+    /// Returns the gateway latency of a shard, that is the time between
+    /// its last heartbeat and the acknowledgement.
+    ///
+    /// Returns `None` before the client logged in, for shards it isn't running
+    /// and before the first heartbeat of the shard was acknowledged.
+    ///
+    /// # Arguments
+    ///
+    /// * `shard_id` - The id of the shard, in the range `0..shard_count`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// for shard_id in 0..gateway.shard_count().unwrap_or_default() {
+    ///     if let Some(latency) = gateway.latency(shard_id) {
+    ///         metrics.record(shard_id, latency);
+    ///     }
+    /// }
+    /// ```
+    pub fn latency(&self, shard_id: u32) -> Option<Duration> {
+        self.gateway()
+            .ok()?
+            .heartbeats
+            .get(shard_id as usize)?
+            .lock()
+            .unwrap()
+            .latency
+    }
+
+    /// Updates the presence of the bot on every shard.
+    ///
+    /// The presence is kept across reconnects.
+    ///
+    /// # Arguments
+    ///
+    /// * `presence` - The new presence.
+    pub fn update_presence(&self, presence: Presence) -> DescordResult {
+        self.gateway()?.update_presence(None, presence)
+    }
+
+    /// Updates the presence of the bot on one shard.
+    ///
+    /// The presence is kept across reconnects of the shard.
+    ///
+    /// # Arguments
+    ///
+    /// * `shard_id` - The id of the shard, in the range `0..shard_count`.
+    /// * `presence` - The new presence.
+    pub fn update_shard_presence(&self, shard_id: u32, presence: Presence) -> DescordResult {
+        self.gateway()?.update_presence(Some(shard_id), presence)
+    }
+
+    /// Requests members of a guild over the gateway and waits until all of them arrived,
+    /// see [`request_guild_members`](crate::request_guild_members).
+    ///
+    /// # Arguments
+    ///
+    /// * `guild_id` - The ID of the guild.
+    /// * `request` - Which members to request.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let members = gateway
+    ///     .request_guild_members(&guild_id, GuildMembersRequest::user_ids(ids))
+    ///     .await?;
+    /// ```
+    pub async fn request_guild_members(
+        &self,
+        guild_id: &str,
+        request: GuildMembersRequest,
+    ) -> Result<Vec<Member>, DescordError> {
+        super::members::request_members(self.gateway()?, guild_id, request).await
+    }
+}
+
+impl std::fmt::Debug for GatewayHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GatewayHandle")
+            .field("shard_count", &self.shard_count())
+            .finish()
+    }
+}
+
+/// How many shards the client should be split into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShardCount {
+    /// Use the shard count recommended by `GET /gateway/bot`.
+    Auto,
+    /// Use a fixed number of shards.
+    Fixed(u32),
+}
+
+/// Identifies the gateway shard an event was received on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShardInfo {
+    /// The id of the shard, in the range `0..total`.
+    pub id: u32,
+    /// The total number of shards the client is running.
+    pub total: u32,
+}
+
+/// State of a shard shared with the handlers running on it.
+#[derive(Clone)]
+pub(crate) struct ShardContext {
+    pub info: ShardInfo,
+    pub heartbeat: Arc<std::sync::Mutex<HeartbeatState>>,
+    pub gateway: Arc<SharedGateway>,
+    pub state: Arc<StateMap>,
+    pub shutdown: ShutdownHandle,
+}
+
+/// Returns the shard the event currently being handled was received on.
+///
+/// This is available inside event, command, slash command and component handlers,
+/// but not inside tasks spawned from them.
+pub fn current_shard() -> Option<ShardInfo> {
+    CURRENT_SHARD.try_with(|shard| shard.info).ok()
+}
+
+/// Returns the gateway latency of the current shard, that is the time
+/// between the last heartbeat and its acknowledgement.
+///
+/// Returns `None` outside of handlers or before the first heartbeat was acknowledged,
+/// use [`GatewayHandle::latency`] in other tasks.
+pub fn gateway_latency() -> Option<Duration> {
+    CURRENT_SHARD
+        .try_with(|shard| shard.heartbeat.lock().unwrap().latency)
+        .ok()
+        .flatten()
+}
+
+/// Updates the presence of the bot on every shard.
+///
+/// The presence is kept across reconnects. This is available inside event, command,
+/// slash command and component handlers, but not inside tasks spawned from them,
+/// use a [`GatewayHandle`] there.
+///
+/// # Arguments
+///
+/// * `presence` - The new presence.
+///
+/// # Examples
+///
+/// ```ignore
+/// update_presence(Presence::new(OnlineStatus::Online).activity(Activity::custom("3 songs in queue")))?;
+/// ```
+pub fn update_presence(presence: Presence) -> DescordResult {
+    CURRENT_SHARD
+        .try_with(|shard| Arc::clone(&shard.gateway))
+        .map_err(|_| DescordError::NotInHandler)?
+        .update_presence(None, presence)
+}
+
+/// Limits how fast shards are allowed to identify.
+///
+/// Shards with the same `shard_id % max_concurrency` share a bucket,
+/// only one identify is allowed per bucket every 5 seconds.
+pub(crate) struct IdentifyQueue {
+    buckets: Vec<Mutex<Option<Instant>>>,
+}
+
+impl IdentifyQueue {
+    pub fn new(max_concurrency: u32) -> Self {
+        Self {
+            buckets: (0..max_concurrency.max(1))
+                .map(|_| Mutex::new(None))
+                .collect(),
+        }
+    }
+
+    /// Waits until the given shard is allowed to identify.
+    pub async fn wait(&self, shard_id: u32) {
+        let mut last_identify = self.buckets[shard_id as usize % self.buckets.len()]
+            .lock()
+            .await;
+
+        if let Some(last) = *last_identify {
+            let elapsed = last.elapsed();
+            if elapsed < IDENTIFY_INTERVAL {
+                tokio::time::sleep(IDENTIFY_INTERVAL - elapsed).await;
+            }
+        }
+
+        *last_identify = Some(Instant::now());
+    }
+}
+
+/// Starts all the shards and waits for them to shut down.
+///
+/// When fetching the gateway information fails it's returned, when a shard fails to connect
+/// or fails for good later on the other shards are shut down and its error is returned.
+pub(crate) async fn start_sharded(
+    options: GatewayOptions,
+    intents: u32,
+    shard_count: ShardCount,
+    presence: Option<Presence>,
+    handlers: Handlers,
+) -> DescordResult {
+    let gateway = utils::fetch_gateway_bot().await?;
+
+    let total = match shard_count {
+        ShardCount::Auto => gateway.shards,
+        ShardCount::Fixed(total) => total,
+    }
+    .max(1);
+
+    let limit = &gateway.session_start_limit;
+    info!(
+        "Starting {total} shards (recommended: {}, max concurrency: {})",
+        gateway.shards, limit.max_concurrency
+    );
+
+    if limit.remaining < total {
+        warn!(
+            "Only {} session starts remaining, waiting {}ms for the limit to reset",
+            limit.remaining, limit.reset_after
+        );
+        tokio::time::sleep(Duration::from_millis(limit.reset_after)).await;
+    }
+
+    let gateway_url = options
+        .gateway_url
+        .clone()
+        .unwrap_or_else(|| format!("{}{}", gateway.url, consts::GATEWAY_QUERY));
+    let identify_queue = Arc::new(IdentifyQueue::new(limit.max_concurrency));
+    let (gateway, commands) = SharedGateway::new(total, presence);
+    options.gateway.connect(&gateway);
+
+    let shards = commands.into_iter().zip(0..total).map(|(commands, id)| {
+        let options = options.clone();
+        let gateway_url = gateway_url.clone();
+        let identify_queue = Arc::clone(&identify_queue);
+        let gateway = Arc::clone(&gateway);
+        let handlers = handlers.clone();
+        let shutdown = options.shutdown.clone();
+
+        let http = Arc::clone(&options.http);
+
+        tokio::spawn(http.scope(async move {
+            let shard = ShardInfo { id, total };
+            let ws = WsManager::new(
+                options,
+                &gateway_url,
+                Some(shard),
+                identify_queue,
+                gateway,
+                commands,
+            );
+
+            let result = match ws.await {
+                Ok(mut ws) => ws.start(intents, handlers).await,
+                Err(e) => Err(e.into()),
+            };
+
+            if let Err(e) = &result {
+                error!("Shard {id} stopped: {e}");
+                shutdown.shutdown();
+            }
+
+            result
+        }))
+    });
+
+    let mut result = Ok(());
+    for shard in futures_util::future::join_all(shards).await {
+        match shard {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) if result.is_ok() => result = Err(e),
+            Ok(Err(_)) => {}
+            Err(e) => error!("A shard panicked: {e}"),
+        }
+    }
+
+    gateway.cancel_member_requests();
+    result
+}
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::*;
+use tokio::sync::watch;
+
+/// A handle to gracefully stop a running client.
+///
+/// Shutting down closes the gateway connections with a normal close frame,
+/// stops dispatching new events and lets [`Client::login`](crate::Client::login)
+/// return once the handlers that are still running finished.
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+    requested: Arc<watch::Sender<bool>>,
+    /// Number of handler tasks that are still running.
+    in_flight: Arc<watch::Sender<usize>>,
+}
+
+impl Default for ShutdownHandle {
+    fn default() -> Self {
+        Self {
+            requested: Arc::new(watch::Sender::new(false)),
+            in_flight: Arc::new(watch::Sender::new(0)),
+        }
+    }
+}
+
+impl ShutdownHandle {
+    /// Stops the client.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let shutdown = client.shutdown_handle();
+    ///
+    /// tokio::spawn(async move {
+    ///     tokio::time::sleep(Duration::from_secs(60)).await;
+    ///     shutdown.shutdown();
+    /// });
+    ///
+    /// client.login().await?;
+    /// ```
+    pub fn shutdown(&self) {
+        self.requested.send_replace(true);
+    }
+
+    /// Returns whether a shutdown was requested.
+    pub fn is_shutdown(&self) -> bool {
+        *self.requested.borrow()
+    }
+
+    /// Shuts the client down when the process receives SIGINT or SIGTERM.
+    ///
+    /// Only SIGINT (ctrl-c) is handled on platforms without unix signals.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// client.shutdown_handle().shutdown_on_signal();
+    /// client.login().await?;
+    /// ```
+    pub fn shutdown_on_signal(&self) {
+        let handle = self.clone();
+
+        tokio::spawn(async move {
+            wait_for_signal().await;
+            info!("Received a shutdown signal");
+            handle.shutdown();
+        });
+    }
+
+    /// Returns a receiver notified once a shutdown is requested.
+    pub(crate) fn subscribe(&self) -> watch::Receiver<bool> {
+        self.requested.subscribe()
+    }
+
+    /// Marks a handler task as running until the returned guard is dropped.
+    pub(crate) fn track(&self) -> TaskGuard {
+        self.in_flight.send_modify(|count| *count += 1);
+        TaskGuard {
+            in_flight: Arc::clone(&self.in_flight),
+        }
+    }
+
+    /// Waits for the running handler tasks to finish, at most for `timeout`.
+    pub(crate) async fn wait_for_tasks(&self, timeout: Duration) {
+        let mut in_flight = self.in_flight.subscribe();
+
+        if tokio::time::timeout(timeout, in_flight.wait_for(|count| *count == 0))
+            .await
+            .is_err()
+        {
+            warn!(
+                "Shut down with {} handlers still running",
+                *in_flight.borrow()
+            );
+        }
+    }
+}
+
+/// Keeps a handler task counted as running, see [`ShutdownHandle::track`].
+pub(crate) struct TaskGuard {
+    in_flight: Arc<watch::Sender<usize>>,
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        self.in_flight.send_modify(|count| *count -= 1);
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(e) => {
+            error!("Failed to listen for SIGTERM: {e}");
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
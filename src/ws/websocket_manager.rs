@@ -2,18 +2,16 @@
 use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
-use std::{clone, thread};
+use std::time::{Duration, Instant};
 
-use guild::{GuildCreate, GuildCreateResponse, Member, MemberLeave};
+use guild::{GuildCreate, GuildCreateResponse, GuildMembersChunk, Member, MemberLeave};
+use json::JsonValue;
 use log::*;
 use nanoserde::{DeJson, SerJson};
-use reqwest::{Method, Url};
+use rand::Rng;
+use reqwest::Method;
 
-use crate::client::BOT_ID;
-use crate::client::RESUME_GATEWAY_URL;
-use crate::client::SESSION_ID;
-use crate::client::TOKEN;
+use crate::client::{attach_current, Http, IdentifyProperties};
 use crate::{internals::*, utils};
 
 // models
@@ -21,66 +19,189 @@ use crate::models::interaction::{
     Interaction, InteractionAutoCompleteChoice, InteractionAutoCompleteChoicePlaceholder,
     InteractionAutoCompleteChoices, InteractionResponsePayload,
 };
+use crate::models::presence::Presence;
 use crate::models::ready_response::ReadyResponse;
 use crate::models::*;
 
-use deleted_message_response::DeletedMessageResponse;
+use deleted_message_response::{BulkDeletedMessages, DeletedMessage, DeletedMessageResponse};
 use message_response::MessageResponse;
-use misc::Reconnect;
 use reaction_response::ReactionResponse;
 use role_response::*;
+use thread::{ThreadListSync, ThreadMember, ThreadMembersUpdate};
 
 // Tokio & Future
-use tokio::io::{AsyncRead, AsyncWrite};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, watch, Mutex, Notify};
 
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
 use tokio_tungstenite::tungstenite::{Message, Result};
 use tokio_tungstenite::MaybeTlsStream;
 use tokio_tungstenite::{connect_async, WebSocketStream};
 
 use futures_util::stream::{SplitSink, SplitStream};
-use futures_util::{future, pin_mut, SinkExt, StreamExt};
+use futures_util::{SinkExt, StreamExt};
 
 use crate::consts::events::Event;
+use crate::consts::close_code::{CloseBehaviour, GatewayCloseCode};
 use crate::consts::opcode::OpCode;
 use crate::consts::{self, payloads, InteractionCallbackType, InteractionType};
 use crate::utils::{fetch_channel, fetch_guild, fetch_member, request};
-use crate::ws::payload::Payload;
+use crate::ws::compression::ZlibStream;
+use crate::ws::event_stream::{self, EventSubscriber};
+use crate::ws::payload::{GatewayEncoding, Payload};
+use crate::ws::reconnect::ReconnectPolicy;
+use crate::ws::shutdown::ShutdownHandle;
+use crate::ws::shard::{
+    GatewayHandle, IdentifyQueue, ShardContext, ShardInfo, SharedGateway, CURRENT_SHARD,
+};
 use crate::Client;
 
-use crate::cache::{MESSAGE_CACHE, ROLE_CACHE};
 use crate::consts::permissions::ADMINISTRATOR;
 use crate::prelude::{Channel, Guild};
 
 type SocketWrite = Arc<Mutex<SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>>>;
 type SocketRead = Arc<Mutex<SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>>>;
 
+/// Session information needed to resume a connection.
+#[derive(Debug, Clone)]
+struct Session {
+    id: String,
+    resume_gateway_url: String,
+}
+
+/// Heartbeat bookkeeping of a connection.
+#[derive(Debug)]
+pub(crate) struct HeartbeatState {
+    /// When the last heartbeat was sent.
+    pub last_sent: Option<Instant>,
+    /// Whether the last heartbeat was acknowledged.
+    pub acked: bool,
+    /// Time it took for the last heartbeat to be acknowledged.
+    pub latency: Option<Duration>,
+}
+
+impl Default for HeartbeatState {
+    fn default() -> Self {
+        Self {
+            last_sent: None,
+            acked: true,
+            latency: None,
+        }
+    }
+}
+
+/// What the connection loop should do once a connection ends.
+#[derive(Debug)]
+enum NextStep {
+    /// Reconnect and resume the current session.
+    Resume,
+    /// Reconnect and start a new session.
+    Identify,
+    /// Stop the bot, reporting the error.
+    Fatal(DescordError),
+    /// Stop the bot, a shutdown was requested.
+    Shutdown,
+}
+
+/// An event parsed by the read loop, waiting for its handlers to run.
+struct Dispatch {
+    event: Event,
+    data: HandlerValue,
+    /// The raw data of a deleted message that was cached, also passed to `MessageDeleteRaw` handlers.
+    raw_delete: Option<DeletedMessage>,
+}
+
+/// Connection settings shared by all the shards of a client.
+#[derive(Debug, Clone)]
+pub(crate) struct GatewayOptions {
+    pub http: Arc<Http>,
+    pub reconnect_policy: ReconnectPolicy,
+    /// Whether to use `zlib-stream` transport compression.
+    pub compress: bool,
+    pub encoding: GatewayEncoding,
+    /// Overrides the gateway url returned by discord.
+    pub gateway_url: Option<String>,
+    pub identify_properties: IdentifyProperties,
+    pub shutdown: ShutdownHandle,
+    /// The handle of the client, connected once the shards were created.
+    pub gateway: GatewayHandle,
+}
+
+impl GatewayOptions {
+    /// Adds the connection options to the query of a gateway url.
+    fn url(&self, gateway_url: &str) -> String {
+        let mut url = gateway_url.replace(
+            "encoding=json",
+            &format!("encoding={}", self.encoding.as_str()),
+        );
+
+        if self.compress {
+            url.push_str(consts::ZLIB_STREAM_QUERY);
+        }
+
+        url
+    }
+}
+
 pub struct WsManager {
-    token: String,
+    options: GatewayOptions,
+    gateway_url: String,
+    /// `None` if the client isn't sharded.
+    shard: Option<ShardInfo>,
+    identify_queue: Arc<IdentifyQueue>,
+    /// The current connection attempt since the last READY or RESUMED,
+    /// starting at 1 and 0 while the session is established.
+    attempts: u32,
     socket: (SocketWrite, SocketRead),
+    /// Decoder of the current connection, `None` without compression.
+    zlib: Option<ZlibStream>,
     sequence: Arc<Mutex<usize>>,
+    session: Option<Session>,
+    heartbeat: Arc<std::sync::Mutex<HeartbeatState>>,
+    gateway: Arc<SharedGateway>,
+    presence_updates: watch::Receiver<Option<Presence>>,
+    /// Commands queued by handlers, e.g. guild member requests.
+    commands: mpsc::UnboundedReceiver<JsonValue>,
+    shutdown: watch::Receiver<bool>,
 }
 
 impl WsManager {
-    pub async fn new(token: &str) -> Result<Self> {
+    pub(crate) async fn new(
+        options: GatewayOptions,
+        gateway_url: &str,
+        shard: Option<ShardInfo>,
+        identify_queue: Arc<IdentifyQueue>,
+        gateway: Arc<SharedGateway>,
+        commands: mpsc::UnboundedReceiver<JsonValue>,
+    ) -> Result<Self> {
+        let index = shard.map_or(0, |shard| shard.id) as usize;
+
         Ok(Self {
-            token: token.to_owned(),
             socket: {
                 info!("Connecting to web socket");
-                Self::connect_socket()
-                    .await
-                    .expect("Failed to connect to the gateway")
+                Self::connect_socket(&options.url(gateway_url)).await?
             },
+            zlib: options.compress.then(ZlibStream::new),
+            shutdown: options.shutdown.subscribe(),
+            options,
+            gateway_url: gateway_url.to_owned(),
+            shard,
+            identify_queue,
+            attempts: 1,
             sequence: Arc::new(Mutex::new(0)),
+            session: None,
+            heartbeat: Arc::clone(&gateway.heartbeats[index]),
+            presence_updates: gateway.presence[index].subscribe(),
+            gateway,
+            commands,
         })
     }
 
-    async fn connect_socket() -> Result<(SocketWrite, SocketRead)> {
+    async fn connect_socket(gateway_url: &str) -> Result<(SocketWrite, SocketRead)> {
         info!("...");
 
-        let (socket, _response) = connect_async(consts::GATEWAY_URL).await?;
+        let (socket, _response) = connect_async(gateway_url).await?;
 
         let (write, read) = socket.split();
         let (write, read) = (Arc::new(Mutex::new(write)), Arc::new(Mutex::new(read)));
@@ -90,110 +211,418 @@ impl WsManager {
         Ok((write, read))
     }
 
-    pub async fn start(&mut self, intents: u32, handlers: Handlers) {
-        let mut interval = 0;
+    /// Runs the connection until the client shuts down or it fails for good.
+    pub async fn start(&mut self, intents: u32, handlers: Handlers) -> Result<(), DescordError> {
         loop {
-            let e = self.connect(intents, handlers.clone()).await;
-            info!("{e:?}");
-
-            if let Ok(false) = e {
-                info!("shutting down the bot");
+            if self.options.shutdown.is_shutdown() {
                 break;
             }
 
+            let step = match self.connect(intents, handlers.clone()).await {
+                Ok(step) => step,
+                // errors of an established session aren't failed attempts
+                Err(e) if self.attempts == 0 => {
+                    Self::report(&handlers, e);
+                    NextStep::Resume
+                }
+
+                Err(e) => {
+                    Self::report(&handlers, self.attempt_error(e));
+                    NextStep::Resume
+                }
+            };
+
+            info!("{step:?}");
+
+            match step {
+                NextStep::Fatal(e) => return Err(e),
+
+                NextStep::Shutdown => {
+                    info!("Gateway connection closed for shutdown");
+                    break;
+                }
+
+                NextStep::Resume => {}
+
+                NextStep::Identify => {
+                    self.session = None;
+                    *self.sequence.lock().await = 0;
+                }
+            }
+
             error!("Connection closed");
-            interval += 1;
-            info!("Attempting to reconnect in {} seconds", interval);
-            thread::sleep(Duration::from_secs(interval));
-            self.socket = Self::reconnect(Arc::clone(&self.sequence)).await;
+            if !self.reconnect_with_backoff(&handlers).await? {
+                break;
+            }
         }
+
+        Ok(())
     }
 
-    // if retuns Ok(false), then it shouldn't try to reconnect
-    async fn connect(&mut self, intents: u32, handlers: Handlers) -> Result<bool> {
-        if let Some(Ok(Message::Text(body))) = self.socket.1.lock().await.next().await {
-            let Some(payload) = Payload::parse(&body) else {
-                panic!("Failed to parse json, body: {body}");
-            };
+    /// Keeps trying to open a new connection, returns false if the client shuts down
+    /// and an error if the retry budget ran out.
+    async fn reconnect_with_backoff(&mut self, handlers: &Handlers) -> Result<bool, DescordError> {
+        loop {
+            self.attempts += 1;
 
-            match payload.operation_code {
-                OpCode::Hello => {
-                    let time_ms = payload.data["heartbeat_interval"].as_u64().unwrap();
-                    let writer = Arc::clone(&self.socket.0);
-                    let reader = Arc::clone(&self.socket.1);
+            if let Some(max_attempts) = self.options.reconnect_policy.max_attempts {
+                if self.attempts > max_attempts {
+                    return Err(DescordError::GatewayRetriesExhausted {
+                        attempts: max_attempts,
+                    });
+                }
+            }
 
-                    info!("heartbeat interval: {}ms", time_ms);
+            if self.session.is_some()
+                && self.attempts > self.options.reconnect_policy.max_resume_attempts
+            {
+                warn!("Failed to resume the session, starting a new one");
+                self.session = None;
+                *self.sequence.lock().await = 0;
+            }
 
-                    tokio::spawn(async move {
-                        Self::heartbeat_start(Duration::from_millis(time_ms), writer, reader).await;
-                    });
+            let delay = self.options.reconnect_policy.delay(self.attempts);
+            info!(
+                "Attempting to reconnect in {:.2} seconds (attempt {})",
+                delay.as_secs_f32(),
+                self.attempts
+            );
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                Ok(()) = self.shutdown.changed() => return Ok(false),
+            }
 
-                    info!("performing handshake");
-                    self.identify(intents).await?;
+            match self.reconnect().await {
+                Ok(socket) => {
+                    self.socket = socket;
+                    // the compression context belongs to a single connection
+                    self.zlib = self.options.compress.then(ZlibStream::new);
+                    return Ok(true);
                 }
 
-                _ => panic!("Unknown event received when attempting to handshake"),
+                Err(e) => Self::report(handlers, self.attempt_error(e.into())),
             }
         }
+    }
+
+    fn attempt_error(&self, error: DescordError) -> DescordError {
+        DescordError::GatewayReconnect {
+            attempt: self.attempts,
+            source: Box::new(error),
+        }
+    }
+
+    /// Passes the error to the error handler, logs it if there isn't one.
+    fn report(handlers: &Handlers, error: DescordError) {
+        if let Some(error_handler) = &handlers.error_handler {
+            error_handler(error);
+        } else {
+            error!("{error}");
+        }
+    }
+
+    async fn connect(&mut self, intents: u32, handlers: Handlers) -> Result<NextStep, DescordError> {
+        let mut heartbeat_task = None;
+        let result = self.run(intents, handlers, &mut heartbeat_task).await;
+
+        // the heartbeat task belongs to this connection only
+        if let Some(task) = heartbeat_task {
+            task.abort();
+        }
+
+        result
+    }
+
+    async fn run(
+        &mut self,
+        intents: u32,
+        handlers: Handlers,
+        heartbeat_task: &mut Option<tokio::task::JoinHandle<()>>,
+    ) -> Result<NextStep, DescordError> {
+        let hello = loop {
+            let message = self.socket.1.lock().await.next().await;
+            let payload = match message {
+                Some(Ok(Message::Text(body))) => Payload::parse(&body),
+                Some(Ok(Message::Binary(data))) => match self.inflate(data)? {
+                    Some(data) => self.options.encoding.parse(&data),
+                    None => continue,
+                },
+
+                Some(Ok(Message::Close(frame))) => return Ok(Self::on_close(frame)),
+                Some(Err(e)) => return Err(e.into()),
+                x => {
+                    return Err(DescordError::GatewayHandshake(format!(
+                        "Expected hello, got: {x:?}"
+                    )))
+                }
+            };
+
+            break payload.ok_or_else(|| {
+                DescordError::GatewayHandshake("Failed to parse hello".to_string())
+            })?;
+        };
+
+        let OpCode::Hello = hello.operation_code else {
+            return Err(DescordError::GatewayHandshake(format!(
+                "Expected hello, got: {:?}",
+                hello.operation_code
+            )));
+        };
+
+        let time_ms = hello.data["heartbeat_interval"].as_u64().ok_or_else(|| {
+            DescordError::GatewayHandshake("Missing heartbeat interval".to_string())
+        })?;
+        let writer = Arc::clone(&self.socket.0);
+        let encoding = self.options.encoding;
+        let sequence = Arc::clone(&self.sequence);
+        let heartbeat = Arc::clone(&self.heartbeat);
+        let zombie = Arc::new(Notify::new());
+        let zombie_signal = Arc::clone(&zombie);
+
+        info!("heartbeat interval: {}ms", time_ms);
+
+        *heartbeat_task = Some(tokio::spawn(async move {
+            Self::heartbeat_start(
+                Duration::from_millis(time_ms),
+                writer,
+                encoding,
+                sequence,
+                heartbeat,
+                zombie_signal,
+            )
+            .await;
+        }));
+
+        if let Some(session) = &self.session {
+            info!("resuming session {}", session.id);
+            let seq = *self.sequence.lock().await;
+            self.send(&payloads::resume(self.options.http.token(), &session.id, seq)).await?;
+        } else {
+            info!("performing handshake");
+            self.identify(intents).await?;
+        }
 
         loop {
-            let x = self.socket.1.lock().await.next().await;
-            if let Some(Ok(Message::Text(body))) = x {
-                let Some(payload) = Payload::parse(&body) else {
-                    error!("Failed to parse json");
-                    continue;
-                };
+            let x = tokio::select! {
+                x = async { self.socket.1.lock().await.next().await } => x,
 
-                info!("Opcode: {:?}", payload.operation_code);
-                if let OpCode::Dispatch = payload.operation_code {
-                    let current_seq = payload.sequence.unwrap_or(0);
-                    *self.sequence.lock().await = current_seq;
-                    info!(
-                        "Received {} event, sequence: {current_seq}",
-                        payload.type_name.as_deref().unwrap_or("Unknown"),
-                        // For Debugging
-                        // json::parse(&payload.raw_json).unwrap().pretty(4)
-                    );
-
-                    let seq = Arc::clone(&self.sequence);
-                    let handlers = handlers.clone();
-
-                    if matches!(
-                        Event::from_str(payload.type_name.as_ref().unwrap().as_str()),
-                        Ok(Event::Reconnect)
-                    ) {
-                        self.socket = Self::reconnect(Arc::clone(&self.sequence)).await;
+                Ok(()) = self.presence_updates.changed() => {
+                    let presence = self.presence_updates.borrow_and_update().clone();
+                    if let Some(presence) = presence {
+                        self.send(&payloads::presence_update(&presence)).await?;
                     }
 
-                    tokio::spawn(async move {
-                        if let Err(e) = Self::dispatch_event(payload, seq, handlers).await {
-                            log::error!("Error dispatching event: {}", e);
-                        }
-                    });
+                    continue;
                 }
-            } else {
-                match x.unwrap().unwrap() {
-                    Message::Close(Some(cf)) => {
-                        self.socket = Self::reconnect(Arc::clone(&self.sequence)).await;
-                    }
 
-                    x => error!("Closing the connection, got the error: {x:?}",),
+                Some(command) = self.commands.recv() => {
+                    self.send(&command).await?;
+                    continue;
+                }
+
+                Ok(()) = self.shutdown.changed() => {
+                    self.close(CloseCode::Normal).await;
+                    return Ok(NextStep::Shutdown);
                 }
 
-                return Ok(false);
+                _ = zombie.notified() => {
+                    // A zombie connection never delivers the close frame of the gateway,
+                    // so the read above would wait forever. The close frame is only sent
+                    // on a best effort basis, the socket is dropped once it's replaced.
+                    warn!("Heartbeat was not acknowledged, dropping the zombie connection");
+                    let _ = tokio::time::timeout(
+                        consts::ZOMBIE_CLOSE_TIMEOUT,
+                        self.close(CloseCode::Library(4000)),
+                    )
+                    .await;
+
+                    return Ok(NextStep::Resume);
+                }
+            };
+
+            let payload = match x {
+                Some(Ok(Message::Text(body))) => Payload::parse(&body),
+                Some(Ok(Message::Binary(data))) => match self.inflate(data)? {
+                    Some(data) => self.options.encoding.parse(&data),
+                    None => continue,
+                },
+
+                // the socket was closed, e.g. because of a zombie connection
+                Some(Ok(Message::Close(frame))) => return Ok(Self::on_close(frame)),
+                None => {
+                    info!("Connection closed by the gateway");
+                    return Ok(NextStep::Resume);
+                }
+
+                Some(Err(e)) => return Err(e.into()),
+                Some(Ok(_)) => continue,
+            };
+
+            let Some(payload) = payload else {
+                error!("Failed to parse payload");
+                continue;
+            };
+
+            info!("Opcode: {:?}", payload.operation_code);
+            match payload.operation_code {
+                OpCode::Dispatch => {}
+
+                OpCode::HeartbeatACK => {
+                    let mut heartbeat = self.heartbeat.lock().unwrap();
+                    heartbeat.acked = true;
+                    heartbeat.latency = heartbeat.last_sent.map(|sent| sent.elapsed());
+                    continue;
+                }
+
+                OpCode::Heartbeat => {
+                    // discord requested a heartbeat, send one right away
+                    let seq = *self.sequence.lock().await;
+                    self.send(&payloads::heartbeat(seq)).await?;
+                    continue;
+                }
+
+                OpCode::Reconnect => {
+                    info!("Gateway requested a reconnect");
+                    self.close(CloseCode::Library(4000)).await;
+                    return Ok(NextStep::Resume);
+                }
+
+                OpCode::InvalidSession => {
+                    let resumable = payload.data.as_bool().unwrap_or(false);
+                    warn!("Session invalidated, resumable: {resumable}");
+
+                    // https://discord.com/developers/docs/topics/gateway#resuming
+                    let delay = rand::thread_rng().gen_range(1.0..=5.0);
+                    tokio::time::sleep(Duration::from_secs_f64(delay)).await;
+
+                    return Ok(if resumable {
+                        self.close(CloseCode::Library(4000)).await;
+                        NextStep::Resume
+                    } else {
+                        self.close(CloseCode::Normal).await;
+                        NextStep::Identify
+                    });
+                }
+
+                _ => continue,
+            }
+
+            if matches!(payload.type_name.as_deref(), Some("READY" | "RESUMED")) {
+                self.attempts = 0;
+            }
+
+            if payload.type_name.as_deref() == Some("READY") {
+                self.session = Some(Session {
+                    id: payload.data["session_id"].as_str().unwrap_or_default().to_owned(),
+                    resume_gateway_url: payload.data["resume_gateway_url"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_owned(),
+                });
             }
+
+            let current_seq = payload.sequence.unwrap_or(0);
+            *self.sequence.lock().await = current_seq;
+            info!(
+                "Received {} event, sequence: {current_seq}",
+                payload.type_name.as_deref().unwrap_or("Unknown"),
+                // For Debugging
+                // json::parse(&payload.raw_json).unwrap().pretty(4)
+            );
+
+            // events still buffered once a shutdown was requested are not dispatched
+            if self.options.shutdown.is_shutdown() {
+                self.close(CloseCode::Normal).await;
+                return Ok(NextStep::Shutdown);
+            }
+
+            let context = self.shard_context(&handlers);
+            let parsed = self
+                .options
+                .http
+                .scope(CURRENT_SHARD.scope(context.clone(), Self::parse_event(payload)))
+                .await;
+
+            let dispatch = match parsed {
+                Ok(Some(dispatch)) => dispatch,
+                Ok(None) => continue,
+                Err(e) => {
+                    log::error!("Error dispatching event: {}", e);
+                    continue;
+                }
+            };
+
+            // Streams get the events in the order of the gateway, a full stream
+            // misses events instead of stalling the shard.
+            event_stream::publish(
+                &handlers.event_streams,
+                dispatch.event,
+                &dispatch.data,
+                context.info,
+            );
+
+            let handlers = handlers.clone();
+            let task = self.options.shutdown.track();
+
+            tokio::spawn(self.options.http.scope(CURRENT_SHARD.scope(context, async move {
+                if let Err(e) = Self::dispatch_event(dispatch, handlers).await {
+                    log::error!("Error dispatching event: {}", e);
+                }
+
+                drop(task);
+            })));
+        }
+    }
+
+    /// Decompresses a binary frame, returns `None` until a complete payload was received.
+    fn inflate(&mut self, data: Vec<u8>) -> Result<Option<Vec<u8>>, DescordError> {
+        match &mut self.zlib {
+            Some(zlib) => zlib.push(&data),
+            None => Ok(Some(data)),
         }
+    }
+
+    /// Decides what to do after the gateway closed the connection.
+    fn on_close(frame: Option<CloseFrame>) -> NextStep {
+        let Some(frame) = frame else {
+            info!("Connection closed without a close frame");
+            return NextStep::Resume;
+        };
 
-        info!("Exiting...");
+        let code = u16::from(frame.code);
+        warn!("Connection closed with code {code}: {}", frame.reason);
 
-        Ok(true)
+        match GatewayCloseCode::parse(code).map(GatewayCloseCode::behaviour) {
+            Some(CloseBehaviour::Fatal) => NextStep::Fatal(DescordError::GatewayClosed {
+                code,
+                reason: frame.reason.into_owned(),
+            }),
+
+            Some(CloseBehaviour::Identify) => NextStep::Identify,
+            Some(CloseBehaviour::Resume) | None => NextStep::Resume,
+        }
     }
 
-    async fn dispatch_event(
-        payload: Payload,
-        seq: Arc<Mutex<usize>>,
-        handlers: Handlers,
-    ) -> Result<(), DescordError> {
+    /// Sends a close frame, a `1000` code invalidates the session
+    /// while any other code allows it to be resumed.
+    async fn close(&self, code: CloseCode) {
+        let _ = self
+            .socket
+            .0
+            .lock()
+            .await
+            .send(Message::Close(Some(CloseFrame {
+                code,
+                reason: "".into(),
+            })))
+            .await;
+    }
+
+    /// Deserializes a dispatched event and updates the caches.
+    ///
+    /// This runs in the read loop so the caches and event streams see the
+    /// events in the order of the gateway, handlers run in [`Self::dispatch_event`].
+    async fn parse_event(payload: Payload) -> Result<Option<Dispatch>, DescordError> {
         // info!(
         //     "payload: {}",
         //     json::parse(&payload.raw_json).unwrap().pretty(4)
@@ -203,101 +632,27 @@ impl WsManager {
             Ok(event) => event,
             Err(_) => {
                 error!("Failed to parse event from payload type name");
-                return Ok(());
+                return Ok(None);
             }
         };
+        let mut raw_delete = None;
+        let http = Http::current()?;
+        let cache = http.cache();
 
         let data = match event {
             Event::Ready => {
                 let data = ReadyResponse::deserialize_json(&payload.raw_json)?;
 
-                *RESUME_GATEWAY_URL.lock().unwrap() = Some(data.data.resume_gateway_url.clone());
-                *SESSION_ID.lock().unwrap() = Some(data.data.session_id.clone());
-                *BOT_ID.lock().unwrap() = Some(data.data.user.id.clone());
+                http.set_bot_id(data.data.user.id.clone());
 
                 data.data.into()
             }
 
-            Event::MessageCreate => {
-                let message_data = MessageResponse::deserialize_json(&payload.raw_json)?;
-
-                MESSAGE_CACHE
-                    .lock()
-                    .await
-                    .put(message_data.data.id.clone(), message_data.data.clone());
-
-                if let Some(command_name) = message_data.data.content.split(' ').next() {
-                    if let Some(command_handler_fn) = handlers.commands.get(command_name) {
-                        let mut required_permissions: u64 = 0;
-
-                        for permission in &command_handler_fn.permissions {
-                            if let Some(p) = consts::permissions::parse(permission) {
-                                required_permissions |= p;
-                            } else {
-                                log::error!("Invalid permission name: {}", permission);
-                            }
-                        }
-
-                        let msg_id = message_data.data.id.clone();
-                        let channel_id = message_data.data.channel_id.clone();
-
-                        if required_permissions != 0 {
-                            // Using map_err to convert errors
-                            let channel = fetch_channel(&channel_id).await?;
-                            let guild_id = channel.guild_id.as_deref().ok_or(DescordError::Other("Channel has no guild_id".into()))?;
-                            let guild = fetch_guild(guild_id).await?;
-                            
-                            let data = message_data.data.clone();
-                            let member = data.member.as_ref().ok_or(DescordError::Other("Message has no member".into()))?;
-                            let author = data.author.as_ref().ok_or(DescordError::Other("Message has no author".into()))?;
-
-                            let user_permissions = Self::fetch_permissions(
-                                member.roles.clone(),
-                                author.id.clone(),
-                                &guild,
-                                Some(&channel),
-                            )
-                            .await?;
-
-                            // bypass the role check if user has admin perms
-                            if user_permissions != consts::permissions::ADMINISTRATOR
-                                && user_permissions & required_permissions != required_permissions
-                            {
-                                utils::send(
-                                    &channel_id,
-                                    Some(&msg_id),
-                                    "You are missing the required permissions for running this command",
-                                )
-                                .await?;
-
-                                return Ok(());
-                            }
-                        }
-
-                        let handler = command_handler_fn.clone();
-                        // Command handler returns HandlerResult, handle error
-                        if let Err(e) = handler.call(message_data.data.clone()).await {
-                            if let Some(error_handler) = &handlers.error_handler {
-                                error_handler(DescordError::CommandHandler {
-                                    command: command_name.to_string(),
-                                    source: e,
-                                });
-                            } else {
-                                let _ = utils::send(&channel_id, Some(&msg_id), e.to_string()).await;
-                            }
-                        }
-
-                        return Ok(());
-                    }
-                }
-
-                message_data.data.into()
-            }
-
-            Event::MessageUpdate => {
+            Event::MessageCreate | Event::MessageUpdate => {
                 let message_data = MessageResponse::deserialize_json(&payload.raw_json)?;
 
-                MESSAGE_CACHE
+                cache
+                    .messages
                     .lock()
                     .await
                     .put(message_data.data.id.clone(), message_data.data.clone());
@@ -322,23 +677,8 @@ impl WsManager {
             Event::MessageDelete => {
                 let data = DeletedMessageResponse::deserialize_json(&payload.raw_json)?;
 
-                if let Some(cached_data) = MESSAGE_CACHE.lock().await.pop(&data.data.message_id) {
-                    if let Some(handler) = handlers
-                        .event_handlers
-                        .get(&Event::MessageDeleteRaw)
-                        .cloned()
-                    {
-                        let msg_id = data.data.message_id.clone();
-                        let channel_id = data.data.channel_id.clone();
-
-                        tokio::spawn(async move {
-                            if let Err(e) = handler.call(data.data.into()).await {
-                                // Ignore error sending error message
-                                let _ = utils::send(&channel_id, Some(&msg_id), e.to_string()).await;
-                            }
-                        });
-                    }
-
+                if let Some(cached_data) = cache.messages.lock().await.pop(&data.data.message_id) {
+                    raw_delete = Some(data.data);
                     cached_data.into()
                 } else {
                     event = Event::MessageDeleteRaw;
@@ -346,14 +686,71 @@ impl WsManager {
                 }
             }
 
+            Event::MessageDeleteBulk => {
+                let data: misc::ResponseWrapper<BulkDeletedMessages> =
+                    DeJson::deserialize_json(&payload.raw_json)?;
+                let mut data = data.data;
+
+                let mut messages = cache.messages.lock().await;
+                data.cached = data.ids.iter().filter_map(|id| messages.pop(id)).collect();
+                drop(messages);
+
+                data.into()
+            }
+
+            Event::ThreadCreate | Event::ThreadUpdate | Event::ThreadDelete => {
+                let data: misc::ResponseWrapper<Channel> =
+                    DeJson::deserialize_json(&payload.raw_json)?;
+                let mut data = data.data;
+                data.mention = format!("<#{}>", data.id);
+
+                data.into()
+            }
+
+            Event::ThreadListSync => {
+                let data: misc::ResponseWrapper<ThreadListSync> =
+                    DeJson::deserialize_json(&payload.raw_json)?;
+                let mut data = data.data;
+
+                for thread in &mut data.threads {
+                    thread.mention = format!("<#{}>", thread.id);
+                }
+
+                data.into()
+            }
+
+            Event::ThreadMemberUpdate => {
+                let data: misc::ResponseWrapper<ThreadMember> =
+                    DeJson::deserialize_json(&payload.raw_json)?;
+                data.data.into()
+            }
+
+            Event::ThreadMembersUpdate => {
+                let data: misc::ResponseWrapper<ThreadMembersUpdate> =
+                    DeJson::deserialize_json(&payload.raw_json)?;
+                data.data.into()
+            }
+
+            Event::GuildMembersChunk => {
+                let data: misc::ResponseWrapper<GuildMembersChunk> =
+                    DeJson::deserialize_json(&payload.raw_json)?;
+
+                if let Ok(gateway) = CURRENT_SHARD.try_with(|shard| Arc::clone(&shard.gateway)) {
+                    super::members::receive_chunk(&gateway, attach_current(data.data.clone()));
+                }
+
+                data.data.into()
+            }
+
             Event::Reconnect => {
-                // This case should be handled by the loop in connect()
-                return Ok(());
+                // Reconnects are handled by the connection loop in run()
+                return Ok(None);
             }
 
             Event::GuildRoleCreate => {
                 let data = RoleCreateResponse::deserialize_json(&payload.raw_json)?;
-                ROLE_CACHE
+                cache
+                    .roles
                     .lock()
                     .await
                     .put(data.data.role.id.clone(), data.data.role.clone());
@@ -362,7 +759,8 @@ impl WsManager {
 
             Event::GuildRoleUpdate => {
                 let data = RoleUpdateResponse::deserialize_json(&payload.raw_json)?;
-                ROLE_CACHE
+                cache
+                    .roles
                     .lock()
                     .await
                     .put(data.data.role.id.clone(), data.data.role.clone());
@@ -371,7 +769,7 @@ impl WsManager {
 
             Event::GuildRoleDelete => {
                 let data = RoleDeleteResponse::deserialize_json(&payload.raw_json)?;
-                ROLE_CACHE.lock().await.pop(&data.data.role_id);
+                cache.roles.lock().await.pop(&data.data.role_id);
                 data.data.into()
             }
 
@@ -401,30 +799,122 @@ impl WsManager {
                 };
 
                 let data = InteractionResponsePayload::deserialize_json(&json_dump)?;
+                data.data.into()
+            }
+
+            _ => {
+                info!("{event:?} event is not implemented");
+                return Ok(None);
+            }
+        };
 
-                if data.data.type_ == InteractionType::ApplicationCommand as u32 {
-                    if let Some(d) = &data.data.data {
+        Ok(Some(Dispatch {
+            event,
+            data: attach_current(data),
+            raw_delete,
+        }))
+    }
+
+    /// Runs the command and event handlers of an event parsed by [`Self::parse_event`].
+    async fn dispatch_event(dispatch: Dispatch, handlers: Handlers) -> Result<(), DescordError> {
+        let Dispatch {
+            event,
+            data,
+            raw_delete,
+        } = dispatch;
+
+        match &data {
+            HandlerValue::Message(message) if event == Event::MessageCreate => {
+                if let Some(command_name) = message.content.split(' ').next() {
+                    if let Some(command_handler_fn) = handlers.commands.get(command_name) {
+                        // commands don't reach the event handler, streams already got the message
+                        let mut required_permissions: u64 = 0;
+
+                        for permission in &command_handler_fn.permissions {
+                            if let Some(p) = consts::permissions::parse(permission) {
+                                required_permissions |= p;
+                            } else {
+                                log::error!("Invalid permission name: {}", permission);
+                            }
+                        }
+
+                        let msg_id = message.id.clone();
+                        let channel_id = message.channel_id.clone();
+
+                        if required_permissions != 0 {
+                            // Using map_err to convert errors
+                            let channel = fetch_channel(&channel_id).await?;
+                            let guild_id = channel.guild_id.as_deref().ok_or(DescordError::Other("Channel has no guild_id".into()))?;
+                            let guild = fetch_guild(guild_id).await?;
+                            
+                            let member = message.member.as_ref().ok_or(DescordError::Other("Message has no member".into()))?;
+                            let author = message.author.as_ref().ok_or(DescordError::Other("Message has no author".into()))?;
+
+                            let user_permissions = Self::fetch_permissions(
+                                member.roles.clone(),
+                                author.id.clone(),
+                                &guild,
+                                Some(&channel),
+                            )
+                            .await?;
+
+                            // bypass the role check if user has admin perms
+                            if user_permissions != consts::permissions::ADMINISTRATOR
+                                && user_permissions & required_permissions != required_permissions
+                            {
+                                utils::send(
+                                    &channel_id,
+                                    Some(&msg_id),
+                                    "You are missing the required permissions for running this command",
+                                )
+                                .await?;
+
+                                return Ok(());
+                            }
+                        }
+
+                        let handler = command_handler_fn.clone();
+                        // Command handler returns HandlerResult, handle error
+                        if let Err(e) = handler.call(message.clone()).await {
+                            if let Some(error_handler) = &handlers.error_handler {
+                                error_handler(DescordError::CommandHandler {
+                                    command: command_name.to_string(),
+                                    source: e,
+                                });
+                            } else {
+                                let _ = utils::send(&channel_id, Some(&msg_id), e.to_string()).await;
+                            }
+                        }
+
+                        return Ok(());
+                    }
+                }
+            }
+
+            HandlerValue::Interaction(interaction) => {
+                if interaction.type_ == InteractionType::ApplicationCommand as u32 {
+                    if let Some(d) = &interaction.data {
                         if let Some(id) = &d.id {
                              if let Some(command) = handlers.slash_commands.get(id) {
                             let handler = command.clone();
-                            if let Err(e) = handler.call(data.data.clone()).await {
+                            if let Err(e) = handler.call((**interaction).clone()).await {
                                 if let Some(error_handler) = &handlers.error_handler {
                                     error_handler(DescordError::CommandHandler {
                                         command: d.command_name.clone().unwrap_or_default(),
                                         source: e,
                                     });
                                 } else {
-                                    let _ = data.data.reply(e.to_string(), true).await;
+                                    let _ = interaction.reply(e.to_string(), true).await;
                                 }
                             };
                             }
                         }
                     }
-                } else if data.data.type_ == InteractionType::MessageComponent as u32 {
-                    if let Some(d) = &data.data.data {
+                } else if interaction.type_ == InteractionType::MessageComponent as u32 {
+                    if let Some(d) = &interaction.data {
                          if let Some(custom_id) = &d.custom_id {
                             if let Some(component_handler) = handlers.component_handlers.get(custom_id) {
-                                if let Err(e) = component_handler.call(data.data.clone()).await {
+                                if let Err(e) = component_handler.call((**interaction).clone()).await {
                                     if let Some(error_handler) = &handlers.error_handler {
                                         error_handler(DescordError::CommandHandler {
                                             command: custom_id.clone(),
@@ -437,9 +927,9 @@ impl WsManager {
                             }
                         }
                     }
-                } else if data.data.type_ == InteractionType::ApplicationCommandAutocomplete as u32
+                } else if interaction.type_ == InteractionType::ApplicationCommandAutocomplete as u32
                 {
-                    if let Some(d) = &data.data.data {
+                    if let Some(d) = &interaction.data {
                         if let Some(id) = &d.id {
                             if let Some(slash_command) = handlers.slash_commands.get(id) {
                                 if let Some(options) = &d.options {
@@ -460,7 +950,7 @@ impl WsManager {
                                                     Method::POST,
                                                     &format!(
                                                         "interactions/{}/{}/callback",
-                                                        data.data.id, data.data.token
+                                                        interaction.id, interaction.token
                                                     ),
                                                     Some(&InteractionAutoCompleteChoices::new(choices).serialize_json()),
                                                 )
@@ -473,18 +963,40 @@ impl WsManager {
                         }
                     }
                 }
-
-                data.data.into()
             }
 
-            _ => {
-                info!("{event:?} event is not implemented");
-                return Ok(());
+            _ => {}
+        }
+
+        // a deleted message that was cached also reaches the raw handlers
+        if let Some(deleted) = raw_delete {
+            if let Some(raw_handlers) = handlers
+                .event_handlers
+                .get(&Event::MessageDeleteRaw)
+                .cloned()
+            {
+                let msg_id = deleted.message_id.clone();
+                let channel_id = deleted.channel_id.clone();
+                let shard = CURRENT_SHARD.with(ShardContext::clone);
+                let http = Http::current()?;
+                let task = shard.shutdown.track();
+
+                tokio::spawn(http.scope(CURRENT_SHARD.scope(shard, async move {
+                    for handler in raw_handlers {
+                        if let Err(e) = handler.call(deleted.clone().into()).await {
+                            // Ignore error sending error message
+                            let _ = utils::send(&channel_id, Some(&msg_id), e.to_string()).await;
+                        }
+                    }
+
+                    drop(task);
+                })));
             }
-        };
+        }
 
-        if let Some(handler) = handlers.event_handlers.get(&event) {
-            if let Err(e) = handler.call(data).await {
+        let event_handlers = handlers.event_handlers.get(&event).into_iter().flatten();
+        for handler in event_handlers {
+            if let Err(e) = handler.call(data.clone()).await {
                 if let Some(error_handler) = &handlers.error_handler {
                     error_handler(DescordError::EventHandler {
                         event: format!("{:?}", event),
@@ -499,62 +1011,102 @@ impl WsManager {
         Ok(())
     }
 
-    async fn reconnect(seq: Arc<Mutex<usize>>) -> (SocketWrite, SocketRead) {
+    /// Opens a new connection, to the resume url if there is a session to resume.
+    /// The resume (or identify) is sent once the gateway says hello.
+    async fn reconnect(&self) -> Result<(SocketWrite, SocketRead)> {
         info!("Reopening the connection...");
 
-        let resume_gateway_url = RESUME_GATEWAY_URL.lock().unwrap().as_ref().unwrap().clone();
-        let token = TOKEN.lock().unwrap().as_ref().unwrap().clone();
-        let session_id = SESSION_ID.lock().unwrap().as_ref().unwrap().clone();
-        let seq = *seq.lock().await;
-
-        let (mut socket, _) = connect_async(Url::parse(&resume_gateway_url).unwrap().as_str())
-            .await
-            .unwrap();
-
-        socket
-            .send(Message::Text(json::stringify(payloads::resume(
-                &token,
-                &session_id,
-                seq,
-            ))))
-            .await
-            .expect("Failed to send resume event");
+        let url = match &self.session {
+            Some(session) => format!("{}{}", session.resume_gateway_url, consts::GATEWAY_QUERY),
+            None => self.gateway_url.clone(),
+        };
 
-        let (write, read) = socket.split();
-        let (write, read) = (Arc::new(Mutex::new(write)), Arc::new(Mutex::new(read)));
-        (write, read)
+        Self::connect_socket(&self.options.url(&url)).await
     }
 
     async fn heartbeat_start(
         heartbeat_interval: Duration,
         writer: SocketWrite,
-        reader: SocketRead,
+        encoding: GatewayEncoding,
+        sequence: Arc<Mutex<usize>>,
+        heartbeat: Arc<std::sync::Mutex<HeartbeatState>>,
+        zombie: Arc<Notify>,
     ) {
-        let mut last_sequence: usize = 0;
+        {
+            // fresh connection, nothing is waiting for an acknowledgement
+            let mut heartbeat = heartbeat.lock().unwrap();
+            heartbeat.last_sent = None;
+            heartbeat.acked = true;
+        }
+
+        // https://discord.com/developers/docs/topics/gateway#sending-heartbeats
+        // the first heartbeat is delayed by a random fraction of the interval
+        let jitter = rand::thread_rng().gen_range(0.0..1.0);
+        tokio::time::sleep(heartbeat_interval.mul_f64(jitter)).await;
+
         loop {
-            let message = Message::Text(json::stringify(payloads::heartbeat(last_sequence)));
-            info!("sending heartbeat");
-            writer
-                .lock()
-                .await
-                .send(message)
-                .await
-                .expect("Failed to send heartbeat");
+            if !heartbeat.lock().unwrap().acked {
+                // The last heartbeat was never acknowledged, the connection is a zombie.
+                // The read loop is told directly, it would never see a close frame.
+                zombie.notify_one();
+                return;
+            }
 
-            // TODO: if it fails, reconnect
+            let last_sequence = *sequence.lock().await;
+            let message = encoding.message(&payloads::heartbeat(last_sequence));
+            info!("sending heartbeat, sequence: {last_sequence}");
+
+            if let Err(e) = writer.lock().await.send(message).await {
+                error!("Failed to send heartbeat: {e}");
+                zombie.notify_one();
+                return;
+            }
+
+            {
+                let mut heartbeat = heartbeat.lock().unwrap();
+                heartbeat.last_sent = Some(Instant::now());
+                heartbeat.acked = false;
+            }
 
             tokio::time::sleep(heartbeat_interval).await;
-            last_sequence += 1;
         }
     }
 
-    async fn identify(&self, intents: u32) -> Result<()> {
-        self.send_text(json::stringify(payloads::identify(&self.token, intents)))
-            .await
+    async fn identify(&mut self, intents: u32) -> Result<()> {
+        let shard = self.shard_info();
+        self.identify_queue.wait(shard.id).await;
+
+        // the identify payload carries the latest presence, no need to send it again
+        let presence = self.presence_updates.borrow_and_update().clone();
+
+        self.send(&payloads::identify(
+            self.options.http.token(),
+            intents,
+            self.shard.map(|shard| [shard.id, shard.total]),
+            presence.as_ref(),
+            &self.options.identify_properties,
+        ))
+        .await
+    }
+
+    /// Returns the shard this connection belongs to, `0/1` if the client isn't sharded.
+    fn shard_info(&self) -> ShardInfo {
+        self.shard.unwrap_or(ShardInfo { id: 0, total: 1 })
+    }
+
+    fn shard_context(&self, handlers: &Handlers) -> ShardContext {
+        ShardContext {
+            info: self.shard_info(),
+            heartbeat: Arc::clone(&self.heartbeat),
+            gateway: Arc::clone(&self.gateway),
+            state: Arc::clone(&handlers.state),
+            shutdown: self.options.shutdown.clone(),
+        }
     }
 
-    async fn send_text(&self, msg: String) -> Result<()> {
-        self.socket.0.lock().await.send(Message::Text(msg)).await
+    async fn send(&self, payload: &JsonValue) -> Result<()> {
+        let message = self.options.encoding.message(payload);
+        self.socket.0.lock().await.send(message).await
     }
 
     async fn fetch_permissions(
@@ -614,11 +1166,13 @@ impl WsManager {
 }
 
 pub struct Handlers {
-    pub event_handlers: Arc<HashMap<Event, EventHandler>>,
+    pub event_handlers: Arc<HashMap<Event, Vec<EventHandler>>>,
     pub commands: Arc<HashMap<String, Command>>,
     pub slash_commands: Arc<HashMap<String, SlashCommand>>,
     pub component_handlers: Arc<HashMap<String, ComponentHandler>>,
     pub error_handler: Option<ErrorHandlerFn>,
+    pub(crate) event_streams: Arc<Vec<EventSubscriber>>,
+    pub(crate) state: Arc<StateMap>,
 }
 
 impl Clone for Handlers {
@@ -629,6 +1183,8 @@ impl Clone for Handlers {
             slash_commands: Arc::clone(&self.slash_commands),
             component_handlers: Arc::clone(&self.component_handlers),
             error_handler: self.error_handler.clone(),
+            event_streams: Arc::clone(&self.event_streams),
+            state: Arc::clone(&self.state),
         }
     }
 }
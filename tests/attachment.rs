@@ -0,0 +1,115 @@
+//! Tests of attachment uploads against a local stand-in for the rest api.
+
+mod common;
+
+use std::io::Cursor;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use common::{json_for, MockServer, Response};
+use descord::models::attachment::AttachmentPayload;
+use descord::prelude::*;
+use descord::utils;
+
+const MESSAGE: &str =
+    r#"{"id":"9","channel_id":"1","content":"hi","attachments":[],"components":[]}"#;
+
+fn message(attachments: Vec<AttachmentPayload>) -> CreateMessageData {
+    CreateMessageData {
+        content: "hi".to_owned(),
+        attachments,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn files_and_readers_are_streamed() {
+    let path = std::env::temp_dir().join(format!("descord-{}.txt", std::process::id()));
+    tokio::fs::write(&path, "from a file").await.unwrap();
+
+    let server = json_for(&[("*", "", MESSAGE)]).await;
+    let client = server.client().await;
+
+    let attachments = vec![
+        AttachmentPayload::from_path(&path).description("A file"),
+        AttachmentPayload::from_reader("stream.bin", Cursor::new(b"from a reader".to_vec())),
+    ];
+
+    client
+        .http()
+        .scope(utils::send("1", None, message(attachments)))
+        .await
+        .unwrap();
+    tokio::fs::remove_file(&path).await.unwrap();
+
+    let body = server.requests()[0].body_str();
+    assert!(body.contains("from a file"), "{body}");
+    assert!(body.contains("from a reader"), "{body}");
+    assert!(body.contains(r#""description":"A file""#), "{body}");
+    assert!(body.contains(r#"filename="stream.bin""#), "{body}");
+}
+
+#[tokio::test]
+async fn missing_files_fail_before_sending() {
+    let server = json_for(&[("*", "", MESSAGE)]).await;
+    let client = server.client().await;
+
+    let attachments = vec![AttachmentPayload::from_path("does/not/exist.png")];
+    let error = client
+        .http()
+        .scope(utils::send("1", None, message(attachments)))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, DescordError::Io(_)), "{error:?}");
+    assert!(server.requests().is_empty());
+}
+
+#[tokio::test]
+async fn readers_are_not_retried() {
+    let calls = AtomicUsize::new(0);
+    let server = MockServer::start(move |_| {
+        // the first attempt of every message fails
+        if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+            Response::json(r#"{"message":"Internal Server Error","code":0}"#).status(500)
+        } else {
+            Response::json(MESSAGE)
+        }
+    })
+    .await;
+
+    let client = server
+        .client_with(|builder| {
+            builder.retry_policy(RetryPolicy {
+                base_delay: Duration::from_millis(10),
+                retry_non_idempotent: true,
+                ..Default::default()
+            })
+        })
+        .await;
+
+    let attachments = vec![AttachmentPayload::from_reader(
+        "stream.bin",
+        Cursor::new(b"once".to_vec()),
+    )];
+    let error = client
+        .http()
+        .scope(utils::send("1", None, message(attachments)))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, DescordError::Api(_)), "{error:?}");
+    assert_eq!(server.requests().len(), 1);
+
+    // content in memory is sent again
+    let attachments = vec![AttachmentPayload::from_bytes("memory.bin", "again")];
+    client
+        .http()
+        .scope(utils::send("1", None, message(attachments)))
+        .await
+        .unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[2].body_str().contains("again"));
+}
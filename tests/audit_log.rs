@@ -0,0 +1,34 @@
+//! Tests of audit log reasons against a local stand-in for the rest api.
+
+mod common;
+
+use common::json_for;
+use descord::utils;
+
+#[tokio::test]
+async fn reasons_are_sent_url_encoded() {
+    let server = json_for(&[]).await;
+    let client = server.client().await;
+
+    client
+        .http()
+        .scope(async {
+            utils::delete_message_with_reason("1", "2", "spam & ads")
+                .await
+                .unwrap();
+            utils::delete_channel("1", None).await.unwrap();
+            utils::bulk_delete_messages("1", vec!["2".to_owned(), "3".to_owned()], Some("raid"))
+                .await
+                .unwrap();
+        })
+        .await;
+
+    let requests = server.requests();
+    assert_eq!(requests[0].path, "channels/1/messages/2");
+    assert_eq!(
+        requests[0].header("x-audit-log-reason"),
+        Some("spam%20%26%20ads")
+    );
+    assert_eq!(requests[1].header("x-audit-log-reason"), None);
+    assert_eq!(requests[2].header("x-audit-log-reason"), Some("raid"));
+}
@@ -0,0 +1,189 @@
+//! Tests of the channel builders against a local stand-in for the rest api.
+
+mod common;
+
+use common::{json_for, Route};
+use descord::prelude::*;
+use descord::utils;
+use nanoserde::DeJson;
+
+const CHANNEL: &str = r#"{"id":"7","type":0,"guild_id":"5","name":"ticket-42"}"#;
+
+const ROUTES: &[Route] = &[("POST", "", CHANNEL), ("PATCH", "channels/", CHANNEL)];
+
+#[tokio::test]
+async fn created_channels_send_the_builder_fields() {
+    let server = json_for(ROUTES).await;
+    let client = server.client().await;
+
+    let data = CreateChannel::new("ticket-42")
+        .channel_type(ChannelType::GuildText)
+        .topic("Support ticket")
+        .parent("3")
+        .position(2)
+        .permission_overwrites(vec![Overwrite::role("5", 1024, 0)])
+        .rate_limit_per_user(10)
+        .nsfw(false)
+        .reason("Ticket opened");
+
+    let channel = client
+        .http()
+        .scope(utils::create_channel("5", data))
+        .await
+        .unwrap();
+    assert_eq!(channel.id, "7");
+
+    let request = &server.requests()[0];
+    assert_eq!(request.path, "guilds/5/channels");
+    assert_eq!(
+        request.header("x-audit-log-reason"),
+        Some("Ticket%20opened")
+    );
+    assert_eq!(
+        request.json(),
+        json::object! {
+            name: "ticket-42",
+            type: 0,
+            topic: "Support ticket",
+            parent_id: "3",
+            position: 2,
+            permission_overwrites: [{ id: "5", type: 0, allow: "1024", deny: "0" }],
+            rate_limit_per_user: 10,
+            nsfw: false,
+        }
+    );
+}
+
+#[tokio::test]
+async fn modified_channels_only_send_the_fields_that_were_set() {
+    let server = json_for(ROUTES).await;
+    let client = server.client().await;
+
+    let data = ModifyChannel::new()
+        .name("closed-42")
+        .parent(None)
+        .reason("Ticket closed");
+
+    client
+        .http()
+        .scope(utils::modify_channel("7", data))
+        .await
+        .unwrap();
+
+    let request = &server.requests()[0];
+    assert_eq!(request.method, "PATCH");
+    assert_eq!(request.path, "channels/7");
+    assert_eq!(
+        request.header("x-audit-log-reason"),
+        Some("Ticket%20closed")
+    );
+    assert_eq!(
+        request.json(),
+        json::object! { name: "closed-42", parent_id: null }
+    );
+}
+
+#[tokio::test]
+async fn updated_channels_leave_out_read_only_fields() {
+    let server = json_for(ROUTES).await;
+    let client = server.client().await;
+
+    let channel = Channel::deserialize_json(
+        r#"{
+            "id": "7",
+            "type": 2,
+            "guild_id": "5",
+            "name": "voice",
+            "position": 4,
+            "permission_overwrites": [{ "id": "9", "type": 1, "allow": "0", "deny": "2048" }],
+            "bitrate": 64000,
+            "user_limit": 10,
+            "parent_id": "3",
+            "last_message_id": "100",
+            "rtc_region": "rotterdam",
+            "flags": 0
+        }"#,
+    )
+    .unwrap();
+
+    client
+        .http()
+        .scope(utils::update_channel(channel))
+        .await
+        .unwrap();
+
+    let request = &server.requests()[0];
+    assert_eq!(request.header("x-audit-log-reason"), None);
+    assert_eq!(
+        request.json(),
+        json::object! {
+            name: "voice",
+            position: 4,
+            permission_overwrites: [{ id: "9", type: 1, allow: "0", deny: "2048" }],
+            bitrate: 64000,
+            user_limit: 10,
+            parent_id: "3",
+        }
+    );
+}
+
+#[tokio::test]
+async fn permissions_and_positions_send_audit_log_reasons() {
+    let server = json_for(ROUTES).await;
+    let client = server.client().await;
+
+    let mut moved = ChannelPosition::new("8", 0);
+    moved.lock_permissions = Some(true);
+    moved.parent_id = Some("3".to_owned());
+
+    client
+        .http()
+        .scope(async {
+            let overwrite = Overwrite::member("9", 1024, 2048);
+            utils::edit_channel_permissions("7", overwrite, Some("Access granted"))
+                .await
+                .unwrap();
+            utils::delete_channel_permission("7", "9", Some("Access revoked"))
+                .await
+                .unwrap();
+            utils::reorder_channels(
+                "5",
+                vec![ChannelPosition::new("7", 1), moved],
+                Some("Sorted"),
+            )
+            .await
+            .unwrap();
+        })
+        .await;
+
+    let requests = server.requests();
+
+    assert_eq!(requests[0].method, "PUT");
+    assert_eq!(requests[0].path, "channels/7/permissions/9");
+    assert_eq!(
+        requests[0].header("x-audit-log-reason"),
+        Some("Access%20granted")
+    );
+    assert_eq!(
+        requests[0].json(),
+        json::object! { type: 1, allow: "1024", deny: "2048" }
+    );
+
+    assert_eq!(requests[1].method, "DELETE");
+    assert_eq!(requests[1].path, "channels/7/permissions/9");
+    assert_eq!(
+        requests[1].header("x-audit-log-reason"),
+        Some("Access%20revoked")
+    );
+
+    assert_eq!(requests[2].method, "PATCH");
+    assert_eq!(requests[2].path, "guilds/5/channels");
+    assert_eq!(requests[2].header("x-audit-log-reason"), Some("Sorted"));
+    assert_eq!(
+        requests[2].json(),
+        json::array![
+            { id: "7", position: 1 },
+            { id: "8", position: 0, lock_permissions: true, parent_id: "3" },
+        ]
+    );
+}
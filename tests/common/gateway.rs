@@ -0,0 +1,115 @@
+//! A local stand-in for the gateway, used by the integration tests.
+
+use std::time::Duration;
+
+use descord::prelude::*;
+use futures_util::StreamExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::handshake::server::Request as Handshake;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+pub type Socket = WebSocketStream<TcpStream>;
+
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Builds a client connecting to the local gateway.
+pub async fn client(gateway_url: &str, configure: fn(ClientBuilder) -> ClientBuilder) -> Client {
+    let builder = Client::builder("TOKEN", 0u32, "!")
+        .api_url("http://127.0.0.1:9/api/v10")
+        .gateway_url(gateway_url)
+        .default_help(false)
+        .reconnect_policy(ReconnectPolicy {
+            base_delay: Duration::from_millis(10),
+            ..Default::default()
+        });
+
+    configure(builder).build().unwrap()
+}
+
+/// Starts a client connected to the local gateway, returns its shutdown handle.
+pub async fn start_client(
+    gateway_url: &str,
+    configure: fn(ClientBuilder) -> ClientBuilder,
+) -> ShutdownHandle {
+    let client = client(gateway_url, configure).await;
+    let shutdown = client.shutdown_handle();
+
+    tokio::spawn(client.login());
+    shutdown
+}
+
+pub async fn accept(listener: &TcpListener) -> Socket {
+    let (stream, _) = listener.accept().await.unwrap();
+    tokio_tungstenite::accept_async(stream).await.unwrap()
+}
+
+/// Accepts a connection, also returns the path and query it was opened with.
+pub async fn accept_with_uri(listener: &TcpListener) -> (Socket, String) {
+    let (stream, _) = listener.accept().await.unwrap();
+    let mut uri = String::new();
+
+    let socket = tokio_tungstenite::accept_hdr_async(stream, |request: &Handshake, response| {
+        uri = request.uri().to_string();
+        Ok(response)
+    })
+    .await
+    .unwrap();
+
+    (socket, uri)
+}
+
+/// Reads the next gateway payload sent by the client, skipping heartbeats.
+pub async fn next_payload(socket: &mut Socket) -> json::JsonValue {
+    loop {
+        let message = socket.next().await.unwrap().unwrap();
+        let Message::Text(text) = message else {
+            continue;
+        };
+
+        let payload = json::parse(&text).unwrap();
+        if payload["op"] != 1 {
+            return payload;
+        }
+    }
+}
+
+pub fn hello(heartbeat_interval: u64) -> Message {
+    Message::Text(json::object! { op: 10, d: { heartbeat_interval: heartbeat_interval } }.dump())
+}
+
+pub fn ready(session_id: &str, resume_gateway_url: &str) -> Message {
+    Message::Text(
+        json::object! {
+            op: 0,
+            t: "READY",
+            s: 1,
+            d: {
+                v: 10,
+                user: { id: "1", username: "bot", discriminator: "0" },
+                guilds: [],
+                session_id: session_id,
+                resume_gateway_url: resume_gateway_url,
+            },
+        }
+        .dump(),
+    )
+}
+
+pub fn message_create(seq: u64, content: &str) -> Message {
+    Message::Text(
+        json::object! {
+            op: 0,
+            t: "MESSAGE_CREATE",
+            s: seq,
+            d: {
+                id: seq.to_string(),
+                channel_id: "1",
+                content: content,
+                attachments: [],
+                components: [],
+            },
+        }
+        .dump(),
+    )
+}
@@ -0,0 +1,247 @@
+//! A local stand-in for the rest api, used by the integration tests.
+
+#![allow(dead_code)]
+
+pub mod gateway;
+
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use descord::prelude::*;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// A request received by the [`MockServer`].
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    /// The path and query, without the `/api/v10/` prefix.
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    /// When the request was received.
+    pub received: Instant,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn body_str(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    pub fn json(&self) -> json::JsonValue {
+        json::parse(&self.body_str()).unwrap()
+    }
+}
+
+/// A response sent by the [`MockServer`].
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn json(body: impl Into<String>) -> Self {
+        Self {
+            status: 200,
+            headers: Vec::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            status: 204,
+            headers: Vec::new(),
+            body: String::new(),
+        }
+    }
+
+    pub fn status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn header(mut self, name: &str, value: impl ToString) -> Self {
+        self.headers.push((name.to_owned(), value.to_string()));
+        self
+    }
+}
+
+type Handler = dyn Fn(&Request) -> Response + Send + Sync;
+
+/// A minimal http server answering every request with a handler.
+pub struct MockServer {
+    url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockServer {
+    pub async fn start(handler: impl Fn(&Request) -> Response + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/api/v10", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler: Arc<Handler> = Arc::new(handler);
+
+        let recorded = Arc::clone(&requests);
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let handler = Arc::clone(&handler);
+                let recorded = Arc::clone(&recorded);
+
+                tokio::spawn(async move {
+                    let _ = serve(stream, handler, recorded).await;
+                });
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// The api url to pass to [`ClientBuilder::api_url`].
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Returns a client sending its requests to this server.
+    pub async fn client(&self) -> Client {
+        self.client_with(|builder| builder).await
+    }
+
+    /// Returns a client sending its requests to this server, configured by `configure`.
+    pub async fn client_with(&self, configure: fn(ClientBuilder) -> ClientBuilder) -> Client {
+        let builder = Client::builder("TOKEN", 0u32, "!")
+            .api_url(&self.url)
+            .default_help(false);
+
+        configure(builder).build().unwrap()
+    }
+}
+
+/// A route of [`json_for`]: the method (`"*"` for any), a prefix of the path
+/// and the json to answer with, an empty body is answered with no content.
+pub type Route<'a> = (&'a str, &'a str, &'a str);
+
+/// Starts a server answering every request with its first matching route,
+/// requests matching no route are answered with no content.
+pub async fn json_for(routes: &[Route<'_>]) -> MockServer {
+    let routes: Vec<_> = routes
+        .iter()
+        .map(|&(method, prefix, body)| (method.to_owned(), prefix.to_owned(), body.to_owned()))
+        .collect();
+
+    MockServer::start(move |request| {
+        let route = routes.iter().find(|(method, prefix, _)| {
+            (method == "*" || *method == request.method) && request.path.starts_with(prefix)
+        });
+
+        match route {
+            Some((_, _, body)) if !body.is_empty() => Response::json(body.clone()),
+            _ => Response::empty(),
+        }
+    })
+    .await
+}
+
+async fn serve(
+    stream: TcpStream,
+    handler: Arc<Handler>,
+    recorded: Arc<Mutex<Vec<Request>>>,
+) -> std::io::Result<()> {
+    let mut stream = BufReader::new(stream);
+
+    loop {
+        let mut line = String::new();
+        if stream.read_line(&mut line).await? == 0 {
+            return Ok(());
+        }
+
+        let received = Instant::now();
+        let mut parts = line.split_whitespace();
+        let method = parts.next().unwrap_or_default().to_owned();
+        let path = parts.next().unwrap_or_default();
+        let path = path.strip_prefix("/api/v10/").unwrap_or(path).to_owned();
+
+        let mut headers = Vec::new();
+        loop {
+            let mut line = String::new();
+            stream.read_line(&mut line).await?;
+
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_lowercase(), value.trim().to_owned()));
+            }
+        }
+
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+
+        let mut body = Vec::new();
+        if header("transfer-encoding").is_some_and(|value| value.contains("chunked")) {
+            loop {
+                let mut size = String::new();
+                stream.read_line(&mut size).await?;
+                let size = usize::from_str_radix(size.trim(), 16).unwrap_or(0);
+
+                let mut chunk = vec![0; size + 2];
+                stream.read_exact(&mut chunk).await?;
+
+                if size == 0 {
+                    break;
+                }
+
+                body.extend_from_slice(&chunk[..size]);
+            }
+        } else if let Some(length) = header("content-length") {
+            body = vec![0; length.parse().unwrap_or(0)];
+            stream.read_exact(&mut body).await?;
+        }
+
+        let request = Request {
+            method,
+            path,
+            headers,
+            body,
+            received,
+        };
+
+        let response = handler(&request);
+        recorded.lock().unwrap().push(request);
+
+        let mut head = format!(
+            "HTTP/1.1 {} Mock\r\ncontent-length: {}\r\ncontent-type: application/json\r\n",
+            response.status,
+            response.body.len()
+        );
+
+        for (name, value) in &response.headers {
+            head += &format!("{name}: {value}\r\n");
+        }
+
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(b"\r\n").await?;
+        stream.write_all(response.body.as_bytes()).await?;
+        stream.flush().await?;
+    }
+}
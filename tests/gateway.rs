@@ -0,0 +1,1171 @@
+//! Tests of the gateway connection against a local websocket stand-in.
+
+mod common;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use common::gateway::*;
+use common::{json_for, MockServer, Response};
+use descord::prelude::*;
+use descord::utils;
+use flate2::{Compress, Compression, FlushCompress};
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message;
+
+/// The message returned by the rest api for replies.
+const REPLY: &str = r#"{"id":"9","channel_id":"1","attachments":[],"components":[]}"#;
+
+#[tokio::test]
+async fn zombie_connection_is_resumed() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let server_url = url.clone();
+    tokio::spawn(async move {
+        let mut first = accept(&listener).await;
+        first.send(hello(100)).await.unwrap();
+
+        let identify = next_payload(&mut first).await;
+        tx.send(identify).unwrap();
+        first.send(ready("session", &server_url)).await.unwrap();
+
+        // Heartbeats are never acknowledged and the connection is kept open without
+        // being read, like a connection whose peer disappeared.
+        let mut second = accept(&listener).await;
+        second.send(hello(1000)).await.unwrap();
+        tx.send(next_payload(&mut second).await).unwrap();
+
+        drop(first);
+        std::future::pending::<()>().await;
+    });
+
+    let shutdown = start_client(&url, |builder| builder).await;
+
+    let identify = tokio::time::timeout(TIMEOUT, rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(identify["op"], 2);
+
+    let resume = tokio::time::timeout(TIMEOUT, rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(resume["op"], 6);
+    assert_eq!(resume["d"]["session_id"], "session");
+    assert_eq!(resume["d"]["seq"], 1);
+
+    shutdown.shutdown();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn event_streams_keep_the_gateway_order() {
+    const EVENTS: u64 = 200;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    let server_url = url.clone();
+    tokio::spawn(async move {
+        let mut socket = accept(&listener).await;
+        socket.send(hello(45000)).await.unwrap();
+        next_payload(&mut socket).await;
+        socket.send(ready("session", &server_url)).await.unwrap();
+
+        for seq in 2..EVENTS + 2 {
+            let message = message_create(seq, &seq.to_string());
+            socket.send(message).await.unwrap();
+        }
+
+        std::future::pending::<()>().await;
+    });
+
+    let mut client = client(&url, |builder| builder).await;
+    let mut messages = client.event_stream_for(&[Event::MessageCreate]);
+
+    // handlers taking different amounts of time don't change the order of the stream
+    client.on(Event::MessageCreate, |data| async move {
+        if let HandlerValue::Message(message) = data {
+            let delay = message.id.parse::<u64>().unwrap() % 7;
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+        }
+        Ok(())
+    });
+
+    let shutdown = client.shutdown_handle();
+    tokio::spawn(client.login());
+
+    for seq in 2..EVENTS + 2 {
+        let event = tokio::time::timeout(TIMEOUT, messages.next())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let HandlerValue::Message(message) = event.data else {
+            panic!("unexpected event {:?}", event.event);
+        };
+        assert_eq!(message.content, seq.to_string());
+    }
+
+    shutdown.shutdown();
+}
+
+#[tokio::test]
+async fn full_event_streams_dont_stall_the_shard() {
+    const EVENTS: u64 = 300;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let server_url = url.clone();
+    tokio::spawn(async move {
+        let mut socket = accept(&listener).await;
+        socket.send(hello(100)).await.unwrap();
+        next_payload(&mut socket).await;
+        socket.send(ready("session", &server_url)).await.unwrap();
+
+        for seq in 2..EVENTS + 2 {
+            let message = message_create(seq, &seq.to_string());
+            socket.send(message).await.unwrap();
+        }
+
+        // acknowledge heartbeats, a second connection means the shard reconnected
+        loop {
+            tokio::select! {
+                message = socket.next() => {
+                    let Some(Ok(Message::Text(text))) = message else {
+                        break;
+                    };
+
+                    if json::parse(&text).unwrap()["op"] == 1 {
+                        let ack = json::object! { op: 11 }.dump();
+                        socket.send(Message::Text(ack)).await.unwrap();
+                        tx.send(true).unwrap();
+                    }
+                }
+                _ = listener.accept() => tx.send(false).unwrap(),
+            }
+        }
+    });
+
+    let mut client = client(&url, |builder| builder).await;
+    // never polled
+    let messages = client.event_stream_for(&[Event::MessageCreate]);
+
+    let shutdown = client.shutdown_handle();
+    tokio::spawn(client.login());
+
+    for _ in 0..5 {
+        let acked = tokio::time::timeout(TIMEOUT, rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(acked, "the shard reconnected");
+    }
+
+    // the stream buffers 256 events
+    assert_eq!(messages.lagged(), EVENTS - 256);
+
+    shutdown.shutdown();
+}
+
+#[tokio::test]
+async fn help_embeds_belong_to_their_client() {
+    let api = json_for(&[("*", "", REPLY)]).await;
+
+    let mut shutdowns = Vec::new();
+    for prefix in ["!", "?"] {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        let server_url = url.clone();
+        tokio::spawn(async move {
+            let mut socket = accept(&listener).await;
+            socket.send(hello(45000)).await.unwrap();
+            next_payload(&mut socket).await;
+            socket.send(ready("session", &server_url)).await.unwrap();
+            socket
+                .send(message_create(2, &format!("{prefix}help")))
+                .await
+                .unwrap();
+
+            std::future::pending::<()>().await;
+        });
+
+        let client = Client::builder("TOKEN", 0u32, prefix)
+            .api_url(api.url())
+            .gateway_url(&url)
+            .build()
+            .unwrap();
+
+        shutdowns.push(client.shutdown_handle());
+        tokio::spawn(client.login());
+    }
+
+    let replies = tokio::time::timeout(TIMEOUT, async {
+        loop {
+            let replies = api.requests();
+            if replies.len() == 2 {
+                return replies;
+            }
+
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    // each client lists its own help command
+    for prefix in ["!", "?"] {
+        let help = format!("`{prefix}help`");
+        assert_eq!(
+            replies
+                .iter()
+                .filter(|reply| reply.body_str().contains(&help))
+                .count(),
+            1,
+            "{replies:?}"
+        );
+    }
+
+    shutdowns.iter().for_each(ShutdownHandle::shutdown);
+}
+
+#[tokio::test]
+async fn models_keep_their_client_in_spawned_tasks() {
+    let api = json_for(&[("*", "", REPLY)]).await;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    let server_url = url.clone();
+    tokio::spawn(async move {
+        let mut socket = accept(&listener).await;
+        socket.send(hello(45000)).await.unwrap();
+        next_payload(&mut socket).await;
+        socket.send(ready("session", &server_url)).await.unwrap();
+        socket.send(message_create(2, "ping")).await.unwrap();
+
+        std::future::pending::<()>().await;
+    });
+
+    let mut client = Client::builder("TOKEN", 0u32, "!")
+        .api_url(api.url())
+        .gateway_url(&url)
+        .default_help(false)
+        .build()
+        .unwrap();
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    client.on(Event::MessageCreate, move |data| {
+        let tx = tx.clone();
+        async move {
+            if let HandlerValue::Message(message) = data {
+                // spawned tasks don't inherit the context of the handler
+                tokio::spawn(async move {
+                    assert!(Http::current().is_err());
+                    tx.send(message.reply("pong").await).unwrap();
+                });
+            }
+            Ok(())
+        }
+    });
+
+    let shutdown = client.shutdown_handle();
+    tokio::spawn(client.login());
+
+    let reply = tokio::time::timeout(TIMEOUT, rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(reply.is_ok(), "{reply:?}");
+
+    let requests = api.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].path, "channels/1/messages");
+    assert_eq!(requests[0].header("authorization"), Some("Bot TOKEN"));
+
+    shutdown.shutdown();
+}
+
+#[tokio::test]
+async fn handlers_of_an_event_run_in_registration_order() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    let server_url = url.clone();
+    tokio::spawn(async move {
+        let mut socket = accept(&listener).await;
+        socket.send(hello(45000)).await.unwrap();
+        next_payload(&mut socket).await;
+        socket.send(ready("session", &server_url)).await.unwrap();
+        socket.send(message_create(2, "first")).await.unwrap();
+        socket.send(message_create(3, "second")).await.unwrap();
+
+        std::future::pending::<()>().await;
+    });
+
+    let mut client = client(&url, |builder| builder).await;
+    let calls = Arc::new(Mutex::new(Vec::new()));
+
+    // the slower handler still runs first
+    let first = Arc::clone(&calls);
+    client.on(Event::MessageCreate, move |data| {
+        let calls = Arc::clone(&first);
+        async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            if let HandlerValue::Message(message) = data {
+                calls
+                    .lock()
+                    .unwrap()
+                    .push(format!("slow {}", message.content));
+            }
+            Ok(())
+        }
+    });
+
+    let second = Arc::clone(&calls);
+    client.on(Event::MessageCreate, move |data| {
+        let calls = Arc::clone(&second);
+        async move {
+            if let HandlerValue::Message(message) = data {
+                calls
+                    .lock()
+                    .unwrap()
+                    .push(format!("fast {}", message.content));
+            }
+            Ok(())
+        }
+    });
+
+    let shutdown = client.shutdown_handle();
+    tokio::spawn(client.login());
+
+    tokio::time::timeout(TIMEOUT, async {
+        while calls.lock().unwrap().len() < 4 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    let calls = calls.lock().unwrap().clone();
+    let order = |content: &str| -> Vec<_> {
+        calls
+            .iter()
+            .filter(|call| call.ends_with(content))
+            .cloned()
+            .collect()
+    };
+    assert_eq!(order("first"), ["slow first", "fast first"]);
+    assert_eq!(order("second"), ["slow second", "fast second"]);
+
+    shutdown.shutdown();
+}
+
+#[tokio::test]
+async fn clients_have_their_own_caches() {
+    let api = json_for(&[(
+        "*",
+        "",
+        r#"{"id":"2","channel_id":"1","content":"api","attachments":[],"components":[]}"#,
+    )])
+    .await;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    let server_url = url.clone();
+    tokio::spawn(async move {
+        let mut socket = accept(&listener).await;
+        socket.send(hello(45000)).await.unwrap();
+        next_payload(&mut socket).await;
+        socket.send(ready("session", &server_url)).await.unwrap();
+        socket.send(message_create(2, "gateway")).await.unwrap();
+
+        std::future::pending::<()>().await;
+    });
+
+    let mut receiving = Client::builder("TOKEN", 0u32, "!")
+        .api_url(api.url())
+        .gateway_url(&url)
+        .default_help(false)
+        .message_cache_size(10)
+        .build()
+        .unwrap();
+    let other = api
+        .client_with(|builder| builder.message_cache_size(10))
+        .await;
+
+    let receiving_http = receiving.http();
+    let mut messages = receiving.event_stream_for(&[Event::MessageCreate]);
+
+    let shutdown = receiving.shutdown_handle();
+    tokio::spawn(receiving.login());
+    tokio::time::timeout(TIMEOUT, messages.next())
+        .await
+        .unwrap()
+        .unwrap();
+
+    let cached = receiving_http.scope(utils::fetch_message("1", "2")).await;
+    assert_eq!(cached.unwrap().content, "gateway");
+    assert!(api.requests().is_empty());
+
+    let fetched = other.http().scope(utils::fetch_message("1", "2")).await;
+    assert_eq!(fetched.unwrap().content, "api");
+    assert_eq!(api.requests().len(), 1);
+
+    shutdown.shutdown();
+}
+
+fn members_chunk(nonce: &str, index: u32, count: u32, user_id: &str) -> Message {
+    Message::Text(
+        json::object! {
+            op: 0,
+            t: "GUILD_MEMBERS_CHUNK",
+            s: null,
+            d: {
+                guild_id: "5",
+                members: [{ user: { id: user_id, username: user_id }, roles: [] }],
+                chunk_index: index,
+                chunk_count: count,
+                nonce: nonce,
+            },
+        }
+        .dump(),
+    )
+}
+
+#[tokio::test]
+async fn member_chunks_are_assembled_by_nonce() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    let server_url = url.clone();
+    tokio::spawn(async move {
+        let mut socket = accept(&listener).await;
+        socket.send(hello(45000)).await.unwrap();
+        next_payload(&mut socket).await;
+        socket.send(ready("session", &server_url)).await.unwrap();
+
+        // the first request is answered in two chunks, with an unrelated chunk in between
+        let request = next_payload(&mut socket).await;
+        assert_eq!(request["op"], 8);
+        assert_eq!(request["d"]["guild_id"], "5");
+        let nonce = request["d"]["nonce"].as_str().unwrap().to_owned();
+
+        for message in [
+            members_chunk(&nonce, 0, 2, "10"),
+            members_chunk("unknown", 0, 1, "99"),
+            members_chunk(&nonce, 1, 2, "11"),
+        ] {
+            socket.send(message).await.unwrap();
+        }
+
+        // the other requests are never answered
+        next_payload(&mut socket).await;
+        next_payload(&mut socket).await;
+        std::future::pending::<()>().await;
+    });
+
+    let mut client = client(&url, |builder| builder).await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    client.on(Event::GuildMembersChunk, move |data| {
+        let tx = tx.clone();
+        async move {
+            if let HandlerValue::GuildMembersChunk(chunk) = data {
+                tx.send((chunk.nonce, chunk.chunk_index)).unwrap();
+            }
+            Ok(())
+        }
+    });
+
+    let gateway = client.gateway_handle();
+    let shutdown = client.shutdown_handle();
+    let login = tokio::spawn(client.login());
+
+    while gateway.shard_count().is_none() {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    let ids = (0..101).map(|id| id.to_string()).collect();
+    let error = gateway
+        .request_guild_members("5", GuildMembersRequest::user_ids(ids))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(error, DescordError::InvalidArgument { .. }),
+        "{error:?}"
+    );
+
+    let request = GuildMembersRequest::user_ids(vec!["10".to_owned(), "11".to_owned()]);
+    let members = tokio::time::timeout(TIMEOUT, gateway.request_guild_members("5", request))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let ids: Vec<_> = members
+        .iter()
+        .map(|member| member.user.as_ref().unwrap().id.as_str())
+        .collect();
+    assert_eq!(ids, ["10", "11"]);
+    assert!(members
+        .iter()
+        .all(|member| member.guild_id.as_deref() == Some("5")));
+
+    let request = GuildMembersRequest::query("a", 10).timeout(Duration::from_millis(100));
+    let error = gateway
+        .request_guild_members("5", request)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, DescordError::Timeout(_)), "{error:?}");
+
+    // handlers see every chunk, including the ones of a request
+    let mut chunks = Vec::new();
+    for _ in 0..3 {
+        chunks.push(
+            tokio::time::timeout(TIMEOUT, rx.recv())
+                .await
+                .unwrap()
+                .unwrap(),
+        );
+    }
+    chunks.sort();
+    assert_eq!(
+        chunks,
+        [
+            (Some("0".to_owned()), 0),
+            (Some("0".to_owned()), 1),
+            (Some("unknown".to_owned()), 0),
+        ]
+    );
+
+    // a request still waiting when the client stops fails right away
+    let pending = tokio::spawn({
+        let gateway = gateway.clone();
+        async move {
+            gateway
+                .request_guild_members("5", GuildMembersRequest::query("b", 10))
+                .await
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    shutdown.shutdown();
+    tokio::time::timeout(TIMEOUT, login)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+
+    let error = tokio::time::timeout(Duration::from_secs(1), pending)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap_err();
+    assert!(matches!(error, DescordError::NotLoggedIn), "{error:?}");
+}
+
+#[tokio::test]
+async fn partial_thread_deletes_are_dispatched() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    let server_url = url.clone();
+    tokio::spawn(async move {
+        let mut socket = accept(&listener).await;
+        socket.send(hello(45000)).await.unwrap();
+        next_payload(&mut socket).await;
+        socket.send(ready("session", &server_url)).await.unwrap();
+
+        // deleted threads only carry these four fields
+        let delete = json::object! {
+            op: 0,
+            t: "THREAD_DELETE",
+            s: 2,
+            d: { id: "42", guild_id: "5", parent_id: "7", type: 11 },
+        };
+        socket.send(Message::Text(delete.dump())).await.unwrap();
+
+        std::future::pending::<()>().await;
+    });
+
+    let mut client = client(&url, |builder| builder).await;
+    let mut threads = client.event_stream_for(&[Event::ThreadDelete]);
+
+    let shutdown = client.shutdown_handle();
+    tokio::spawn(client.login());
+
+    let event = tokio::time::timeout(TIMEOUT, threads.next())
+        .await
+        .unwrap()
+        .unwrap();
+
+    let HandlerValue::Channel(thread) = event.data else {
+        panic!("unexpected event {:?}", event.event);
+    };
+    assert_eq!(thread.id, "42");
+    assert_eq!(thread.guild_id.as_deref(), Some("5"));
+    assert_eq!(thread.parent_id.as_deref(), Some("7"));
+    assert_eq!(thread.channel_type, ChannelType::PublicThread as u32);
+    assert_eq!(thread.mention, "<#42>");
+    assert!(thread.name.is_none());
+
+    shutdown.shutdown();
+}
+
+/// Serves a single message, a second one is only sent once a shutdown was requested.
+/// Returns the close frame sent by the client.
+async fn serve_until_closed(
+    listener: TcpListener,
+    shutdown_requested: oneshot::Receiver<()>,
+) -> Option<CloseFrame<'static>> {
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let mut socket = accept(&listener).await;
+    socket.send(hello(45000)).await.unwrap();
+    next_payload(&mut socket).await;
+    socket.send(ready("session", &url)).await.unwrap();
+    socket.send(message_create(2, "slow")).await.unwrap();
+
+    shutdown_requested.await.unwrap();
+    // the client might be closing the connection already
+    let _ = socket.send(message_create(3, "late")).await;
+
+    while let Some(Ok(message)) = socket.next().await {
+        if let Message::Close(frame) = message {
+            return frame;
+        }
+    }
+
+    None
+}
+
+/// Registers a handler taking `delay` to handle each message, it reports the
+/// messages it started with and sets the flag once it finished.
+fn slow_handler(
+    client: &mut Client,
+    delay: Duration,
+) -> (mpsc::UnboundedReceiver<String>, Arc<AtomicBool>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let finished = Arc::new(AtomicBool::new(false));
+
+    let done = Arc::clone(&finished);
+    client.on(Event::MessageCreate, move |data| {
+        let tx = tx.clone();
+        let done = Arc::clone(&done);
+        async move {
+            if let HandlerValue::Message(message) = data {
+                tx.send(message.content).unwrap();
+                tokio::time::sleep(delay).await;
+                done.store(true, Ordering::SeqCst);
+            }
+            Ok(())
+        }
+    });
+
+    (rx, finished)
+}
+
+#[tokio::test]
+async fn shutdown_waits_for_running_handlers() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let (requested, shutdown_requested) = oneshot::channel();
+    let server = tokio::spawn(serve_until_closed(listener, shutdown_requested));
+
+    let mut client = client(&url, |builder| builder).await;
+    let (mut started, finished) = slow_handler(&mut client, Duration::from_millis(500));
+
+    let shutdown = client.shutdown_handle();
+    let login = tokio::spawn(client.login());
+
+    let content = tokio::time::timeout(TIMEOUT, started.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(content, "slow");
+
+    shutdown.shutdown();
+    requested.send(()).unwrap();
+
+    let frame = tokio::time::timeout(TIMEOUT, server)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(frame.code, CloseCode::Normal);
+
+    // the connection is closed, but the handler is still running
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!login.is_finished());
+
+    tokio::time::timeout(TIMEOUT, login)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert!(finished.load(Ordering::SeqCst));
+
+    // the message received after the shutdown was not dispatched
+    assert_eq!(started.try_recv().ok(), None);
+}
+
+#[tokio::test]
+async fn shutdown_stops_waiting_for_handlers_after_the_timeout() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let (requested, shutdown_requested) = oneshot::channel();
+    tokio::spawn(serve_until_closed(listener, shutdown_requested));
+
+    let mut client = client(&url, |builder| {
+        builder.shutdown_timeout(Duration::from_millis(200))
+    })
+    .await;
+    let (mut started, finished) = slow_handler(&mut client, Duration::from_secs(60));
+
+    let shutdown = client.shutdown_handle();
+    let login = tokio::spawn(client.login());
+
+    tokio::time::timeout(TIMEOUT, started.recv())
+        .await
+        .unwrap()
+        .unwrap();
+
+    let start = std::time::Instant::now();
+    shutdown.shutdown();
+    requested.send(()).unwrap();
+
+    tokio::time::timeout(TIMEOUT, login)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(200));
+    assert!(!finished.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn login_returns_fatal_close_codes() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        let mut socket = accept(&listener).await;
+        socket.send(hello(45000)).await.unwrap();
+        next_payload(&mut socket).await;
+
+        let frame = CloseFrame {
+            code: CloseCode::from(4004),
+            reason: "Authentication failed.".into(),
+        };
+        socket.close(Some(frame)).await.unwrap();
+    });
+
+    let client = client(&url, |builder| builder).await;
+    let error = tokio::time::timeout(TIMEOUT, client.login())
+        .await
+        .unwrap()
+        .unwrap_err();
+
+    assert!(
+        matches!(error, DescordError::GatewayClosed { code: 4004, .. }),
+        "{error:?}"
+    );
+}
+
+#[tokio::test]
+async fn failed_attempts_are_counted_from_one() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    tokio::spawn(async move {
+        loop {
+            let mut socket = accept(&listener).await;
+            socket
+                .send(Message::Text("not a hello".into()))
+                .await
+                .unwrap();
+        }
+    });
+
+    let mut client = client(&url, |builder| {
+        builder.reconnect_policy(ReconnectPolicy {
+            base_delay: Duration::from_millis(10),
+            max_attempts: Some(2),
+            ..Default::default()
+        })
+    })
+    .await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    client.on_error(move |error| {
+        if let DescordError::GatewayReconnect { attempt, .. } = error {
+            tx.send(attempt).unwrap();
+        }
+    });
+
+    let error = tokio::time::timeout(TIMEOUT, client.login())
+        .await
+        .unwrap()
+        .unwrap_err();
+
+    assert!(
+        matches!(error, DescordError::GatewayRetriesExhausted { attempts: 2 }),
+        "{error:?}"
+    );
+    assert_eq!(rx.recv().await, Some(1));
+    assert_eq!(rx.recv().await, Some(2));
+    assert_eq!(rx.try_recv().ok(), None);
+}
+
+#[tokio::test]
+async fn login_returns_initial_connection_errors() {
+    // nothing listens on the discard port
+    let client = client("ws://127.0.0.1:9", |builder| builder).await;
+    let error = tokio::time::timeout(TIMEOUT, client.login())
+        .await
+        .unwrap()
+        .unwrap_err();
+
+    assert!(matches!(error, DescordError::WebSocket(_)), "{error:?}");
+}
+
+#[tokio::test]
+async fn sharded_login_returns_gateway_bot_errors() {
+    let api = MockServer::start(|_| {
+        Response::json(r#"{"message":"401: Unauthorized","code":0}"#).status(401)
+    })
+    .await;
+
+    let client = api
+        .client_with(|builder| builder.shards(ShardCount::Fixed(2)))
+        .await;
+    let error = tokio::time::timeout(TIMEOUT, client.login())
+        .await
+        .unwrap()
+        .unwrap_err();
+
+    assert!(matches!(error, DescordError::Api(_)), "{error:?}");
+    assert_eq!(api.requests()[0].path, "gateway/bot");
+}
+
+#[tokio::test]
+async fn shards_identify_in_buckets_of_max_concurrency() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let gateway_bot = format!(
+        r#"{{"url":"{url}","shards":4,"session_start_limit":{{"total":1000,"remaining":1000,"reset_after":0,"max_concurrency":2}}}}"#
+    );
+    let api = json_for(&[("GET", "gateway/bot", &gateway_bot)]).await;
+
+    let server_url = url.clone();
+    tokio::spawn(async move {
+        loop {
+            let mut socket = accept(&listener).await;
+            let tx = tx.clone();
+            let server_url = server_url.clone();
+
+            tokio::spawn(async move {
+                socket.send(hello(45000)).await.unwrap();
+                let identify = next_payload(&mut socket).await;
+                tx.send((std::time::Instant::now(), identify)).unwrap();
+                socket.send(ready("session", &server_url)).await.unwrap();
+
+                std::future::pending::<()>().await;
+            });
+        }
+    });
+
+    let client = api
+        .client_with(|builder| builder.shards(ShardCount::Auto))
+        .await;
+    let shutdown = client.shutdown_handle();
+    let start = std::time::Instant::now();
+    tokio::spawn(client.login());
+
+    let mut identifies = Vec::new();
+    for _ in 0..4 {
+        let (received, identify) = tokio::time::timeout(TIMEOUT, rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(identify["op"], 2);
+        assert_eq!(identify["d"]["shard"][1], 4);
+
+        let id = identify["d"]["shard"][0].as_u32().unwrap();
+        identifies.push((received - start, id));
+    }
+
+    let mut ids: Vec<_> = identifies.iter().map(|(_, id)| *id).collect();
+    ids.sort();
+    assert_eq!(ids, [0, 1, 2, 3]);
+
+    // one shard of each bucket identifies right away, the other one 5 seconds later
+    let (first, second) = identifies.split_at(2);
+    for wave in [first, second] {
+        assert_ne!(wave[0].1 % 2, wave[1].1 % 2, "{identifies:?}");
+    }
+    assert!(
+        first.iter().all(|(at, _)| *at < Duration::from_secs(1)),
+        "{identifies:?}"
+    );
+    for (at, id) in second {
+        let (previous, _) = first.iter().find(|(_, other)| other % 2 == id % 2).unwrap();
+        assert!(
+            *at - *previous >= Duration::from_millis(4900),
+            "{identifies:?}"
+        );
+    }
+
+    shutdown.shutdown();
+}
+
+#[tokio::test]
+async fn sharded_login_returns_shard_connection_errors() {
+    let api = json_for(&[(
+        "GET",
+        "gateway/bot",
+        r#"{"url":"ws://127.0.0.1:9","shards":2,"session_start_limit":{"total":1000,"remaining":1000,"reset_after":0,"max_concurrency":1}}"#,
+    )])
+    .await;
+
+    let client = api
+        .client_with(|builder| {
+            builder
+                .gateway_url("ws://127.0.0.1:9")
+                .shards(ShardCount::Auto)
+        })
+        .await;
+    let error = tokio::time::timeout(TIMEOUT, client.login())
+        .await
+        .unwrap()
+        .unwrap_err();
+
+    assert!(matches!(error, DescordError::WebSocket(_)), "{error:?}");
+}
+
+#[tokio::test]
+async fn gateway_handles_update_the_presence_from_any_task() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let server_url = url.clone();
+    tokio::spawn(async move {
+        let mut socket = accept(&listener).await;
+        socket.send(hello(45000)).await.unwrap();
+        next_payload(&mut socket).await;
+        socket.send(ready("session", &server_url)).await.unwrap();
+
+        loop {
+            tx.send(next_payload(&mut socket).await).unwrap();
+        }
+    });
+
+    let client = client(&url, |builder| builder).await;
+    let gateway = client.gateway_handle();
+    let shutdown = client.shutdown_handle();
+
+    let error = gateway
+        .update_presence(Presence::new(OnlineStatus::Idle))
+        .unwrap_err();
+    assert!(matches!(error, DescordError::NotLoggedIn), "{error:?}");
+
+    tokio::spawn(client.login());
+    while gateway.shard_count().is_none() {
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    assert_eq!(gateway.shard_count(), Some(1));
+    assert!(gateway
+        .update_shard_presence(1, Presence::new(OnlineStatus::Idle))
+        .is_err());
+
+    let presence = Presence::new(OnlineStatus::DoNotDisturb).activity(Activity::watching("logs"));
+    gateway.update_presence(presence).unwrap();
+
+    let update = tokio::time::timeout(TIMEOUT, rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(update["op"], 3);
+    assert_eq!(update["d"]["status"], "dnd");
+    assert_eq!(update["d"]["activities"][0]["name"], "logs");
+
+    gateway
+        .update_shard_presence(0, Presence::new(OnlineStatus::Idle))
+        .unwrap();
+
+    let update = tokio::time::timeout(TIMEOUT, rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(update["d"]["status"], "idle");
+
+    shutdown.shutdown();
+}
+
+#[tokio::test]
+async fn gateway_handles_report_the_latency_of_each_shard() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    let server_url = url.clone();
+    tokio::spawn(async move {
+        let mut socket = accept(&listener).await;
+        socket.send(hello(50)).await.unwrap();
+        next_payload(&mut socket).await;
+        socket.send(ready("session", &server_url)).await.unwrap();
+
+        // acknowledges every heartbeat
+        while let Some(Ok(message)) = socket.next().await {
+            let Message::Text(text) = message else {
+                continue;
+            };
+
+            if json::parse(&text).unwrap()["op"] == 1 {
+                let ack = json::object! { op: 11 }.dump();
+                socket.send(Message::Text(ack)).await.unwrap();
+            }
+        }
+    });
+
+    let client = client(&url, |builder| builder).await;
+    let gateway = client.gateway_handle();
+    let shutdown = client.shutdown_handle();
+    assert_eq!(gateway.latency(0), None);
+
+    tokio::spawn(client.login());
+
+    // read from a task outside of any handler
+    let latency = tokio::spawn(async move {
+        loop {
+            if let Some(latency) = gateway.latency(0) {
+                return (latency, gateway.latency(1));
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    });
+
+    let (latency, other_shard) = tokio::time::timeout(TIMEOUT, latency)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(latency < TIMEOUT);
+    assert_eq!(other_shard, None);
+
+    shutdown.shutdown();
+}
+
+/// The compression context of one connection, the gateway keeps one for every connection.
+struct ZlibStream(Compress);
+
+impl ZlibStream {
+    fn new() -> Self {
+        Self(Compress::new(Compression::default(), true))
+    }
+
+    /// Compresses a payload into three frames, the last one splits the `00 00 ff ff` suffix.
+    fn frames(&mut self, message: Message) -> Vec<Message> {
+        let Message::Text(text) = message else {
+            panic!("only text payloads are compressed");
+        };
+
+        let mut output = Vec::with_capacity(text.len() + 128);
+        self.0
+            .compress_vec(text.as_bytes(), &mut output, FlushCompress::Sync)
+            .unwrap();
+        assert!(output.ends_with(&[0x00, 0x00, 0xff, 0xff]));
+
+        let (middle, end) = (output.len() / 2, output.len() - 2);
+        [&output[..middle], &output[middle..end], &output[end..]]
+            .into_iter()
+            .map(|frame| Message::Binary(frame.to_vec()))
+            .collect()
+    }
+
+    async fn send(&mut self, socket: &mut Socket, message: Message) {
+        for frame in self.frames(message) {
+            socket.send(frame).await.unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn zlib_stream_frames_are_reassembled() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let server_url = url.clone();
+    tokio::spawn(async move {
+        let (mut first, uri) = accept_with_uri(&listener).await;
+        tx.send(uri).unwrap();
+
+        let mut zlib = ZlibStream::new();
+        zlib.send(&mut first, hello(45000)).await;
+        next_payload(&mut first).await;
+        zlib.send(&mut first, ready("session", &server_url)).await;
+
+        // the second message refers back to the first one, it can only be
+        // inflated with the context the first one was inflated with
+        zlib.send(&mut first, message_create(2, "compressed")).await;
+        zlib.send(&mut first, message_create(3, "compressed")).await;
+
+        let frame = CloseFrame {
+            code: CloseCode::from(4000),
+            reason: "Unknown error".into(),
+        };
+        first.close(Some(frame)).await.unwrap();
+
+        // the resumed connection is a new zlib stream
+        let (mut second, uri) = accept_with_uri(&listener).await;
+        tx.send(uri).unwrap();
+
+        let mut zlib = ZlibStream::new();
+        zlib.send(&mut second, hello(45000)).await;
+        let resume = next_payload(&mut second).await;
+        assert_eq!(resume["op"], 6);
+        zlib.send(&mut second, message_create(4, "resumed")).await;
+
+        std::future::pending::<()>().await;
+    });
+
+    let mut client = client(&format!("{url}/?v=10&encoding=json"), |builder| {
+        builder.compression(true)
+    })
+    .await;
+    let mut messages = client.event_stream_for(&[Event::MessageCreate]);
+
+    let shutdown = client.shutdown_handle();
+    tokio::spawn(client.login());
+
+    for content in ["compressed", "compressed", "resumed"] {
+        let event = tokio::time::timeout(TIMEOUT, messages.next())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let HandlerValue::Message(message) = event.data else {
+            panic!("unexpected event {:?}", event.event);
+        };
+        assert_eq!(message.content, content);
+    }
+
+    for _ in 0..2 {
+        let uri = rx.recv().await.unwrap();
+        assert!(uri.ends_with("&compress=zlib-stream"), "{uri}");
+    }
+
+    shutdown.shutdown();
+}
@@ -0,0 +1,205 @@
+//! Tests of the channel history and purges against a local stand-in for the rest api.
+
+mod common;
+
+use std::time::Duration;
+
+use common::{json_for, MockServer, Response};
+use descord::prelude::*;
+use descord::utils::{self, Purge};
+use futures_util::StreamExt;
+
+fn message(id: &str, author_id: &str) -> json::JsonValue {
+    json::object! {
+        id: id,
+        channel_id: "1",
+        content: "hi",
+        author: { id: author_id, username: author_id },
+        attachments: [],
+        components: [],
+    }
+}
+
+/// A page of messages with the given ids, newest first.
+fn page(ids: impl Iterator<Item = u64>) -> Response {
+    let messages: Vec<_> = ids.map(|id| message(&id.to_string(), "2")).collect();
+    Response::json(json::JsonValue::from(messages).dump())
+}
+
+/// The id of a message sent `age` ago.
+fn sent_ago(age: Duration) -> String {
+    utils::snowflake_from_timestamp(chrono::Utc::now() - age)
+}
+
+#[tokio::test]
+async fn history_pages_until_a_short_page() {
+    let server = MockServer::start(|request| {
+        if request.path.contains("before=") {
+            page((871..=900).rev())
+        } else {
+            page((901..=1000).rev())
+        }
+    })
+    .await;
+    let client = server.client().await;
+
+    let messages: Vec<_> = client
+        .http()
+        .scope(utils::channel_history("1", None).collect::<Vec<_>>())
+        .await;
+
+    let ids: Vec<u64> = messages
+        .into_iter()
+        .map(|message| message.unwrap().id.parse().unwrap())
+        .collect();
+    assert_eq!(ids, (871..=1000).rev().collect::<Vec<_>>());
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].path, "channels/1/messages?limit=100");
+    assert_eq!(requests[1].path, "channels/1/messages?limit=100&before=901");
+}
+
+#[tokio::test]
+async fn history_ends_after_an_error() {
+    let server = MockServer::start(|request| {
+        if request.path.contains("before=901") {
+            Response::json(r#"{"message":"Missing Access","code":50001}"#).status(403)
+        } else {
+            page((901..=1000).rev())
+        }
+    })
+    .await;
+    let client = server.client().await;
+
+    let messages: Vec<_> = client
+        .http()
+        .scope(utils::channel_history("1", Some("5000")).collect::<Vec<_>>())
+        .await;
+
+    assert_eq!(messages.len(), 101);
+    assert!(messages[..100].iter().all(Result::is_ok));
+    assert!(matches!(messages[100], Err(DescordError::Api(_))));
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[0].path.ends_with("&before=5000"));
+}
+
+/// Answers the history with `messages` and every deletion with no content.
+async fn purge_server(messages: Vec<json::JsonValue>) -> MockServer {
+    let messages = json::JsonValue::from(messages).dump();
+    json_for(&[("GET", "", &messages)]).await
+}
+
+#[tokio::test]
+async fn purges_delete_old_messages_one_by_one() {
+    let minute = Duration::from_secs(60);
+    let day = 24 * 60 * minute;
+
+    let recent = sent_ago(minute);
+    let old = sent_ago(20 * day);
+    let older = sent_ago(21 * day);
+    let server = purge_server(vec![
+        message(&recent, "2"),
+        message(&sent_ago(2 * minute), "3"),
+        message(&old, "2"),
+        message(&older, "2"),
+    ])
+    .await;
+    let client = server.client().await;
+
+    let deleted = client
+        .http()
+        .scope(utils::purge_messages("1", Purge::new(100).author("2")))
+        .await
+        .unwrap();
+    assert_eq!(deleted, 3);
+
+    // a single recent message can't be bulk deleted
+    let requests: Vec<_> = server.requests()[1..]
+        .iter()
+        .map(|request| format!("{} {}", request.method, request.path))
+        .collect();
+    assert_eq!(
+        requests,
+        [
+            format!("DELETE channels/1/messages/{recent}"),
+            format!("DELETE channels/1/messages/{old}"),
+            format!("DELETE channels/1/messages/{older}"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn purges_stop_at_newer_than() {
+    let minute = Duration::from_secs(60);
+
+    let first = sent_ago(minute);
+    let second = sent_ago(2 * minute);
+    let server = purge_server(vec![
+        message(&first, "2"),
+        message(&second, "3"),
+        message(&sent_ago(2 * 60 * minute), "2"),
+        message(&sent_ago(30 * minute), "2"),
+    ])
+    .await;
+    let client = server.client().await;
+
+    let purge = Purge::new(100).newer_than(60 * minute).reason("Spam");
+    let deleted = client
+        .http()
+        .scope(utils::purge_messages("1", purge))
+        .await
+        .unwrap();
+    assert_eq!(deleted, 2);
+
+    // the message after the first older one is left alone, even though it's newer
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[1].method, "POST");
+    assert_eq!(requests[1].path, "channels/1/messages/bulk-delete");
+    assert_eq!(requests[1].header("x-audit-log-reason"), Some("Spam"));
+    assert_eq!(
+        requests[1].json()["messages"],
+        json::array![first.as_str(), second.as_str()]
+    );
+}
+
+#[tokio::test]
+async fn purges_delete_messages_near_the_bulk_limit_one_by_one() {
+    let minute = Duration::from_secs(60);
+    let day = 24 * 60 * minute;
+
+    let first = sent_ago(minute);
+    let second = sent_ago(2 * minute);
+    let almost_old = sent_ago(14 * day - 2 * minute);
+    let server = purge_server(vec![
+        message(&first, "2"),
+        message("not-a-snowflake", "2"),
+        message(&second, "2"),
+        message(&almost_old, "2"),
+    ])
+    .await;
+    let client = server.client().await;
+
+    let deleted = client
+        .http()
+        .scope(utils::purge_messages("1", Purge::new(100)))
+        .await
+        .unwrap();
+    assert_eq!(deleted, 4);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 4);
+    assert_eq!(requests[1].path, "channels/1/messages/bulk-delete");
+    assert_eq!(
+        requests[1].json()["messages"],
+        json::array![first.as_str(), second.as_str()]
+    );
+    assert_eq!(requests[2].path, "channels/1/messages/not-a-snowflake");
+    assert_eq!(
+        requests[3].path,
+        format!("channels/1/messages/{almost_old}")
+    );
+}
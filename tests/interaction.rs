@@ -0,0 +1,48 @@
+//! Tests of interaction responses against a local stand-in for the rest api.
+
+mod common;
+
+use common::json_for;
+use descord::models::attachment::AttachmentPayload;
+use descord::prelude::*;
+use nanoserde::DeJson;
+
+const MESSAGE: &str =
+    r#"{"id":"9","channel_id":"1","content":"done","attachments":[],"components":[]}"#;
+
+fn interaction() -> Interaction {
+    Interaction::deserialize_json(
+        r#"{"id":"1","application_id":"2","type":2,"token":"tok","app_permissions":"0","guild_id":"3"}"#,
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn followups_are_sent_with_their_attachments() {
+    let server = json_for(&[("*", "", MESSAGE)]).await;
+    let client = server.client().await;
+
+    let followup = CreateMessageData {
+        content: "done".to_owned(),
+        attachments: vec![AttachmentPayload::from_bytes("log.txt", "hello")],
+        ..Default::default()
+    };
+
+    client
+        .http()
+        .scope(async { interaction().followup(followup).await.unwrap() })
+        .await;
+
+    let request = &server.requests()[0];
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.path, "webhooks/2/tok");
+    assert!(request
+        .header("content-type")
+        .unwrap()
+        .starts_with("multipart/form-data"));
+
+    let body = request.body_str();
+    assert!(body.contains(r#""content":"done""#), "{body}");
+    assert!(body.contains(r#""filename":"log.txt""#), "{body}");
+    assert!(body.contains("hello"), "{body}");
+}
@@ -0,0 +1,228 @@
+//! Tests of the rate limiter against a local stand-in for the rest api.
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use common::{MockServer, Response};
+use descord::prelude::*;
+use descord::utils;
+
+/// Rate limits are 300ms long, a request counts as delayed after 250ms.
+const LIMIT: f64 = 0.3;
+const DELAYED: Duration = Duration::from_millis(250);
+
+const MESSAGE: &str =
+    r#"{"id":"9","channel_id":"1","content":"hi","attachments":[],"components":[]}"#;
+
+/// Records the kind of every rate limit a request waited for.
+struct Recorder(Arc<Mutex<Vec<RateLimitKind>>>);
+
+impl RestObserver for Recorder {
+    fn on_rate_limit(&self, event: &RateLimitEvent) {
+        self.0.lock().unwrap().push(event.kind);
+    }
+}
+
+async fn client(server: &MockServer) -> (Client, Arc<Mutex<Vec<RateLimitKind>>>) {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let client = Client::builder("TOKEN", 0u32, "!")
+        .api_url(server.url())
+        .default_help(false)
+        .rest_observer(Recorder(Arc::clone(&events)))
+        .build()
+        .unwrap();
+
+    (client, events)
+}
+
+fn body(path: &str) -> Response {
+    if path.contains("/messages?") {
+        Response::json("[]")
+    } else {
+        Response::json(MESSAGE)
+    }
+}
+
+fn rate_limited(scope: &str) -> Response {
+    Response::json(format!(
+        r#"{{"message":"You are being rate limited.","retry_after":{LIMIT},"global":{}}}"#,
+        scope == "global"
+    ))
+    .status(429)
+    .header("retry-after", LIMIT)
+    .header("x-ratelimit-scope", scope)
+}
+
+#[tokio::test]
+async fn reset_after_is_waited_for() {
+    let server = MockServer::start(|request| {
+        body(&request.path)
+            .header("x-ratelimit-bucket", "messages")
+            .header("x-ratelimit-remaining", 0)
+            .header("x-ratelimit-reset-after", LIMIT)
+    })
+    .await;
+
+    let (client, events) = client(&server).await;
+    client
+        .http()
+        .scope(async {
+            utils::get_channel_messages("1", None, None).await.unwrap();
+            utils::get_channel_messages("1", None, None).await.unwrap();
+        })
+        .await;
+
+    let requests = server.requests();
+    assert!(requests[1].received - requests[0].received >= DELAYED);
+    assert_eq!(*events.lock().unwrap(), [RateLimitKind::Bucket]);
+}
+
+#[tokio::test]
+async fn routes_with_the_same_hash_share_a_bucket() {
+    let listed = AtomicUsize::new(0);
+    let server = MockServer::start(move |request| {
+        // the second list request exhausts the bucket
+        let remaining = if request.path.contains("/messages?") {
+            1 - listed.fetch_add(1, Ordering::SeqCst).min(1)
+        } else {
+            5
+        };
+
+        body(&request.path)
+            .header("x-ratelimit-bucket", "messages")
+            .header("x-ratelimit-remaining", remaining)
+            .header("x-ratelimit-reset-after", LIMIT)
+    })
+    .await;
+
+    let (client, events) = client(&server).await;
+    client
+        .http()
+        .scope(async {
+            utils::get_channel_messages("1", None, None).await.unwrap();
+            utils::get_channel_messages("1", None, None).await.unwrap();
+            // the hash of the message route isn't known yet
+            utils::fetch_message("1", "5").await.unwrap();
+            // now it is, and it maps to the exhausted bucket
+            utils::fetch_message("1", "6").await.unwrap();
+        })
+        .await;
+
+    let requests = server.requests();
+    assert!(requests[2].received - requests[1].received < DELAYED);
+    assert!(requests[3].received - requests[1].received >= DELAYED);
+    assert_eq!(*events.lock().unwrap(), [RateLimitKind::Bucket]);
+}
+
+#[tokio::test]
+async fn global_rate_limit_blocks_every_route() {
+    let calls = AtomicUsize::new(0);
+    let server = MockServer::start(move |request| {
+        if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+            return rate_limited("global").header("x-ratelimit-global", true);
+        }
+
+        body(&request.path)
+    })
+    .await;
+
+    let (client, events) = client(&server).await;
+    let requests = || server.requests();
+
+    client
+        .http()
+        .scope(async {
+            tokio::join!(
+                async { utils::get_channel_messages("1", None, None).await.unwrap() },
+                async {
+                    // another major parameter, sent once the first request was rate limited
+                    while requests().is_empty() {
+                        tokio::time::sleep(Duration::from_millis(5)).await;
+                    }
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+
+                    utils::get_channel_messages("2", None, None).await.unwrap()
+                },
+            )
+        })
+        .await;
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    for request in &requests[1..] {
+        assert!(request.received - requests[0].received >= DELAYED);
+    }
+
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|kind| *kind == RateLimitKind::Global));
+}
+
+#[tokio::test]
+async fn shared_rate_limits_leave_the_bucket_alone() {
+    let calls = AtomicUsize::new(0);
+    let server = MockServer::start(move |request| {
+        let response = match calls.fetch_add(1, Ordering::SeqCst) {
+            0 => rate_limited("shared"),
+            _ => body(&request.path),
+        };
+
+        response
+            .header("x-ratelimit-bucket", "messages")
+            .header("x-ratelimit-remaining", 5)
+            .header("x-ratelimit-reset-after", 10)
+    })
+    .await;
+
+    let (client, events) = client(&server).await;
+    client
+        .http()
+        .scope(async {
+            utils::get_channel_messages("1", None, None).await.unwrap();
+            utils::get_channel_messages("1", None, None).await.unwrap();
+        })
+        .await;
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    // the rate limited request is retried after `retry-after`
+    assert!(requests[1].received - requests[0].received >= DELAYED);
+    // the bucket still has requests left
+    assert!(requests[2].received - requests[1].received < DELAYED);
+    assert_eq!(*events.lock().unwrap(), [RateLimitKind::Shared]);
+}
+
+#[tokio::test]
+async fn major_parameters_get_separate_buckets() {
+    let server = MockServer::start(|request| {
+        let remaining = if request.path.starts_with("channels/1/") {
+            0
+        } else {
+            5
+        };
+
+        // both channels report the same hash
+        body(&request.path)
+            .header("x-ratelimit-bucket", "messages")
+            .header("x-ratelimit-remaining", remaining)
+            .header("x-ratelimit-reset-after", 10)
+    })
+    .await;
+
+    let (client, events) = client(&server).await;
+    client
+        .http()
+        .scope(async {
+            utils::get_channel_messages("1", None, None).await.unwrap();
+            utils::get_channel_messages("2", None, None).await.unwrap();
+            utils::get_channel_messages("2", None, None).await.unwrap();
+        })
+        .await;
+
+    let requests = server.requests();
+    assert!(requests[2].received - requests[0].received < DELAYED);
+    assert!(events.lock().unwrap().is_empty());
+}
@@ -0,0 +1,122 @@
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use common::{MockServer, Response};
+use descord::prelude::*;
+use descord::utils;
+
+const TOKEN: &str = "s3cr3t-webhook-token";
+const MESSAGE: &str =
+    r#"{"id":"9","channel_id":"1","content":"hi","attachments":[],"components":[]}"#;
+
+/// Records every event as its route followed by its debug representation.
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+impl RestObserver for Recorder {
+    fn on_request(&self, event: &RequestEvent) {
+        let event = format!("{} {:?}", event.route, event);
+        self.0.lock().unwrap().push(event);
+    }
+
+    fn on_rate_limit(&self, event: &RateLimitEvent) {
+        let event = format!("{} {:?}", event.route, event);
+        self.0.lock().unwrap().push(event);
+    }
+}
+
+#[tokio::test]
+async fn observers_never_see_tokens() {
+    let calls = AtomicUsize::new(0);
+    let server = MockServer::start(move |request| {
+        // the first request is rate limited, so the observer also gets a rate limit event
+        if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+            return Response::json(
+                r#"{"message":"You are being rate limited.","retry_after":0.05,"global":false}"#,
+            )
+            .status(429)
+            .header("retry-after", 0.05)
+            .header("x-ratelimit-bucket", "abc")
+            .header("x-ratelimit-remaining", 0)
+            .header("x-ratelimit-reset-after", 0.05);
+        }
+
+        match request.method.as_str() {
+            "DELETE" => Response::empty(),
+            _ => Response::json(MESSAGE),
+        }
+    })
+    .await;
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let client = Client::builder("TOKEN", 0u32, "!")
+        .api_url(server.url())
+        .rest_observer(Recorder(Arc::clone(&events)))
+        .build()
+        .unwrap();
+
+    client
+        .http()
+        .scope(async {
+            let message = ExecuteWebhook::new("hi").wait(true).thread_id("7");
+            utils::execute_webhook("123", TOKEN, message).await.unwrap();
+            utils::edit_webhook_message("123", TOKEN, "9", "edited", None)
+                .await
+                .unwrap();
+            utils::fetch_webhook_message("123", TOKEN, "@original", None)
+                .await
+                .unwrap();
+            utils::delete_webhook_message("123", TOKEN, "9", Some("7"))
+                .await
+                .unwrap();
+        })
+        .await;
+
+    // the token did reach the api
+    assert!(server.requests().iter().all(|r| r.path.contains(TOKEN)));
+
+    let events = events.lock().unwrap();
+    assert!(events.iter().any(|event| event.contains("RateLimitEvent")));
+    assert_eq!(
+        events
+            .iter()
+            .filter(|event| event.contains("RequestEvent"))
+            .count(),
+        5
+    );
+
+    for event in events.iter() {
+        assert!(
+            !event.contains(TOKEN),
+            "token reported to observer: {event}"
+        );
+        assert!(event.starts_with("webhooks/123/:token"), "{event}");
+    }
+}
+
+#[tokio::test]
+async fn transport_errors_never_show_tokens() {
+    // nothing listens on the discard port
+    let client = Client::builder("TOKEN", 0u32, "!")
+        .api_url("http://127.0.0.1:9/api/v10")
+        .retry_policy(RetryPolicy::disabled())
+        .connect_timeout(Duration::from_secs(1))
+        .build()
+        .unwrap();
+
+    let error = client
+        .http()
+        .scope(utils::execute_webhook(
+            "123",
+            TOKEN,
+            ExecuteWebhook::new("hi"),
+        ))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, DescordError::Http(_)));
+    assert!(!error.to_string().contains(TOKEN), "{error}");
+    assert!(!format!("{error:?}").contains(TOKEN), "{error:?}");
+}
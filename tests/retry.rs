@@ -0,0 +1,147 @@
+//! Tests of the retry policy against a local stand-in for the rest api.
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use common::{MockServer, Response};
+use descord::prelude::*;
+use descord::utils;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpListener;
+
+const MESSAGE: &str =
+    r#"{"id":"9","channel_id":"1","content":"hi","attachments":[],"components":[]}"#;
+
+/// Retries wait 100ms to 200ms, jitter makes a retry wait at least half its delay.
+const BASE_DELAY: Duration = Duration::from_millis(100);
+
+fn policy(max_attempts: u32, retry_non_idempotent: bool) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        base_delay: BASE_DELAY,
+        max_delay: BASE_DELAY * 2,
+        retry_non_idempotent,
+    }
+}
+
+async fn client(api_url: &str, policy: RetryPolicy) -> Client {
+    Client::builder("TOKEN", 0u32, "!")
+        .api_url(api_url)
+        .default_help(false)
+        .retry_policy(policy)
+        .build()
+        .unwrap()
+}
+
+/// Answers the first `failures` requests with a server error.
+async fn failing_server(failures: usize) -> MockServer {
+    let calls = AtomicUsize::new(0);
+    MockServer::start(move |_| {
+        if calls.fetch_add(1, Ordering::SeqCst) < failures {
+            Response::json(r#"{"message":"Internal Server Error","code":0}"#).status(500)
+        } else {
+            Response::json(MESSAGE)
+        }
+    })
+    .await
+}
+
+#[tokio::test]
+async fn server_errors_are_retried() {
+    let server = failing_server(2).await;
+    let client = client(server.url(), policy(3, false)).await;
+
+    let message = client
+        .http()
+        .scope(utils::fetch_message("1", "9"))
+        .await
+        .unwrap();
+    assert_eq!(message.id, "9");
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[1].received - requests[0].received >= BASE_DELAY / 2);
+    // the second retry waits up to twice as long as the first
+    assert!(requests[2].received - requests[1].received >= BASE_DELAY);
+}
+
+#[tokio::test]
+async fn retries_stop_after_max_attempts() {
+    let server = failing_server(usize::MAX).await;
+    let client = client(server.url(), policy(4, false)).await;
+
+    let error = client
+        .http()
+        .scope(utils::fetch_message("1", "9"))
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(&error, DescordError::Api(error) if error.status == 500),
+        "{error:?}"
+    );
+    assert_eq!(server.requests().len(), 4);
+}
+
+#[tokio::test]
+async fn posts_are_not_retried_by_default() {
+    let server = failing_server(1).await;
+    let client = client(server.url(), policy(3, false)).await;
+
+    let error = client
+        .http()
+        .scope(utils::send("1", None, "hi"))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, DescordError::Api(_)), "{error:?}");
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn posts_are_retried_when_opted_in() {
+    let server = failing_server(1).await;
+    let client = client(server.url(), policy(3, true)).await;
+
+    client
+        .http()
+        .scope(utils::send("1", None, "hi"))
+        .await
+        .unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests.iter().all(|request| request.method == "POST"));
+}
+
+#[tokio::test]
+async fn reset_connections_are_retried() {
+    // resets every connection once the request was received
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/api/v10", listener.local_addr().unwrap());
+    let connections = Arc::new(AtomicUsize::new(0));
+
+    let accepted = Arc::clone(&connections);
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            accepted.fetch_add(1, Ordering::SeqCst);
+
+            let _ = stream.read(&mut [0; 1024]).await;
+            stream.set_linger(Some(Duration::ZERO)).unwrap();
+        }
+    });
+
+    let client = client(&url, policy(3, false)).await;
+    let error = client
+        .http()
+        .scope(utils::fetch_message("1", "9"))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, DescordError::Http(_)), "{error:?}");
+    assert_eq!(connections.load(Ordering::SeqCst), 3);
+}
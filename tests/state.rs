@@ -0,0 +1,163 @@
+//! Tests of the state passed to handlers through `State<T>` parameters.
+
+mod common;
+
+use common::gateway::*;
+use common::json_for;
+use descord::prelude::*;
+use futures_util::SinkExt;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+
+/// Records which handler ran, with the arguments it received.
+#[derive(Debug)]
+struct Recorder(mpsc::UnboundedSender<String>);
+
+/// Never added to the client.
+struct Missing;
+
+#[descord::command]
+async fn add(_msg: Message, a: isize, recorder: State<Recorder>, b: isize) {
+    let _ = recorder.0.send(format!("add {a} {b}"));
+}
+
+#[descord::command]
+async fn missing(_msg: Message, _missing: State<Missing>) {}
+
+#[descord::event(message_create)]
+async fn on_message(msg: Message, recorder: State<Recorder>) {
+    let _ = recorder.0.send(format!("event {}", msg.content));
+}
+
+#[descord::component(id = "button")]
+async fn button(interaction: Interaction, recorder: State<Recorder>) {
+    let _ = recorder.0.send(format!("component {}", interaction.id));
+}
+
+#[descord::slash]
+async fn greet(_interaction: Interaction, recorder: State<Recorder>, name: String) {
+    let _ = recorder.0.send(format!("slash {name}"));
+}
+
+fn interaction_create(seq: u64, data: json::JsonValue) -> WsMessage {
+    let kind = if data["custom_id"].is_null() { 2 } else { 3 };
+    WsMessage::Text(
+        json::object! {
+            op: 0,
+            t: "INTERACTION_CREATE",
+            s: seq,
+            d: {
+                id: seq.to_string(),
+                application_id: "1",
+                type: kind,
+                token: "token",
+                app_permissions: "0",
+                guild_id: "5",
+                data: data,
+            },
+        }
+        .dump(),
+    )
+}
+
+#[tokio::test]
+async fn handlers_of_every_kind_receive_the_state() {
+    let api = json_for(&[
+        (
+            "GET",
+            "users/@me",
+            r#"{"id":"1","username":"bot","discriminator":"0"}"#,
+        ),
+        ("GET", "applications/1/commands", "[]"),
+        ("POST", "applications/1/commands", r#"{"id":"100"}"#),
+    ])
+    .await;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}", listener.local_addr().unwrap());
+
+    let server_url = url.clone();
+    tokio::spawn(async move {
+        let mut socket = accept(&listener).await;
+        socket.send(hello(45000)).await.unwrap();
+        next_payload(&mut socket).await;
+        socket.send(ready("session", &server_url)).await.unwrap();
+
+        for message in [
+            message_create(2, "!add 2 3"),
+            message_create(3, "hello"),
+            message_create(4, "!missing"),
+            interaction_create(5, json::object! { custom_id: "button", component_type: 2 }),
+            interaction_create(
+                6,
+                json::object! {
+                    id: "100",
+                    name: "greet",
+                    type: 1,
+                    options: [{ name: "name", type: 3, value: "bob" }],
+                },
+            ),
+        ] {
+            socket.send(message).await.unwrap();
+        }
+
+        std::future::pending::<()>().await;
+    });
+
+    let mut client = Client::builder("TOKEN", 0u32, "!")
+        .api_url(api.url())
+        .gateway_url(&url)
+        .default_help(false)
+        .build()
+        .unwrap();
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    client.add_state(Recorder(tx.clone()));
+    client.on_error(move |error| {
+        if let DescordError::CommandHandler { command, source } = error {
+            if let Some(DescordError::MissingState(name)) = source.downcast_ref() {
+                let _ = tx.send(format!("{command} is missing {name}"));
+            }
+        }
+    });
+
+    client.register_commands(vec![add(), missing()]);
+    client.register_events(vec![on_message()]);
+    client.register_component_callbacks(vec![button()]);
+    client.register_slash_commands(vec![greet()]).await;
+
+    let shutdown = client.shutdown_handle();
+    tokio::spawn(client.login());
+
+    let mut received = Vec::new();
+    for _ in 0..5 {
+        received.push(
+            tokio::time::timeout(TIMEOUT, rx.recv())
+                .await
+                .unwrap()
+                .unwrap(),
+        );
+    }
+    received.sort();
+
+    // the state parameter doesn't shift the positional arguments of `add`
+    assert_eq!(
+        received,
+        [
+            "!missing is missing state::Missing",
+            "add 2 3",
+            "component 5",
+            "event hello",
+            "slash bob",
+        ]
+    );
+
+    shutdown.shutdown();
+}
+
+#[test]
+fn state_is_only_available_inside_handlers() {
+    let error = State::<Recorder>::get().unwrap_err();
+    assert!(matches!(error, DescordError::NotInHandler), "{error:?}");
+}
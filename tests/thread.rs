@@ -0,0 +1,75 @@
+//! Tests of threads and forum posts against a local stand-in for the rest api.
+
+mod common;
+
+use common::json_for;
+use descord::models::attachment::AttachmentPayload;
+use descord::prelude::*;
+use descord::utils;
+
+const POST: &str = r#"{"id":"42","type":11,"guild_id":"5","parent_id":"7","name":"Crash on startup","applied_tags":["3"]}"#;
+
+#[tokio::test]
+async fn forum_posts_are_created_with_their_tags_and_message() {
+    let server = json_for(&[("*", "", POST)]).await;
+    let client = server.client().await;
+
+    let data = CreateForumPost::new("Crash on startup", "Steps to reproduce: start it")
+        .applied_tags(vec!["3".to_owned()])
+        .auto_archive_duration(1440)
+        .reason("Bug report");
+
+    let post = client
+        .http()
+        .scope(utils::create_forum_post("7", data))
+        .await
+        .unwrap();
+    assert_eq!(post.id, "42");
+    assert_eq!(post.mention, "<#42>");
+    assert_eq!(post.applied_tags, Some(vec!["3".to_owned()]));
+
+    let request = &server.requests()[0];
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.path, "channels/7/threads");
+    assert_eq!(request.header("x-audit-log-reason"), Some("Bug%20report"));
+
+    let body = request.json();
+    assert_eq!(body["name"], "Crash on startup");
+    assert_eq!(body["applied_tags"], json::array!["3"]);
+    assert_eq!(body["auto_archive_duration"], 1440);
+    assert_eq!(body["message"]["content"], "Steps to reproduce: start it");
+}
+
+#[tokio::test]
+async fn forum_posts_send_their_attachments() {
+    let server = json_for(&[("*", "", POST)]).await;
+    let client = server.client().await;
+
+    let message = CreateMessageData {
+        content: "Log attached".to_owned(),
+        attachments: vec![AttachmentPayload::from_bytes(
+            "crash.log",
+            "panicked at main",
+        )],
+        ..Default::default()
+    };
+    let data = CreateForumPost::new("Crash on startup", message).applied_tags(vec!["3".to_owned()]);
+
+    client
+        .http()
+        .scope(utils::create_forum_post("7", data))
+        .await
+        .unwrap();
+
+    let request = &server.requests()[0];
+    assert!(request
+        .header("content-type")
+        .unwrap()
+        .starts_with("multipart/form-data"));
+
+    let body = request.body_str();
+    assert!(body.contains(r#""applied_tags":["3"]"#), "{body}");
+    assert!(body.contains(r#""content":"Log attached""#), "{body}");
+    assert!(body.contains(r#""filename":"crash.log""#), "{body}");
+    assert!(body.contains("panicked at main"), "{body}");
+}
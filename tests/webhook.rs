@@ -0,0 +1,146 @@
+//! Tests of webhook requests against a local stand-in for the rest api.
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::{json_for, MockServer, Route};
+use descord::prelude::*;
+use descord::utils;
+use nanoserde::DeJson;
+
+const TOKEN: &str = "s3cr3t-webhook-token";
+const MESSAGE: &str =
+    r#"{"id":"9","channel_id":"1","content":"hi","attachments":[],"components":[]}"#;
+
+/// Records the route of every request.
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+impl RestObserver for Recorder {
+    fn on_request(&self, event: &RequestEvent) {
+        let route = format!("{} {}", event.method, event.route);
+        self.0.lock().unwrap().push(route);
+    }
+}
+
+const ROUTES: &[Route] = &[
+    ("DELETE", "", ""),
+    ("POST", "webhooks/123/s3cr3t-webhook-token?wait=false", ""),
+    ("*", "", MESSAGE),
+];
+
+fn webhook(server: &MockServer) -> WebhookClient {
+    WebhookClient::from_url(&format!("{}/webhooks/123/{TOKEN}", server.url())).unwrap()
+}
+
+#[tokio::test]
+async fn execute_urls_carry_wait_and_the_thread_id() {
+    let server = json_for(ROUTES).await;
+    let webhook = webhook(&server);
+
+    let sent = webhook.execute(ExecuteWebhook::new("hi")).await.unwrap();
+    assert!(sent.is_none());
+
+    let message = ExecuteWebhook::new("hi")
+        .username("Backups")
+        .wait(true)
+        .thread_id("7");
+    let sent = webhook.execute(message).await.unwrap().unwrap();
+    assert_eq!(sent.id, "9");
+
+    let requests = server.requests();
+    assert_eq!(requests[0].method, "POST");
+    assert_eq!(requests[0].path, format!("webhooks/123/{TOKEN}?wait=false"));
+    assert_eq!(
+        requests[1].path,
+        format!("webhooks/123/{TOKEN}?wait=true&thread_id=7")
+    );
+    assert_eq!(requests[1].json()["content"], "hi");
+    assert_eq!(requests[1].json()["username"], "Backups");
+}
+
+#[tokio::test]
+async fn message_urls_carry_the_thread_id() {
+    let server = json_for(ROUTES).await;
+    let webhook = webhook(&server);
+
+    webhook.edit_message("9", "edited", None).await.unwrap();
+    webhook
+        .edit_message("9", "edited", Some("7"))
+        .await
+        .unwrap();
+    webhook.fetch_message("@original", None).await.unwrap();
+    webhook.delete_message("9", None).await.unwrap();
+    webhook.delete_message("9", Some("7")).await.unwrap();
+
+    let requests: Vec<_> = server
+        .requests()
+        .into_iter()
+        .map(|request| format!("{} {}", request.method, request.path))
+        .collect();
+
+    assert_eq!(
+        requests,
+        [
+            format!("PATCH webhooks/123/{TOKEN}/messages/9"),
+            format!("PATCH webhooks/123/{TOKEN}/messages/9?thread_id=7"),
+            format!("GET webhooks/123/{TOKEN}/messages/@original"),
+            format!("DELETE webhooks/123/{TOKEN}/messages/9"),
+            format!("DELETE webhooks/123/{TOKEN}/messages/9?thread_id=7"),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn routes_never_contain_the_token() {
+    let server = json_for(ROUTES).await;
+    let routes = Arc::new(Mutex::new(Vec::new()));
+    let client = Client::builder("TOKEN", 0u32, "!")
+        .api_url(server.url())
+        .default_help(false)
+        .rest_observer(Recorder(Arc::clone(&routes)))
+        .build()
+        .unwrap();
+
+    client
+        .http()
+        .scope(async {
+            let message = ExecuteWebhook::new("hi").wait(true).thread_id("7");
+            utils::execute_webhook("123", TOKEN, message).await.unwrap();
+            utils::edit_webhook_message("123", TOKEN, "9", "edited", Some("7"))
+                .await
+                .unwrap();
+            utils::delete_webhook_message("123", TOKEN, "9", None)
+                .await
+                .unwrap();
+            utils::delete_webhook_with_token("123", TOKEN)
+                .await
+                .unwrap();
+        })
+        .await;
+
+    assert_eq!(
+        *routes.lock().unwrap(),
+        [
+            "POST webhooks/123/:token",
+            "PATCH webhooks/123/:token/messages/:id",
+            "DELETE webhooks/123/:token/messages/:id",
+            "DELETE webhooks/123/:token",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn debug_output_never_contains_the_token() {
+    let server = json_for(ROUTES).await;
+    let webhook = webhook(&server);
+    assert!(!format!("{webhook:?}").contains(TOKEN), "{webhook:?}");
+
+    let model = Webhook::deserialize_json(&format!(
+        r#"{{"id":"123","token":"{TOKEN}","url":"https://discord.com/api/webhooks/123/{TOKEN}"}}"#
+    ))
+    .unwrap();
+    let debug = format!("{model:?}");
+    assert!(!debug.contains(TOKEN), "{debug}");
+    assert!(debug.contains("webhooks/123/:token"), "{debug}");
+}